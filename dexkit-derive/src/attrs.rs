@@ -0,0 +1,133 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Attribute, DeriveInput, Lifetime, LitStr};
+
+/// The impl generics of a derived type. Dex data borrows the bridge, so the type may declare
+/// one lifetime, used as the lifetime of the dexkit traits, or none and a new one is added.
+pub(crate) struct DexGenerics {
+    pub(crate) lifetime: Lifetime,
+    pub(crate) impl_generics: TokenStream,
+    pub(crate) ty_generics: TokenStream,
+    pub(crate) where_clause: TokenStream,
+}
+
+impl DexGenerics {
+    pub(crate) fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let generics = &input.generics;
+        if let Some(param) = generics.type_params().next() {
+            return Err(syn::Error::new_spanned(
+                param,
+                "type parameters are not supported",
+            ));
+        }
+        if let Some(param) = generics.const_params().next() {
+            return Err(syn::Error::new_spanned(
+                param,
+                "const parameters are not supported",
+            ));
+        }
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let where_clause = quote!(#where_clause);
+        let lifetimes: Vec<_> = generics.lifetimes().collect();
+        match lifetimes[..] {
+            [] => {
+                let lifetime = Lifetime::new("'__dex", Span::call_site());
+                Ok(Self {
+                    impl_generics: quote!(<#lifetime>),
+                    lifetime,
+                    ty_generics: TokenStream::new(),
+                    where_clause,
+                })
+            }
+            [param] => Ok(Self {
+                lifetime: param.lifetime.clone(),
+                impl_generics: quote!(#impl_generics),
+                ty_generics: quote!(#ty_generics),
+                where_clause,
+            }),
+            [_, second, ..] => Err(syn::Error::new_spanned(
+                second,
+                "at most one lifetime is supported",
+            )),
+        }
+    }
+}
+
+/// The `#[dex(...)]` attributes of a struct, enum, field or variant.
+#[derive(Default)]
+pub(crate) struct DexAttrs {
+    /// `annotation = "com.example.Route"`, the annotation type of a struct
+    pub(crate) annotation: Option<LitStr>,
+    /// `name = "value"`, the element or enum constant name
+    pub(crate) name: Option<LitStr>,
+    /// `default`, use `Default::default()` when the element is missing
+    pub(crate) default: bool,
+}
+
+impl DexAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut dex_attrs = DexAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("dex")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("annotation") {
+                    dex_attrs.annotation = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("name") {
+                    dex_attrs.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    dex_attrs.default = true;
+                } else {
+                    return Err(meta.error("expected `annotation`, `name` or `default`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(dex_attrs)
+    }
+}
+
+/// "com.example.Route" or "Lcom/example/Route;" to "Lcom/example/Route;"
+pub(crate) fn to_descriptor(name: &str) -> String {
+    match name.starts_with('L') && name.ends_with(';') {
+        true => name.to_string(),
+        false => format!("L{};", name.replace('.', "/")),
+    }
+}
+
+/// "LocalVariable" to "LOCAL_VARIABLE", the naming of Java enum constants
+pub(crate) fn to_upper_snake(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_uppercase());
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_descriptor() {
+        assert_eq!(to_descriptor("com.example.Route"), "Lcom/example/Route;");
+        assert_eq!(to_descriptor("Lcom/example/Route;"), "Lcom/example/Route;");
+        assert_eq!(to_descriptor("a.b$C"), "La/b$C;");
+    }
+
+    #[test]
+    fn test_to_upper_snake() {
+        assert_eq!(to_upper_snake("Get"), "GET");
+        assert_eq!(to_upper_snake("LocalVariable"), "LOCAL_VARIABLE");
+        assert_eq!(to_upper_snake("GetURL"), "GET_URL");
+        assert_eq!(to_upper_snake("HTTPServer"), "HTTP_SERVER");
+        assert_eq!(to_upper_snake("Http2Get"), "HTTP2_GET");
+        assert_eq!(to_upper_snake("RED"), "RED");
+    }
+}
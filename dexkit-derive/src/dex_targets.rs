@@ -0,0 +1,496 @@
+use crate::attrs::DexGenerics;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Class,
+    Method,
+    Field,
+}
+
+impl Kind {
+    fn of(attr: &Attribute) -> Option<Self> {
+        let path = attr.path();
+        if path.is_ident("class") {
+            Some(Kind::Class)
+        } else if path.is_ident("method") {
+            Some(Kind::Method)
+        } else if path.is_ident("field") {
+            Some(Kind::Field)
+        } else {
+            None
+        }
+    }
+
+    /// the accepted keys, (key, repeatable, is a type condition)
+    fn keys(self) -> &'static [(&'static str, bool, bool)] {
+        match self {
+            Kind::Class => &[
+                ("name", false, false),
+                ("using_string", true, false),
+                ("super_class", false, true),
+                ("implements", true, true),
+                ("source", false, false),
+            ],
+            Kind::Method => &[
+                ("name", false, false),
+                ("using_string", true, false),
+                ("returns", false, true),
+                ("params", false, true),
+                ("in_class", false, true),
+            ],
+            Kind::Field => &[
+                ("name", false, false),
+                ("type", false, true),
+                ("in_class", false, true),
+            ],
+        }
+    }
+}
+
+/// How many matches a target takes, from its field type.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Cardinality {
+    One,
+    Optional,
+    All,
+}
+
+impl Cardinality {
+    fn of(ty: &Type) -> Self {
+        let last = match ty {
+            Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+            _ => None,
+        };
+        match last {
+            Some(segment) if segment.ident == "Option" => Cardinality::Optional,
+            Some(segment) if segment.ident == "Vec" => Cardinality::All,
+            _ => Cardinality::One,
+        }
+    }
+}
+
+/// A type condition: a class name or `"self.<field>"`.
+enum TypeName {
+    Name(String),
+    Target(Ident),
+}
+
+impl TypeName {
+    fn parse(value: &str) -> Self {
+        match value.trim().strip_prefix("self.") {
+            Some(field) => TypeName::Target(format_ident!("{}", field)),
+            None => TypeName::Name(value.trim().to_string()),
+        }
+    }
+
+    /// the name as an expression of the `resolve` of `target`
+    fn tokens(&self, target: &str) -> TokenStream {
+        match self {
+            TypeName::Name(name) => quote!(#name),
+            TypeName::Target(field) => {
+                let local = local_ident(field);
+                quote!(::dexkit::query::__private::class_name(#target, &#local)?)
+            }
+        }
+    }
+}
+
+struct Target {
+    ident: Ident,
+    kind: Kind,
+    cardinality: Cardinality,
+    conditions: Vec<(String, LitStr)>,
+}
+
+impl Target {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.clone().unwrap();
+        let mut attrs = field
+            .attrs
+            .iter()
+            .filter_map(|attr| Some((Kind::of(attr)?, attr)));
+        let Some((kind, attr)) = attrs.next() else {
+            return Err(syn::Error::new_spanned(
+                &ident,
+                "expected one of #[class(...)], #[method(...)] or #[field(...)]",
+            ));
+        };
+        if let Some((_, extra)) = attrs.next() {
+            return Err(syn::Error::new_spanned(
+                extra,
+                "a field takes a single target",
+            ));
+        }
+        let mut conditions: Vec<(String, LitStr)> = Vec::new();
+        attr.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(|key| key.unraw().to_string());
+            let Some(&(key, repeatable, _)) = kind
+                .keys()
+                .iter()
+                .find(|(name, ..)| Some(*name) == key.as_deref())
+            else {
+                let keys: Vec<_> = kind.keys().iter().map(|(name, ..)| *name).collect();
+                return Err(meta.error(format!("expected one of {}", keys.join(", "))));
+            };
+            if !repeatable && conditions.iter().any(|(k, _)| k == key) {
+                return Err(meta.error(format!("duplicate `{}`", key)));
+            }
+            conditions.push((key.to_string(), meta.value()?.parse()?));
+            Ok(())
+        })?;
+        Ok(Target {
+            ident,
+            kind,
+            cardinality: Cardinality::of(&field.ty),
+            conditions,
+        })
+    }
+
+    /// the class targets this target refers to with `"self.<field>"`
+    fn dependencies(&self) -> Vec<(Ident, &LitStr)> {
+        self.type_conditions()
+            .flat_map(|(_, value)| {
+                type_names(&value.value())
+                    .into_iter()
+                    .filter_map(move |name| match name {
+                        TypeName::Target(field) => Some((field, value)),
+                        TypeName::Name(_) => None,
+                    })
+            })
+            .collect()
+    }
+
+    fn type_conditions(&self) -> impl Iterator<Item = &(String, LitStr)> {
+        self.conditions.iter().filter(|(key, _)| {
+            self.kind
+                .keys()
+                .iter()
+                .any(|(name, _, is_type)| name == key && *is_type)
+        })
+    }
+
+    /// the block running the query of the target
+    fn resolve(&self) -> TokenStream {
+        let mut matcher = Vec::new();
+        let mut query = Vec::new();
+        let mut using_strings = Vec::new();
+        let target = self.ident.unraw().to_string();
+        for (key, value) in &self.conditions {
+            let type_name = TypeName::parse(&value.value());
+            let type_tokens = type_name.tokens(&target);
+            match (self.kind, key.as_str()) {
+                (_, "using_string") => using_strings.push(value),
+                (Kind::Class, "name") => matcher.push(quote!(.set_class_name_str(#value))),
+                (Kind::Class, "super_class") => {
+                    matcher.push(quote!(.set_super_class_name_str(#type_tokens)))
+                }
+                (Kind::Class, "implements") => {
+                    matcher.push(quote!(.add_interfaces_str(#type_tokens)))
+                }
+                (Kind::Class, "source") => matcher.push(quote! {
+                    .set_source_matcher(
+                        ::dexkit::query::matchers::base::StringMatcher::create_eq_string_str(#value),
+                    )
+                }),
+                (Kind::Method, "name") => matcher.push(quote!(.set_eq_method_name_str(#value))),
+                (Kind::Method, "returns") => matcher.push(quote! {
+                    .set_return_type_matcher(
+                        ::dexkit::query::matchers::ClassMatcher::create()
+                            .set_class_name_str(#type_tokens),
+                    )
+                }),
+                (Kind::Method, "params") => {
+                    let params = type_names(&value.value());
+                    let count = params.len() as u32;
+                    let params = params.into_iter().map(|param| {
+                        let param = param.tokens(&target);
+                        quote! {
+                            .add_param_matcher(::core::option::Option::Some(
+                                ::dexkit::query::matchers::ParameterMatcher::create().set_type_matcher(
+                                    ::dexkit::query::matchers::ClassMatcher::create()
+                                        .set_class_name_str(#param),
+                                ),
+                            ))
+                        }
+                    });
+                    matcher.push(quote! {
+                        .set_params_matcher(
+                            ::dexkit::query::matchers::ParametersMatcher::create()
+                                .count(#count)
+                                #(#params)*
+                        )
+                    });
+                }
+                (Kind::Field, "name") => matcher.push(quote! {
+                    .set_field_name_matcher(
+                        ::dexkit::query::matchers::base::StringMatcher::create_eq_string_str(#value),
+                    )
+                }),
+                (Kind::Field, "type") => matcher.push(quote!(.set_type_name_str(#type_tokens))),
+                (Kind::Method | Kind::Field, "in_class") => match &type_name {
+                    TypeName::Target(field) => {
+                        let local = local_ident(field);
+                        query.push(quote!(.add_search_class(::core::clone::Clone::clone(&#local))));
+                    }
+                    TypeName::Name(name) => matcher.push(match self.kind {
+                        Kind::Method => quote! {
+                            .set_class_matcher(
+                                ::dexkit::query::matchers::ClassMatcher::create()
+                                    .set_class_name_str(#name),
+                            )
+                        },
+                        _ => quote!(.set_class_name_str(#name)),
+                    }),
+                },
+                _ => unreachable!("key checked by Target::parse"),
+            }
+        }
+        if !using_strings.is_empty() {
+            matcher.push(match self.kind {
+                Kind::Method => quote! {
+                    .set_using_strings_matcher(::std::vec![
+                        #(::dexkit::query::matchers::base::StringMatcher::create_string_str(#using_strings),)*
+                    ])
+                },
+                _ => quote!(#(.add_using_string_str(#using_strings))*),
+            });
+        }
+
+        let (matcher_type, query_type, find) = match self.kind {
+            Kind::Class => (quote!(ClassMatcher), quote!(FindClass), quote!(find_class)),
+            Kind::Method => (
+                quote!(MethodMatcher),
+                quote!(FindMethod),
+                quote!(find_method),
+            ),
+            Kind::Field => (quote!(FieldMatcher), quote!(FindField), quote!(find_field)),
+        };
+        let take = match self.cardinality {
+            Cardinality::One => quote!(unique),
+            Cardinality::Optional => quote!(optional),
+            Cardinality::All => quote!(all),
+        };
+        quote! {
+            {
+                let matcher = ::dexkit::query::matchers::#matcher_type::create()
+                    #(#matcher)*;
+                let query = ::dexkit::query::__private::query(
+                    #target,
+                    ::dexkit::query::#query_type::create()
+                        #(#query)*
+                        .try_set_matcher(matcher),
+                )?;
+                ::dexkit::query::__private::#take(
+                    #target,
+                    ::core::convert::Into::into(bridge.#find(query)),
+                )?
+            }
+        }
+    }
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let DexGenerics {
+        lifetime,
+        impl_generics,
+        ty_generics,
+        where_clause,
+    } = DexGenerics::parse(&input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            fields => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "expected a struct with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "expected a struct with named fields",
+            ));
+        }
+    };
+    let targets = fields
+        .iter()
+        .map(Target::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let order = resolve_order(&targets)?;
+
+    let steps = order.iter().map(|&i| {
+        let target = &targets[i];
+        let local = local_ident(&target.ident);
+        let resolve = target.resolve();
+        quote!(let #local = #resolve;)
+    });
+    let inits = targets.iter().map(|target| {
+        let ident = &target.ident;
+        let local = local_ident(ident);
+        quote!(#ident: #local)
+    });
+    let ident = &input.ident;
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// run the query of every target, see `dexkit::query::DexTargets`
+            pub fn resolve(
+                bridge: &#lifetime ::dexkit::DexkitBridge,
+            ) -> ::core::result::Result<Self, ::dexkit::errors::ResolveError> {
+                #(#steps)*
+                ::core::result::Result::Ok(Self { #(#inits,)* })
+            }
+        }
+    })
+}
+
+/// the targets in an order resolving `"self.<field>"` references first, declaration order
+/// otherwise. References must name a required class target
+fn resolve_order(targets: &[Target]) -> syn::Result<Vec<usize>> {
+    let mut dependencies = Vec::new();
+    for target in targets {
+        let mut indexes = Vec::new();
+        for (field, value) in target.dependencies() {
+            let Some(index) = targets.iter().position(|t| t.ident == field) else {
+                return Err(syn::Error::new_spanned(
+                    value,
+                    format!("no target named `{}`", field),
+                ));
+            };
+            let dependency = &targets[index];
+            if dependency.kind != Kind::Class || dependency.cardinality != Cardinality::One {
+                return Err(syn::Error::new_spanned(
+                    value,
+                    format!("`{}` is not a required class target", field),
+                ));
+            }
+            indexes.push(index);
+        }
+        dependencies.push(indexes);
+    }
+
+    let mut order: Vec<usize> = Vec::new();
+    while order.len() < targets.len() {
+        let next = (0..targets.len())
+            .find(|i| !order.contains(i) && dependencies[*i].iter().all(|d| order.contains(d)));
+        match next {
+            Some(next) => order.push(next),
+            None => {
+                let cycle = (0..targets.len()).find(|i| !order.contains(i)).unwrap();
+                return Err(syn::Error::new_spanned(
+                    &targets[cycle].ident,
+                    "targets refer to each other in a cycle",
+                ));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// "a, self.b" to the type names of a comma separated list, empty for ""
+fn type_names(value: &str) -> Vec<TypeName> {
+    value
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(TypeName::parse)
+        .collect()
+}
+
+/// the local variable holding a resolved target in `resolve`
+fn local_ident(field: &Ident) -> Ident {
+    format_ident!("__{}", field.unraw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_expand() {
+        let input: DeriveInput = parse_quote! {
+            struct LoginTargets<'a> {
+                #[method(using_string = "login", returns = "void", in_class = "self.login_manager")]
+                login: MethodData<'a>,
+                #[class(name = "com.example.LoginManager")]
+                login_manager: ClassData<'a>,
+                #[field(type = "self.login_manager")]
+                instances: Vec<FieldData<'a>>,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        // login_manager is resolved before the targets referring to it
+        let manager = expanded.find("let __login_manager =").unwrap();
+        let login = expanded.find("let __login =").unwrap();
+        assert!(login > manager);
+        assert!(
+            expanded.contains(
+                "add_search_class (:: core :: clone :: Clone :: clone (& __login_manager))"
+            )
+        );
+        assert!(expanded.contains(
+            "set_type_name_str (:: dexkit :: query :: __private :: class_name (\"instances\" , & __login_manager) ?)"
+        ));
+        assert!(expanded.contains(":: dexkit :: query :: __private :: unique (\"login\""));
+        assert!(expanded.contains(":: dexkit :: query :: __private :: all (\"instances\""));
+        // a rejected matcher is reported as an invalid query of its target
+        assert!(expanded.contains(". try_set_matcher (matcher)"));
+    }
+
+    #[test]
+    fn test_expand_source() {
+        let input: DeriveInput = parse_quote! {
+            struct T<'a> {
+                #[class(source = "Login.java")]
+                login: ClassData<'a>,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        assert!(expanded.contains(
+            "set_source_matcher (:: dexkit :: query :: matchers :: base :: StringMatcher :: create_eq_string_str (\"Login.java\") ,)"
+        ));
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let unknown: DeriveInput = parse_quote! {
+            struct T<'a> {
+                #[method(in_class = "self.missing")]
+                m: MethodData<'a>,
+            }
+        };
+        assert!(expand(unknown).is_err());
+
+        let cycle: DeriveInput = parse_quote! {
+            struct T<'a> {
+                #[class(super_class = "self.b")]
+                a: ClassData<'a>,
+                #[class(implements = "self.a")]
+                b: ClassData<'a>,
+            }
+        };
+        assert!(expand(cycle).is_err());
+
+        let optional: DeriveInput = parse_quote! {
+            struct T<'a> {
+                #[class(name = "a.B")]
+                a: Option<ClassData<'a>>,
+                #[method(in_class = "self.a")]
+                m: MethodData<'a>,
+            }
+        };
+        assert!(expand(optional).is_err());
+
+        let bad_key: DeriveInput = parse_quote! {
+            struct T<'a> {
+                #[field(returns = "int")]
+                f: FieldData<'a>,
+            }
+        };
+        assert!(expand(bad_key).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::attrs::{DexAttrs, DexGenerics, to_descriptor, to_upper_snake};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DataEnum, DeriveInput, Fields, Type};
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let generics = DexGenerics::parse(&input)?;
+    let attrs = DexAttrs::parse(&input.attrs)?;
+    match &input.data {
+        Data::Struct(data) => expand_struct(&input, &generics, &attrs, &data.fields),
+        Data::Enum(data) => {
+            if let Some(annotation) = &attrs.annotation {
+                return Err(syn::Error::new_spanned(
+                    annotation,
+                    "enums are read from enum constants, not annotations",
+                ));
+            }
+            expand_enum(&input, &generics, data)
+        }
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "unions are not supported",
+        )),
+    }
+}
+
+fn expand_struct(
+    input: &DeriveInput,
+    generics: &DexGenerics,
+    attrs: &DexAttrs,
+    fields: &Fields,
+) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let DexGenerics {
+        lifetime,
+        impl_generics,
+        ty_generics,
+        where_clause,
+    } = generics;
+    let descriptor = match &attrs.annotation {
+        Some(annotation) => {
+            let descriptor = to_descriptor(&annotation.value());
+            quote!(::core::option::Option::Some(#descriptor))
+        }
+        None => quote!(::core::option::Option::None),
+    };
+    let body = match fields {
+        Fields::Named(fields) => {
+            let reads = fields
+                .named
+                .iter()
+                .map(|field| {
+                    let attrs = DexAttrs::parse(&field.attrs)?;
+                    let ident = field.ident.as_ref().unwrap();
+                    let name = match attrs.name {
+                        Some(name) => name.value(),
+                        None => ident.to_string().trim_start_matches("r#").to_string(),
+                    };
+                    let read = if is_option(&field.ty) {
+                        quote!(annotation.optional_element(#name)?)
+                    } else if attrs.default {
+                        quote!(annotation.optional_element(#name)?.unwrap_or_default())
+                    } else {
+                        quote!(annotation.element(#name)?)
+                    };
+                    Ok(quote!(#ident: #read))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote!(Self { #(#reads,)* })
+        }
+        Fields::Unit => quote!(Self),
+        Fields::Unnamed(fields) => {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "tuple structs are not supported, elements are read by field name",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::dexkit::result::FromDexAnnotation<#lifetime> for #ident #ty_generics
+        #where_clause
+        {
+            fn annotation_descriptor() -> ::core::option::Option<&'static str> {
+                #descriptor
+            }
+
+            fn from_elements(
+                annotation: &::dexkit::result::AnnotationData<#lifetime>,
+            ) -> ::core::result::Result<Self, ::dexkit::errors::FromAnnotationError> {
+                ::core::result::Result::Ok(#body)
+            }
+        }
+
+        impl #impl_generics ::dexkit::result::FromEncodeValue<#lifetime> for #ident #ty_generics
+        #where_clause
+        {
+            fn from_encode_value(
+                value: &::dexkit::result::AnnotationEncodeValue<#lifetime>,
+            ) -> ::core::result::Result<Self, ::dexkit::errors::FromAnnotationError> {
+                ::dexkit::result::__private::nested_annotation(value)
+            }
+        }
+    })
+}
+
+fn expand_enum(
+    input: &DeriveInput,
+    generics: &DexGenerics,
+    data: &DataEnum,
+) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let DexGenerics {
+        lifetime,
+        impl_generics,
+        ty_generics,
+        where_clause,
+    } = generics;
+    let mut all_names: Vec<String> = Vec::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "only unit variants can be read from enum constants",
+            ));
+        }
+        let attrs = DexAttrs::parse(&variant.attrs)?;
+        let mut names = match attrs.name {
+            Some(name) => vec![name.value()],
+            None => vec![
+                variant.ident.to_string(),
+                to_upper_snake(&variant.ident.to_string()),
+            ],
+        };
+        names.dedup();
+        if let Some(name) = names.iter().find(|name| all_names.contains(name)) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("enum constant `{}` is matched by another variant", name),
+            ));
+        }
+        all_names.extend(names.iter().cloned());
+        let variant_ident = &variant.ident;
+        arms.push(quote!(#(#names)|* => ::core::result::Result::Ok(Self::#variant_ident)));
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::dexkit::result::FromEncodeValue<#lifetime> for #ident #ty_generics
+        #where_clause
+        {
+            fn from_encode_value(
+                value: &::dexkit::result::AnnotationEncodeValue<#lifetime>,
+            ) -> ::core::result::Result<Self, ::dexkit::errors::FromAnnotationError> {
+                let name = ::dexkit::result::__private::enum_constant_name(value)?;
+                match name.as_str() {
+                    #(#arms,)*
+                    _ => ::core::result::Result::Err(
+                        ::dexkit::errors::FromAnnotationError::UnknownEnumConstant(name),
+                    ),
+                }
+            }
+        }
+    })
+}
+
+/// the field type is written `Option<..>`, a missing element reads as None
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_expand_struct() {
+        let input: DeriveInput = parse_quote! {
+            #[dex(annotation = "com.example.Route")]
+            struct Route<'a> {
+                path: String,
+                #[dex(name = "methods")]
+                http_methods: Vec<HttpMethod>,
+                handler: Option<ClassData<'a>>,
+                #[dex(default)]
+                priority: i32,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        assert!(expanded.contains("FromDexAnnotation < 'a > for Route < 'a >"));
+        assert!(expanded.contains("Some (\"Lcom/example/Route;\")"));
+        assert!(expanded.contains("http_methods : annotation . element (\"methods\") ?"));
+        assert!(expanded.contains("handler : annotation . optional_element (\"handler\") ?"));
+        assert!(expanded.contains("optional_element (\"priority\") ? . unwrap_or_default ()"));
+
+        let input: DeriveInput = parse_quote!(
+            struct Pair(String, String);
+        );
+        assert!(expand(input).is_err());
+    }
+
+    #[test]
+    fn test_expand_enum() {
+        let input: DeriveInput = parse_quote! {
+            enum HttpMethod {
+                Get,
+                #[dex(name = "DELETE")]
+                Remove,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        assert!(expanded.contains("FromEncodeValue < '__dex > for HttpMethod"));
+        assert!(expanded.contains("\"Get\" | \"GET\" =>"));
+        assert!(expanded.contains("\"DELETE\" =>"));
+
+        let input: DeriveInput = parse_quote!(
+            enum Dup {
+                Red,
+                #[dex(name = "Red")]
+                Other,
+            }
+        );
+        assert!(expand(input).is_err());
+    }
+}
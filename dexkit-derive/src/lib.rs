@@ -0,0 +1,26 @@
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+mod attrs;
+mod dex_targets;
+mod from_dex_annotation;
+
+/// Read an annotation into a struct, or an enum constant into a unit enum,
+/// see `dexkit::result::FromDexAnnotation`.
+#[proc_macro_derive(FromDexAnnotation, attributes(dex))]
+pub fn derive_from_dex_annotation(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_dex_annotation::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Resolve a struct of hook targets, generates `resolve(&DexkitBridge) -> Result<Self, ResolveError>`,
+/// see `dexkit::query::DexTargets`.
+#[proc_macro_derive(DexTargets, attributes(class, method, field))]
+pub fn derive_dex_targets(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    dex_targets::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
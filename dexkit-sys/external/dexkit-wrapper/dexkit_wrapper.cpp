@@ -9,6 +9,63 @@ const T *From(const void *buf)
 using dexkit::DexKit;
 using dexkit::Error;
 
+// Called back for each candidate of a filtered search with the whole result buffer
+// and the position of the candidate in it, returns whether the candidate is kept.
+typedef bool (*dexkit_filter_fn)(void *ctx, const void *buf, size_t len, uint32_t index);
+
+// Run `filter` over the candidates of a native search result, keeping at most `limit` of them.
+// The result buffer is copied to `out_buf` and the kept positions to `out_indexes`,
+// both are null when no candidate is kept.
+template <typename Holder, typename Result, typename Items>
+void filter_result(Result &result, Items items, dexkit_filter_fn filter, void *ctx, size_t limit,
+                   void **out_buf, size_t *out_len, uint32_t **out_indexes, size_t *out_index_len)
+{
+    *out_buf = nullptr;
+    *out_len = 0;
+    *out_indexes = nullptr;
+    *out_index_len = 0;
+    if (result == nullptr)
+        return;
+
+    auto ptr = result->GetBufferPointer();
+    size_t size = result->GetSize();
+    flatbuffers::Verifier verifier(ptr, size);
+    auto holder = size > 0 && verifier.VerifyBuffer<Holder>(nullptr) ? From<Holder>(ptr) : nullptr;
+    auto candidates = holder != nullptr ? items(holder) : nullptr;
+    std::vector<uint32_t> kept;
+    if (candidates != nullptr)
+    {
+        for (uint32_t i = 0; i < candidates->size() && kept.size() < limit; ++i)
+        {
+            if (filter(ctx, ptr, size, i))
+                kept.push_back(i);
+        }
+    }
+
+    if (!kept.empty())
+    {
+        void *buf = malloc(size);
+        auto indexes = static_cast<uint32_t *>(malloc(kept.size() * sizeof(uint32_t)));
+        if (buf != nullptr && indexes != nullptr)
+        {
+            memcpy(buf, ptr, size);
+            memcpy(indexes, kept.data(), kept.size() * sizeof(uint32_t));
+            *out_buf = buf;
+            *out_len = size;
+            *out_indexes = indexes;
+            *out_index_len = kept.size();
+        }
+        else
+        {
+            free(buf);
+            free(indexes);
+        }
+    }
+
+    // Release the FlatBufferBuilder to free its internal memory
+    result->Release();
+}
+
 extern "C"
 {
     void *dexkit_new()
@@ -110,6 +167,17 @@ extern "C"
         *out_buf = nullptr;
     }
 
+    void dexkit_find_class_filtered(void *handle, void *buffer, dexkit_filter_fn filter, void *ctx, size_t limit,
+                                    void **out_buf, size_t *out_len, uint32_t **out_indexes, size_t *out_index_len)
+    {
+        auto dexkit = static_cast<DexKit *>(handle);
+        auto query = From<dexkit::schema::FindClass>(buffer);
+        auto result = dexkit->FindClass(query);
+        filter_result<dexkit::schema::ClassMetaArrayHolder>(
+            result, [](const dexkit::schema::ClassMetaArrayHolder *holder) { return holder->classes(); },
+            filter, ctx, limit, out_buf, out_len, out_indexes, out_index_len);
+    }
+
     void dexkit_find_method(void *handle, void *buffer, void **out_buf, size_t *out_len)
     {
         auto dexkit = static_cast<DexKit *>(handle);
@@ -147,6 +215,25 @@ extern "C"
         *out_buf = nullptr;
     }
 
+    void dexkit_find_method_filtered(void *handle, void *buffer, dexkit_filter_fn filter, void *ctx, size_t limit,
+                                     void **out_buf, size_t *out_len, uint32_t **out_indexes, size_t *out_index_len)
+    {
+        auto dexkit = static_cast<DexKit *>(handle);
+        auto query = From<dexkit::schema::FindMethod>(buffer);
+        auto result = dexkit->FindMethod(query);
+        filter_result<dexkit::schema::MethodMetaArrayHolder>(
+            result, [](const dexkit::schema::MethodMetaArrayHolder *holder) { return holder->methods(); },
+            filter, ctx, limit, out_buf, out_len, out_indexes, out_index_len);
+    }
+
+    void dexkit_filtered_indexes_free(uint32_t **out_indexes, size_t out_index_len)
+    {
+        if (!out_indexes)
+            return;
+        free(*out_indexes);
+        *out_indexes = nullptr;
+    }
+
     void dexkit_find_field(void *handle, void *buffer, void **out_buf, size_t *out_len)
     {
         auto dexkit = static_cast<DexKit *>(handle);
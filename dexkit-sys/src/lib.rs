@@ -2,6 +2,11 @@ use std::ffi::{c_char, c_int, c_void};
 
 pub type DexkitHandle = *mut c_void;
 
+/// Called back by the filtered searches for each native candidate, with the whole result
+/// buffer and the position of the candidate in it. Returns whether the candidate is kept.
+pub type DexkitFilterFn =
+    unsafe extern "C" fn(ctx: *mut c_void, buf: *const c_void, len: usize, index: u32) -> bool;
+
 #[link(name = "dexkit_wrapper", kind = "static")]
 unsafe extern "C" {
     pub fn dexkit_new() -> DexkitHandle;
@@ -30,6 +35,18 @@ unsafe extern "C" {
     );
     pub fn dexkit_find_class_free(out_buf: *mut *mut c_void, len: usize);
 
+    pub fn dexkit_find_class_filtered(
+        handle: *mut c_void,
+        buffer: *mut c_void,
+        filter: DexkitFilterFn,
+        ctx: *mut c_void,
+        limit: usize,
+        out_buf: *mut *mut c_void,
+        out_len: *mut usize,
+        out_indexes: *mut *mut u32,
+        out_index_len: *mut usize,
+    );
+
     pub fn dexkit_find_method(
         handle: *mut c_void,
         buffer: *mut c_void,
@@ -38,6 +55,20 @@ unsafe extern "C" {
     );
     pub fn dexkit_find_method_free(out_buf: *mut *mut c_void, len: usize);
 
+    pub fn dexkit_find_method_filtered(
+        handle: *mut c_void,
+        buffer: *mut c_void,
+        filter: DexkitFilterFn,
+        ctx: *mut c_void,
+        limit: usize,
+        out_buf: *mut *mut c_void,
+        out_len: *mut usize,
+        out_indexes: *mut *mut u32,
+        out_index_len: *mut usize,
+    );
+
+    pub fn dexkit_filtered_indexes_free(out_indexes: *mut *mut u32, len: usize);
+
     pub fn dexkit_find_field(
         handle: *mut c_void,
         buffer: *mut c_void,
@@ -0,0 +1,450 @@
+use crate::DexkitBridge;
+use crate::apk::{ResourceValue, XmlElement};
+use crate::errors::Error;
+use crate::result::ClassData;
+
+// android attribute resource ids, see frameworks/base/core/res/res/values/public.xml
+const ATTR_NAME: u32 = 0x0101_0003;
+const ATTR_PERMISSION: u32 = 0x0101_0006;
+const ATTR_ENABLED: u32 = 0x0101_000e;
+const ATTR_EXPORTED: u32 = 0x0101_0010;
+const ATTR_PROCESS: u32 = 0x0101_0011;
+const ATTR_AUTHORITIES: u32 = 0x0101_0018;
+const ATTR_PRIORITY: u32 = 0x0101_001c;
+const ATTR_MIME_TYPE: u32 = 0x0101_0026;
+const ATTR_SCHEME: u32 = 0x0101_0027;
+const ATTR_HOST: u32 = 0x0101_0028;
+const ATTR_PORT: u32 = 0x0101_0029;
+const ATTR_PATH: u32 = 0x0101_002a;
+const ATTR_PATH_PREFIX: u32 = 0x0101_002b;
+const ATTR_PATH_PATTERN: u32 = 0x0101_002c;
+const ATTR_TARGET_ACTIVITY: u32 = 0x0101_0202;
+const ATTR_VERSION_CODE: u32 = 0x0101_021b;
+const ATTR_VERSION_NAME: u32 = 0x0101_021c;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentType {
+    Activity,
+    ActivityAlias,
+    Service,
+    Receiver,
+    Provider,
+}
+
+/// A `<data>` element of an intent filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntentFilterData {
+    pub scheme: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub path: Option<String>,
+    pub path_prefix: Option<String>,
+    pub path_pattern: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+/// An `<intent-filter>` of a component.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntentFilter {
+    pub actions: Vec<String>,
+    pub categories: Vec<String>,
+    pub data: Vec<IntentFilterData>,
+    pub priority: Option<i32>,
+}
+
+/// An activity, activity-alias, service, receiver or provider declared in the manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestComponent {
+    component_type: ComponentType,
+    name: String,
+    target_activity: Option<String>,
+    exported: Option<bool>,
+    enabled: Option<bool>,
+    permission: Option<String>,
+    process: Option<String>,
+    authorities: Vec<String>,
+    intent_filters: Vec<IntentFilter>,
+}
+
+impl ManifestComponent {
+    pub fn component_type(&self) -> ComponentType {
+        self.component_type
+    }
+
+    /// fully qualified class name, e.g. "com.example.MainActivity"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// fully qualified class name of the activity an activity-alias points to
+    pub fn target_activity(&self) -> Option<&str> {
+        self.target_activity.as_deref()
+    }
+
+    /// `android:exported`, `None` if not declared
+    pub fn exported(&self) -> Option<bool> {
+        self.exported
+    }
+
+    /// `android:enabled`, `None` if not declared
+    pub fn enabled(&self) -> Option<bool> {
+        self.enabled
+    }
+
+    pub fn permission(&self) -> Option<&str> {
+        self.permission.as_deref()
+    }
+
+    pub fn process(&self) -> Option<&str> {
+        self.process.as_deref()
+    }
+
+    /// content provider authorities, empty for the other components
+    pub fn authorities(&self) -> &[String] {
+        &self.authorities
+    }
+
+    pub fn intent_filters(&self) -> &[IntentFilter] {
+        &self.intent_filters
+    }
+
+    /// Get the class implementing the component, the target activity for an activity-alias.
+    /// # Returns
+    /// * `None` - If the class is not defined in the loaded dex files
+    pub fn class_data<'a>(&self, bridge: &'a DexkitBridge) -> Option<ClassData<'a>> {
+        bridge.get_class_data(self.target_activity.as_deref().unwrap_or(&self.name))
+    }
+}
+
+/// Content of the binary AndroidManifest.xml of an apk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AndroidManifest {
+    package_name: String,
+    version_code: Option<i64>,
+    version_name: Option<String>,
+    application_name: Option<String>,
+    uses_permissions: Vec<String>,
+    permissions: Vec<String>,
+    activities: Vec<ManifestComponent>,
+    services: Vec<ManifestComponent>,
+    receivers: Vec<ManifestComponent>,
+    providers: Vec<ManifestComponent>,
+}
+
+impl AndroidManifest {
+    /// Parse the content of a binary AndroidManifest.xml file.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        let root = XmlElement::parse(data)?;
+        if root.name != "manifest" {
+            return Err(Error::ResourceError(format!(
+                "unexpected manifest root element <{}>",
+                root.name
+            )));
+        }
+        // `package` is not an android attribute, it has no resource id
+        let package_name = root
+            .attributes
+            .iter()
+            .find(|attr| attr.name == "package")
+            .and_then(|attr| {
+                attr.raw_value
+                    .clone()
+                    .or_else(|| attr.value.as_str().map(String::from))
+            })
+            .unwrap_or_default();
+
+        let mut manifest = Self {
+            version_code: root
+                .attribute(ATTR_VERSION_CODE, "versionCode")
+                .and_then(|attr| match attr.value {
+                    ResourceValue::Int(value) => Some(value as i64),
+                    _ => attr.raw_value.as_deref()?.parse().ok(),
+                }),
+            version_name: root.string_attribute(ATTR_VERSION_NAME, "versionName"),
+            package_name,
+            ..Self::default()
+        };
+        for element in &root.children {
+            match element.name.as_str() {
+                "uses-permission" | "uses-permission-sdk-23" | "uses-permission-sdk-m" => {
+                    if let Some(name) = element.string_attribute(ATTR_NAME, "name") {
+                        manifest.uses_permissions.push(name);
+                    }
+                }
+                "permission" => {
+                    if let Some(name) = element.string_attribute(ATTR_NAME, "name") {
+                        manifest.permissions.push(name);
+                    }
+                }
+                "application" => manifest.parse_application(element),
+                _ => {}
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// package name, e.g. "com.example"
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    /// `android:versionCode`
+    pub fn version_code(&self) -> Option<i64> {
+        self.version_code
+    }
+
+    /// `android:versionName`, `None` if it is not declared or is a resource reference
+    pub fn version_name(&self) -> Option<&str> {
+        self.version_name.as_deref()
+    }
+
+    /// fully qualified class name of the custom `Application`
+    pub fn application_name(&self) -> Option<&str> {
+        self.application_name.as_deref()
+    }
+
+    /// permissions requested with `<uses-permission>`
+    pub fn uses_permissions(&self) -> &[String] {
+        &self.uses_permissions
+    }
+
+    /// permissions defined with `<permission>`
+    pub fn permissions(&self) -> &[String] {
+        &self.permissions
+    }
+
+    /// activities and activity aliases
+    pub fn activities(&self) -> &[ManifestComponent] {
+        &self.activities
+    }
+
+    pub fn services(&self) -> &[ManifestComponent] {
+        &self.services
+    }
+
+    pub fn receivers(&self) -> &[ManifestComponent] {
+        &self.receivers
+    }
+
+    pub fn providers(&self) -> &[ManifestComponent] {
+        &self.providers
+    }
+
+    /// all components, in activities, services, receivers, providers order
+    pub fn components(&self) -> impl Iterator<Item = &ManifestComponent> {
+        self.activities
+            .iter()
+            .chain(&self.services)
+            .chain(&self.receivers)
+            .chain(&self.providers)
+    }
+
+    /// Get the custom `Application` class.
+    pub fn application_class_data<'a>(&self, bridge: &'a DexkitBridge) -> Option<ClassData<'a>> {
+        bridge.get_class_data(self.application_name.as_deref()?)
+    }
+
+    /// internal use
+    fn parse_application(&mut self, application: &XmlElement) {
+        self.application_name = application
+            .string_attribute(ATTR_NAME, "name")
+            .map(|name| self.class_name(&name));
+        for element in &application.children {
+            let component_type = match element.name.as_str() {
+                "activity" => ComponentType::Activity,
+                "activity-alias" => ComponentType::ActivityAlias,
+                "service" => ComponentType::Service,
+                "receiver" => ComponentType::Receiver,
+                "provider" => ComponentType::Provider,
+                _ => continue,
+            };
+            let Some(component) = self.parse_component(component_type, element) else {
+                continue;
+            };
+            match component_type {
+                ComponentType::Activity | ComponentType::ActivityAlias => {
+                    self.activities.push(component)
+                }
+                ComponentType::Service => self.services.push(component),
+                ComponentType::Receiver => self.receivers.push(component),
+                ComponentType::Provider => self.providers.push(component),
+            }
+        }
+    }
+
+    /// internal use, `None` if the component has no name
+    fn parse_component(
+        &self,
+        component_type: ComponentType,
+        element: &XmlElement,
+    ) -> Option<ManifestComponent> {
+        let name = element.string_attribute(ATTR_NAME, "name")?;
+        Some(ManifestComponent {
+            component_type,
+            name: self.class_name(&name),
+            target_activity: element
+                .string_attribute(ATTR_TARGET_ACTIVITY, "targetActivity")
+                .map(|name| self.class_name(&name)),
+            exported: Self::bool_attribute(element, ATTR_EXPORTED, "exported"),
+            enabled: Self::bool_attribute(element, ATTR_ENABLED, "enabled"),
+            permission: element.string_attribute(ATTR_PERMISSION, "permission"),
+            process: element.string_attribute(ATTR_PROCESS, "process"),
+            authorities: element
+                .string_attribute(ATTR_AUTHORITIES, "authorities")
+                .map(|authorities| authorities.split(';').map(String::from).collect())
+                .unwrap_or_default(),
+            intent_filters: element
+                .children_named("intent-filter")
+                .map(Self::parse_intent_filter)
+                .collect(),
+        })
+    }
+
+    /// internal use
+    fn parse_intent_filter(element: &XmlElement) -> IntentFilter {
+        let names = |tag: &str| -> Vec<String> {
+            element
+                .children_named(tag)
+                .filter_map(|child| child.string_attribute(ATTR_NAME, "name"))
+                .collect()
+        };
+        IntentFilter {
+            actions: names("action"),
+            categories: names("category"),
+            data: element
+                .children_named("data")
+                .map(|data| IntentFilterData {
+                    scheme: data.string_attribute(ATTR_SCHEME, "scheme"),
+                    host: data.string_attribute(ATTR_HOST, "host"),
+                    port: data.string_attribute(ATTR_PORT, "port"),
+                    path: data.string_attribute(ATTR_PATH, "path"),
+                    path_prefix: data.string_attribute(ATTR_PATH_PREFIX, "pathPrefix"),
+                    path_pattern: data.string_attribute(ATTR_PATH_PATTERN, "pathPattern"),
+                    mime_type: data.string_attribute(ATTR_MIME_TYPE, "mimeType"),
+                })
+                .collect(),
+            priority: element
+                .attribute(ATTR_PRIORITY, "priority")
+                .and_then(|attr| match attr.value {
+                    ResourceValue::Int(value) => Some(value),
+                    _ => None,
+                }),
+        }
+    }
+
+    /// internal use, `None` if not declared or a resource reference
+    fn bool_attribute(element: &XmlElement, resource_id: u32, name: &str) -> Option<bool> {
+        match element.attribute(resource_id, name)?.value {
+            ResourceValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// internal use, expand ".MainActivity" and "MainActivity" relative to the package
+    fn class_name(&self, name: &str) -> String {
+        if name.starts_with('.') {
+            format!("{}{}", self.package_name, name)
+        } else if !name.contains('.') {
+            format!("{}.{}", self.package_name, name)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apk::xml_builder::{document, end_element, start_element};
+
+    #[test]
+    fn test_parse_manifest() {
+        let strings = [
+            "name",
+            "versionCode",
+            "versionName",
+            "exported",
+            "package",
+            "manifest",
+            "uses-permission",
+            "application",
+            "activity",
+            "intent-filter",
+            "action",
+            "category",
+            "provider",
+            "authorities",
+            "com.example",
+            "1.2",
+            "android.permission.INTERNET",
+            ".App",
+            ".MainActivity",
+            "android.intent.action.MAIN",
+            "android.intent.category.LAUNCHER",
+            "com.example.data.Provider",
+            "com.example.a;com.example.b",
+        ];
+        let resource_map = [
+            ATTR_NAME,
+            ATTR_VERSION_CODE,
+            ATTR_VERSION_NAME,
+            ATTR_EXPORTED,
+        ];
+        let data = document(
+            &strings,
+            &resource_map,
+            &[
+                start_element(5, &[(4, 0x03, 14), (1, 0x10, 12), (2, 0x03, 15)]),
+                start_element(6, &[(0, 0x03, 16)]),
+                end_element(6),
+                start_element(7, &[(0, 0x03, 17)]),
+                start_element(8, &[(0, 0x03, 18), (3, 0x12, 0xffff_ffff)]),
+                start_element(9, &[]),
+                start_element(10, &[(0, 0x03, 19)]),
+                end_element(10),
+                start_element(11, &[(0, 0x03, 20)]),
+                end_element(11),
+                end_element(9),
+                end_element(8),
+                start_element(12, &[(0, 0x03, 21), (13, 0x03, 22)]),
+                end_element(12),
+                end_element(7),
+                end_element(5),
+            ],
+        );
+        let manifest = AndroidManifest::parse(&data).unwrap();
+        assert_eq!(manifest.package_name(), "com.example");
+        assert_eq!(manifest.version_code(), Some(12));
+        assert_eq!(manifest.version_name(), Some("1.2"));
+        assert_eq!(manifest.application_name(), Some("com.example.App"));
+        assert_eq!(manifest.uses_permissions(), ["android.permission.INTERNET"]);
+
+        let activity = &manifest.activities()[0];
+        assert_eq!(activity.name(), "com.example.MainActivity");
+        assert_eq!(activity.exported(), Some(true));
+        assert_eq!(activity.enabled(), None);
+        assert_eq!(
+            activity.intent_filters()[0],
+            IntentFilter {
+                actions: vec!["android.intent.action.MAIN".to_string()],
+                categories: vec!["android.intent.category.LAUNCHER".to_string()],
+                ..IntentFilter::default()
+            }
+        );
+
+        let provider = &manifest.providers()[0];
+        assert_eq!(provider.name(), "com.example.data.Provider");
+        assert_eq!(provider.authorities(), ["com.example.a", "com.example.b"]);
+        assert_eq!(manifest.components().count(), 2);
+    }
+
+    #[test]
+    fn test_not_a_manifest() {
+        let data = document(
+            &["resources"],
+            &[],
+            &[start_element(0, &[]), end_element(0)],
+        );
+        assert!(AndroidManifest::parse(&data).is_err());
+    }
+}
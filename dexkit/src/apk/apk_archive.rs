@@ -0,0 +1,92 @@
+use crate::apk::ApkEntry;
+use crate::errors::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// the size in the zip header is not trusted, at most this much is allocated up front
+const MAX_PREALLOCATED_SIZE: u64 = 16 << 20;
+
+/// internal use, read access to the entries of an apk
+pub(crate) struct ApkArchive {
+    path: PathBuf,
+    archive: zip::ZipArchive<File>,
+}
+
+impl ApkArchive {
+    pub(crate) fn open<P: AsRef<Path>>(apk_path: P) -> Result<Self, Error> {
+        let path = apk_path.as_ref().to_path_buf();
+        let file = File::open(&path)
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", path.display(), e)))?;
+        let archive = zip::ZipArchive::new(file)
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", path.display(), e)))?;
+        Ok(Self { path, archive })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// entry names in the order of the central directory
+    pub(crate) fn entry_names(&self) -> Vec<String> {
+        self.archive.file_names().map(str::to_string).collect()
+    }
+
+    /// "classes.dex", "classes2.dex", ... in dex id order
+    pub(crate) fn dex_entry_names(&self) -> Vec<String> {
+        let mut names: Vec<(u32, String)> = self
+            .archive
+            .file_names()
+            .filter_map(|name| Self::dex_entry_index(name).map(|index| (index, name.to_string())))
+            .collect();
+        names.sort();
+        names.into_iter().map(|(_, name)| name).collect()
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.archive.index_for_name(name).is_some()
+    }
+
+    /// uncompressed content of the entry `name`
+    pub(crate) fn read(&mut self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut entry = self
+            .archive
+            .by_name(name)
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", name, e)))?;
+        let mut data = Vec::with_capacity(entry.size().min(MAX_PREALLOCATED_SIZE) as usize);
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", name, e)))?;
+        Ok(data)
+    }
+
+    /// sizes and crc of the entry `name`
+    pub(crate) fn entry(&mut self, name: &str) -> Result<ApkEntry, Error> {
+        let index = self
+            .archive
+            .index_for_name(name)
+            .ok_or_else(|| Error::ApkReadError(format!("{}: entry not found", name)))?;
+        let entry = self
+            .archive
+            .by_index_raw(index)
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", name, e)))?;
+        Ok(ApkEntry {
+            name: name.to_string(),
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            crc32: entry.crc32(),
+        })
+    }
+
+    /// internal use, entry index of "classes{N}.dex" in the apk root, "classes.dex" is 1
+    fn dex_entry_index(name: &str) -> Option<u32> {
+        let index = name.strip_prefix("classes")?.strip_suffix(".dex")?;
+        if index.is_empty() {
+            return Some(1);
+        }
+        if index.starts_with('0') || !index.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        index.parse().ok()
+    }
+}
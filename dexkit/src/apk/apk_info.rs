@@ -0,0 +1,141 @@
+use crate::apk::{
+    AndroidManifest, ApkArchive, SignatureScheme, SigningCertificate, is_v1_signature_entry,
+    pkcs7_certificates, signing_block_certificates,
+};
+use crate::errors::Error;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Size and checksum of an entry of the apk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApkEntry {
+    pub(crate) name: String,
+    pub(crate) size: u64,
+    pub(crate) compressed_size: u64,
+    pub(crate) crc32: u32,
+}
+
+impl ApkEntry {
+    /// entry name, e.g. "classes2.dex"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// uncompressed size in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// stored size in bytes, equal to `size` for uncompressed entries
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// CRC-32 of the uncompressed content, as recorded in the central directory
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+}
+
+/// Summary of an apk: identity, dex entries and signing certificates.
+#[derive(Debug, Clone)]
+pub struct ApkInfo {
+    path: PathBuf,
+    package_name: String,
+    version_code: Option<i64>,
+    version_name: Option<String>,
+    dex_entries: Vec<ApkEntry>,
+    certificates: Vec<SigningCertificate>,
+}
+
+impl ApkInfo {
+    /// Read the summary of the apk at `apk_path` without loading it into DexKit.
+    pub fn load<P: AsRef<Path>>(apk_path: P) -> Result<Self, Error> {
+        let mut archive = ApkArchive::open(apk_path)?;
+        let manifest = AndroidManifest::parse(&archive.read("AndroidManifest.xml")?)?;
+        let dex_entries = archive
+            .dex_entry_names()
+            .iter()
+            .map(|name| archive.entry(name))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut certificates = Vec::new();
+        let v1_entries: Vec<String> = archive
+            .entry_names()
+            .into_iter()
+            .filter(|name| is_v1_signature_entry(name))
+            .collect();
+        for name in v1_entries {
+            let data = archive.read(&name)?;
+            for der in pkcs7_certificates(&data)? {
+                let certificate = SigningCertificate::from_der(SignatureScheme::V1, der);
+                if !certificates.contains(&certificate) {
+                    certificates.push(certificate);
+                }
+            }
+        }
+        let mut file = File::open(archive.path())
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", archive.path().display(), e)))?;
+        certificates.extend(signing_block_certificates(&mut file)?);
+
+        Ok(Self {
+            path: archive.path().to_path_buf(),
+            package_name: manifest.package_name().to_string(),
+            version_code: manifest.version_code(),
+            version_name: manifest.version_name().map(String::from),
+            dex_entries,
+            certificates,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// package name from the manifest, e.g. "com.example"
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    /// `android:versionCode` from the manifest
+    pub fn version_code(&self) -> Option<i64> {
+        self.version_code
+    }
+
+    /// `android:versionName` from the manifest
+    pub fn version_name(&self) -> Option<&str> {
+        self.version_name.as_deref()
+    }
+
+    /// "classes.dex", "classes2.dex", ... in dex id order
+    pub fn dex_entries(&self) -> &[ApkEntry] {
+        &self.dex_entries
+    }
+
+    /// signing certificates of every scheme, v1 first, without duplicates within a scheme
+    pub fn certificates(&self) -> &[SigningCertificate] {
+        &self.certificates
+    }
+
+    /// signing certificates found in `scheme`
+    pub fn certificates_of(
+        &self,
+        scheme: SignatureScheme,
+    ) -> impl Iterator<Item = &SigningCertificate> {
+        self.certificates
+            .iter()
+            .filter(move |certificate| certificate.scheme() == scheme)
+    }
+
+    /// schemes the apk is signed with, in ascending order
+    pub fn signature_schemes(&self) -> Vec<SignatureScheme> {
+        let mut schemes: Vec<SignatureScheme> = self
+            .certificates
+            .iter()
+            .map(SigningCertificate::scheme)
+            .collect();
+        schemes.sort();
+        schemes.dedup();
+        schemes
+    }
+}
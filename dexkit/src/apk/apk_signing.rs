@@ -0,0 +1,415 @@
+use crate::errors::Error;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom};
+
+const EOCD_MAGIC: u32 = 0x0605_4b50;
+const EOCD_SIZE: usize = 22;
+const SIGNING_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const SIGNATURE_SCHEME_V2_ID: u32 = 0x7109_871a;
+const SIGNATURE_SCHEME_V3_ID: u32 = 0xf053_68c0;
+const SIGNATURE_SCHEME_V31_ID: u32 = 0x1b93_ad61;
+
+const DER_SEQUENCE: u8 = 0x30;
+const DER_OID: u8 = 0x06;
+const DER_CONTEXT_0: u8 = 0xa0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SignatureScheme {
+    /// JAR signing, META-INF/*.RSA, *.DSA, *.EC
+    V1,
+    /// APK Signature Scheme v2
+    V2,
+    /// APK Signature Scheme v3 and v3.1
+    V3,
+}
+
+/// A certificate of an apk signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningCertificate {
+    scheme: SignatureScheme,
+    sha256: [u8; 32],
+}
+
+impl SigningCertificate {
+    /// the scheme the certificate was found in
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    /// SHA-256 digest of the DER encoded certificate
+    pub fn sha256(&self) -> &[u8; 32] {
+        &self.sha256
+    }
+
+    /// lowercase hex of `sha256`, as printed by `apksigner verify --print-certs`
+    pub fn sha256_hex(&self) -> String {
+        self.sha256.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// internal use
+    pub(crate) fn from_der(scheme: SignatureScheme, der: &[u8]) -> Self {
+        Self {
+            scheme,
+            sha256: Sha256::digest(der).into(),
+        }
+    }
+}
+
+/// internal use, is `name` a v1 signature block, e.g. "META-INF/CERT.RSA"
+pub(crate) fn is_v1_signature_entry(name: &str) -> bool {
+    let Some(file_name) = name.strip_prefix("META-INF/") else {
+        return false;
+    };
+    let upper = file_name.to_ascii_uppercase();
+    !file_name.contains('/')
+        && (upper.ends_with(".RSA") || upper.ends_with(".DSA") || upper.ends_with(".EC"))
+}
+
+/// internal use, DER certificates of a PKCS#7 SignedData, the content of a v1 signature block
+pub(crate) fn pkcs7_certificates(data: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    // ContentInfo ::= SEQUENCE { contentType OID, content [0] EXPLICIT SignedData }
+    let content_info = DerElement::read(data, 0)?.expect(DER_SEQUENCE)?;
+    let content_type = DerElement::read(content_info.content, 0)?.expect(DER_OID)?;
+    let content =
+        DerElement::read(content_info.content, content_type.end)?.expect(DER_CONTEXT_0)?;
+    let signed_data = DerElement::read(content.content, 0)?.expect(DER_SEQUENCE)?;
+
+    // SignedData ::= SEQUENCE { version, digestAlgorithms, encapContentInfo,
+    //     certificates [0] IMPLICIT SET OF Certificate OPTIONAL, crls [1] OPTIONAL, signerInfos }
+    let mut pos = 0;
+    while pos < signed_data.content.len() {
+        let element = DerElement::read(signed_data.content, pos)?;
+        if element.tag == DER_CONTEXT_0 {
+            let mut certificates = Vec::new();
+            let mut cert_pos = 0;
+            while cert_pos < element.content.len() {
+                let certificate = DerElement::read(element.content, cert_pos)?;
+                certificates.push(certificate.raw);
+                cert_pos = certificate.end;
+            }
+            return Ok(certificates);
+        }
+        pos = element.end;
+    }
+    Ok(Vec::new())
+}
+
+/// internal use, certificates of the v2 and v3 signers in the APK Signing Block,
+/// empty if the apk has no signing block
+pub(crate) fn signing_block_certificates<R: Read + Seek>(
+    reader: &mut R,
+) -> Result<Vec<SigningCertificate>, Error> {
+    let Some(block) = read_signing_block(reader)? else {
+        return Ok(Vec::new());
+    };
+    // size of block (u64), id-value pairs, size of block (u64), magic
+    let pairs = &block[8..block.len() - 24];
+    let mut certificates = Vec::new();
+    let mut pos = 0;
+    while pos < pairs.len() {
+        let pair_size = read_u64(pairs, pos)? as usize;
+        let pair = slice(pairs, pos + 8, pair_size)?;
+        pos += 8 + pair_size;
+        let scheme = match read_u32(pair, 0)? {
+            SIGNATURE_SCHEME_V2_ID => SignatureScheme::V2,
+            SIGNATURE_SCHEME_V3_ID | SIGNATURE_SCHEME_V31_ID => SignatureScheme::V3,
+            _ => continue,
+        };
+        // signers: length-prefixed sequence of length-prefixed signer
+        // signer: signed data, ..., signed data: digests, certificates, ...
+        let (signers, _) = length_prefixed(pair, 4)?;
+        for signer in length_prefixed_items(signers)? {
+            let (signed_data, _) = length_prefixed(signer, 0)?;
+            let (_, certificates_pos) = length_prefixed(signed_data, 0)?;
+            let (signer_certificates, _) = length_prefixed(signed_data, certificates_pos)?;
+            for certificate in length_prefixed_items(signer_certificates)? {
+                let certificate = SigningCertificate::from_der(scheme, certificate);
+                if !certificates.contains(&certificate) {
+                    certificates.push(certificate);
+                }
+            }
+        }
+    }
+    Ok(certificates)
+}
+
+/// internal use, the whole APK Signing Block, located right before the central directory
+fn read_signing_block<R: Read + Seek>(reader: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let Some(central_directory_offset) = find_central_directory_offset(reader)? else {
+        return Ok(None);
+    };
+    if central_directory_offset < 32 {
+        return Ok(None);
+    }
+    let mut footer = [0u8; 24];
+    read_at(reader, central_directory_offset - 24, &mut footer)?;
+    if &footer[8..] != SIGNING_BLOCK_MAGIC {
+        return Ok(None);
+    }
+    let block_size = read_u64(&footer, 0)?;
+    // the leading size field is not counted in block_size
+    let total_size = match block_size.checked_add(8) {
+        Some(total_size) if block_size >= 24 && total_size <= central_directory_offset => {
+            total_size
+        }
+        _ => return Err(invalid_signing_block()),
+    };
+    let mut block = vec![0u8; total_size as usize];
+    read_at(reader, central_directory_offset - total_size, &mut block)?;
+    if read_u64(&block, 0)? != block_size {
+        return Err(invalid_signing_block());
+    }
+    Ok(Some(block))
+}
+
+/// internal use, central directory offset recorded in the end of central directory record
+fn find_central_directory_offset<R: Read + Seek>(reader: &mut R) -> Result<Option<u64>, Error> {
+    let file_size = reader.seek(SeekFrom::End(0)).map_err(io_error)?;
+    // the record is followed by a comment of at most 65535 bytes
+    let tail_size = file_size.min((EOCD_SIZE + 0xffff) as u64) as usize;
+    if tail_size < EOCD_SIZE {
+        return Ok(None);
+    }
+    let mut tail = vec![0u8; tail_size];
+    read_at(reader, file_size - tail_size as u64, &mut tail)?;
+    for pos in (0..=tail_size - EOCD_SIZE).rev() {
+        if read_u32(&tail, pos)? != EOCD_MAGIC {
+            continue;
+        }
+        let comment_size = u16::from_le_bytes([tail[pos + 20], tail[pos + 21]]) as usize;
+        if pos + EOCD_SIZE + comment_size == tail_size {
+            let offset = read_u32(&tail, pos + 16)? as u64;
+            // the central directory precedes the record, this bounds the signing block size
+            let record_offset = file_size - (tail_size - pos) as u64;
+            return Ok((offset <= record_offset).then_some(offset));
+        }
+    }
+    Ok(None)
+}
+
+/// internal use, one DER encoded element
+struct DerElement<'d> {
+    tag: u8,
+    /// tag, length and content
+    raw: &'d [u8],
+    content: &'d [u8],
+    /// offset of the next element in the parent
+    end: usize,
+}
+
+impl<'d> DerElement<'d> {
+    fn read(data: &'d [u8], pos: usize) -> Result<Self, Error> {
+        let tag = *data.get(pos).ok_or_else(invalid_pkcs7)?;
+        let first = *data.get(pos + 1).ok_or_else(invalid_pkcs7)? as usize;
+        let (length, header_size) = if first < 0x80 {
+            (first, 2)
+        } else {
+            // long form, indefinite lengths (BER) are not supported
+            let count = first & 0x7f;
+            if count == 0 || count > 4 {
+                return Err(invalid_pkcs7());
+            }
+            let bytes = data
+                .get(pos + 2..pos + 2 + count)
+                .ok_or_else(invalid_pkcs7)?;
+            let length = bytes.iter().fold(0usize, |acc, &b| acc << 8 | b as usize);
+            (length, 2 + count)
+        };
+        let end = pos + header_size + length;
+        if end > data.len() {
+            return Err(invalid_pkcs7());
+        }
+        Ok(Self {
+            tag,
+            raw: &data[pos..end],
+            content: &data[pos + header_size..end],
+            end,
+        })
+    }
+
+    fn expect(self, tag: u8) -> Result<Self, Error> {
+        if self.tag == tag {
+            Ok(self)
+        } else {
+            Err(invalid_pkcs7())
+        }
+    }
+}
+
+/// internal use, the u32 length-prefixed slice at `pos` and the offset following it
+fn length_prefixed(data: &[u8], pos: usize) -> Result<(&[u8], usize), Error> {
+    let size = read_u32(data, pos)? as usize;
+    Ok((slice(data, pos + 4, size)?, pos + 4 + size))
+}
+
+/// internal use, a sequence of u32 length-prefixed items
+fn length_prefixed_items(data: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    let mut items = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (item, next) = length_prefixed(data, pos)?;
+        items.push(item);
+        pos = next;
+    }
+    Ok(items)
+}
+
+fn slice(data: &[u8], pos: usize, size: usize) -> Result<&[u8], Error> {
+    data.get(pos..pos.checked_add(size).ok_or_else(invalid_signing_block)?)
+        .ok_or_else(invalid_signing_block)
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, Error> {
+    let bytes = slice(data, pos, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(data: &[u8], pos: usize) -> Result<u64, Error> {
+    let bytes = slice(data, pos, 8)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, buf: &mut [u8]) -> Result<(), Error> {
+    reader.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+    reader.read_exact(buf).map_err(io_error)
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::ApkReadError(e.to_string())
+}
+
+fn invalid_signing_block() -> Error {
+    Error::ApkReadError("invalid APK Signing Block".to_string())
+}
+
+fn invalid_pkcs7() -> Error {
+    Error::ApkReadError("invalid PKCS#7 signature block".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn length_prefix(items: &[&[u8]]) -> Vec<u8> {
+        items
+            .iter()
+            .flat_map(|item| [&(item.len() as u32).to_le_bytes()[..], item].concat())
+            .collect()
+    }
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut data = vec![tag];
+        if content.len() < 0x80 {
+            data.push(content.len() as u8);
+        } else {
+            data.push(0x82);
+            data.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        }
+        data.extend_from_slice(content);
+        data
+    }
+
+    /// a zip with no entries, `prefix` is placed before the central directory
+    fn zip_with(prefix: &[u8]) -> Vec<u8> {
+        let mut data = prefix.to_vec();
+        let central_directory_offset = data.len() as u32;
+        data.extend_from_slice(&EOCD_MAGIC.to_le_bytes());
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&central_directory_offset.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_v1_entry_name() {
+        assert!(is_v1_signature_entry("META-INF/CERT.RSA"));
+        assert!(is_v1_signature_entry("META-INF/release.ec"));
+        assert!(!is_v1_signature_entry("META-INF/CERT.SF"));
+        assert!(!is_v1_signature_entry("META-INF/a/CERT.RSA"));
+        assert!(!is_v1_signature_entry("CERT.RSA"));
+    }
+
+    #[test]
+    fn test_pkcs7_certificates() {
+        let certificate = der(DER_SEQUENCE, &[0x02, 0x01, 0x01]);
+        let signed_data = der(
+            DER_SEQUENCE,
+            &[
+                der(0x02, &[1]),
+                der(0x31, &[]),
+                der(DER_SEQUENCE, &der(DER_OID, &[0x2a])),
+                der(DER_CONTEXT_0, &certificate),
+                der(0x31, &[]),
+            ]
+            .concat(),
+        );
+        let content_info = der(
+            DER_SEQUENCE,
+            &[der(DER_OID, &[0x2a]), der(DER_CONTEXT_0, &signed_data)].concat(),
+        );
+        assert_eq!(
+            pkcs7_certificates(&content_info).unwrap(),
+            vec![&certificate[..]]
+        );
+        assert!(pkcs7_certificates(&content_info[..content_info.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_signing_block_certificates() {
+        let certificate: &[u8] = b"certificate";
+        let signed_data = length_prefix(&[&length_prefix(&[]), &length_prefix(&[certificate])]);
+        let signer = length_prefix(&[&signed_data]);
+        let signers = length_prefix(&[&length_prefix(&[&signer])]);
+        let mut pair = SIGNATURE_SCHEME_V2_ID.to_le_bytes().to_vec();
+        pair.extend_from_slice(&signers);
+        let mut pairs = (pair.len() as u64).to_le_bytes().to_vec();
+        pairs.extend_from_slice(&pair);
+
+        let block_size = (pairs.len() + 24) as u64;
+        let mut block = block_size.to_le_bytes().to_vec();
+        block.extend_from_slice(&pairs);
+        block.extend_from_slice(&block_size.to_le_bytes());
+        block.extend_from_slice(SIGNING_BLOCK_MAGIC);
+
+        let mut apk = Cursor::new(zip_with(&[&[0u8; 4][..], &block].concat()));
+        let certificates = signing_block_certificates(&mut apk).unwrap();
+        assert_eq!(
+            certificates,
+            vec![SigningCertificate::from_der(
+                SignatureScheme::V2,
+                certificate
+            )]
+        );
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        let certificate = SigningCertificate::from_der(SignatureScheme::V1, b"abc");
+        assert_eq!(
+            certificate.sha256_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_invalid_signing_block_size() {
+        let mut footer = u64::MAX.to_le_bytes().to_vec();
+        footer.extend_from_slice(SIGNING_BLOCK_MAGIC);
+        let mut apk = Cursor::new(zip_with(&[&[0u8; 16][..], &footer].concat()));
+        assert!(signing_block_certificates(&mut apk).is_err());
+
+        let mut footer = 1024u64.to_le_bytes().to_vec();
+        footer.extend_from_slice(SIGNING_BLOCK_MAGIC);
+        let mut apk = Cursor::new(zip_with(&[&[0u8; 16][..], &footer].concat()));
+        assert!(signing_block_certificates(&mut apk).is_err());
+    }
+
+    #[test]
+    fn test_no_signing_block() {
+        let mut apk = Cursor::new(zip_with(&[0u8; 64]));
+        assert!(signing_block_certificates(&mut apk).unwrap().is_empty());
+        let mut not_zip = Cursor::new(vec![0u8; 16]);
+        assert!(signing_block_certificates(&mut not_zip).unwrap().is_empty());
+    }
+}
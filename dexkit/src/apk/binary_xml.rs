@@ -0,0 +1,235 @@
+use crate::apk::{
+    RES_STRING_POOL_TYPE, RES_XML_END_ELEMENT_TYPE, RES_XML_RESOURCE_MAP_TYPE,
+    RES_XML_START_ELEMENT_TYPE, RES_XML_TYPE, ResChunk, ResStringPool, ResourceValue, read_u32,
+};
+use crate::errors::Error;
+
+const NO_STRING: u32 = 0xffff_ffff;
+
+/// internal use, one attribute of a binary xml element
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct XmlAttribute {
+    pub(crate) namespace: Option<String>,
+    pub(crate) name: String,
+    /// attribute resource id from the resource map, e.g. 0x01010003 for android:name
+    pub(crate) resource_id: Option<u32>,
+    /// the original string of the attribute, if kept by aapt
+    pub(crate) raw_value: Option<String>,
+    pub(crate) value: ResourceValue,
+}
+
+/// internal use, one element of a binary xml document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct XmlElement {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<XmlAttribute>,
+    pub(crate) children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    /// internal use, parse a binary xml document (ResXMLTree) and return its root element
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let document = ResChunk::read(data, 0)?;
+        if document.chunk_type != RES_XML_TYPE {
+            return Err(Error::ResourceError(
+                "not a binary xml document".to_string(),
+            ));
+        }
+
+        let mut pool = ResStringPool::default();
+        let mut resource_map: Vec<u32> = Vec::new();
+        // elements being built, the root at the bottom
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root: Option<XmlElement> = None;
+        for chunk in document.children() {
+            let chunk = chunk?;
+            match chunk.chunk_type {
+                RES_STRING_POOL_TYPE => pool = ResStringPool::parse(&chunk)?,
+                RES_XML_RESOURCE_MAP_TYPE => {
+                    resource_map = (chunk.header_size..chunk.data.len())
+                        .step_by(4)
+                        .map(|offset| chunk.u32_at(offset))
+                        .collect::<Result<Vec<u32>, Error>>()?;
+                }
+                RES_XML_START_ELEMENT_TYPE => {
+                    stack.push(Self::parse_start_element(&chunk, &pool, &resource_map)?);
+                }
+                RES_XML_END_ELEMENT_TYPE => {
+                    let element = stack.pop().ok_or_else(|| {
+                        Error::ResourceError("unbalanced end element".to_string())
+                    })?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => {
+                            root.get_or_insert(element);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        root.ok_or_else(|| Error::ResourceError("binary xml has no root element".to_string()))
+    }
+
+    /// child elements named `name`
+    pub(crate) fn children_named<'e>(
+        &'e self,
+        name: &'e str,
+    ) -> impl Iterator<Item = &'e XmlElement> + 'e {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// attribute matched by resource id first, names may be stripped by obfuscators
+    pub(crate) fn attribute(&self, resource_id: u32, name: &str) -> Option<&XmlAttribute> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.resource_id == Some(resource_id))
+            .or_else(|| {
+                self.attributes
+                    .iter()
+                    .find(|attr| attr.resource_id.is_none() && attr.name == name)
+            })
+    }
+
+    /// string value of an attribute, from the typed value or the raw value
+    pub(crate) fn string_attribute(&self, resource_id: u32, name: &str) -> Option<String> {
+        let attr = self.attribute(resource_id, name)?;
+        match &attr.value {
+            ResourceValue::String(value) => Some(value.clone()),
+            _ => attr.raw_value.clone(),
+        }
+    }
+
+    /// internal use, parse a ResXMLTree_node with a ResXMLTree_attrExt
+    fn parse_start_element(
+        chunk: &ResChunk,
+        pool: &ResStringPool,
+        resource_map: &[u32],
+    ) -> Result<Self, Error> {
+        let ext = chunk.header_size;
+        let name = Self::string(pool, chunk.u32_at(ext + 4)?);
+        let attribute_start = chunk.u16_at(ext + 8)? as usize;
+        let attribute_size = chunk.u16_at(ext + 10)? as usize;
+        let attribute_count = chunk.u16_at(ext + 12)? as usize;
+
+        let mut attributes = Vec::with_capacity(attribute_count);
+        for idx in 0..attribute_count {
+            let offset = ext + attribute_start + idx * attribute_size;
+            let namespace_idx = read_u32(chunk.data, offset)?;
+            let name_idx = read_u32(chunk.data, offset + 4)?;
+            let raw_value_idx = read_u32(chunk.data, offset + 8)?;
+            let value = ResourceValue::read(chunk.data, offset + 12, pool)?;
+            attributes.push(XmlAttribute {
+                namespace: (namespace_idx != NO_STRING).then(|| Self::string(pool, namespace_idx)),
+                name: Self::string(pool, name_idx),
+                resource_id: resource_map
+                    .get(name_idx as usize)
+                    .copied()
+                    .filter(|&id| id != 0),
+                raw_value: (raw_value_idx != NO_STRING).then(|| Self::string(pool, raw_value_idx)),
+                value,
+            });
+        }
+        Ok(Self {
+            name,
+            attributes,
+            children: Vec::new(),
+        })
+    }
+
+    fn string(pool: &ResStringPool, idx: u32) -> String {
+        pool.get(idx).unwrap_or_default().to_string()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod xml_builder {
+    use crate::apk::chunk_builder::{chunk, string_pool};
+    use crate::apk::*;
+
+    /// an attribute as (name string index, typed value type, typed value data)
+    pub(crate) type Attribute = (u32, u8, u32);
+
+    pub(crate) fn start_element(name: u32, attributes: &[Attribute]) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+        let mut body = Vec::new();
+        body.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+        body.extend_from_slice(&name.to_le_bytes());
+        body.extend_from_slice(&20u16.to_le_bytes());
+        body.extend_from_slice(&20u16.to_le_bytes());
+        body.extend_from_slice(&(attributes.len() as u16).to_le_bytes());
+        body.extend_from_slice(&[0; 6]);
+        for &(name, data_type, data) in attributes {
+            body.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+            body.extend_from_slice(&name.to_le_bytes());
+            let raw_value = if data_type == 0x03 { data } else { 0xffff_ffff };
+            body.extend_from_slice(&raw_value.to_le_bytes());
+            body.extend_from_slice(&[8, 0, 0, data_type]);
+            body.extend_from_slice(&data.to_le_bytes());
+        }
+        chunk(RES_XML_START_ELEMENT_TYPE, &header, &body)
+    }
+
+    pub(crate) fn end_element(name: u32) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+        let mut body = Vec::new();
+        body.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+        body.extend_from_slice(&name.to_le_bytes());
+        chunk(RES_XML_END_ELEMENT_TYPE, &header, &body)
+    }
+
+    /// build a binary xml document from its strings, attribute resource ids and element chunks
+    pub(crate) fn document(strings: &[&str], resource_map: &[u32], nodes: &[Vec<u8>]) -> Vec<u8> {
+        let map: Vec<u8> = resource_map
+            .iter()
+            .flat_map(|id| id.to_le_bytes())
+            .collect();
+        let mut body = string_pool(strings);
+        body.extend_from_slice(&chunk(RES_XML_RESOURCE_MAP_TYPE, &[], &map));
+        nodes.iter().for_each(|node| body.extend_from_slice(node));
+        chunk(RES_XML_TYPE, &[], &body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xml_builder::{document, end_element, start_element};
+    use super::*;
+
+    #[test]
+    fn test_parse_tree() {
+        // "name" maps to android:name in the resource map, "label" has no resource id
+        let data = document(
+            &["name", "label", "manifest", "application", ".App"],
+            &[0x0101_0003],
+            &[
+                start_element(2, &[]),
+                start_element(3, &[(0, 0x03, 4), (1, 0x01, 0x7f0f_0000)]),
+                end_element(3),
+                end_element(2),
+            ],
+        );
+        let root = XmlElement::parse(&data).unwrap();
+        assert_eq!(root.name, "manifest");
+        assert_eq!(root.children.len(), 1);
+
+        let application = root.children_named("application").next().unwrap();
+        assert_eq!(
+            application.string_attribute(0x0101_0003, "name").as_deref(),
+            Some(".App")
+        );
+        let label = application.attribute(0x0101_0001, "label").unwrap();
+        assert_eq!(label.resource_id, None);
+        assert_eq!(label.value, ResourceValue::Reference(0x7f0f_0000));
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        let data = document(&["manifest"], &[], &[end_element(0)]);
+        assert!(XmlElement::parse(&data).is_err());
+    }
+}
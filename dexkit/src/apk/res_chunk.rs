@@ -0,0 +1,370 @@
+use crate::errors::Error;
+
+// ResChunk_header types, see frameworks/base/libs/androidfw/include/androidfw/ResourceTypes.h
+pub(crate) const RES_STRING_POOL_TYPE: u16 = 0x0001;
+pub(crate) const RES_TABLE_TYPE: u16 = 0x0002;
+pub(crate) const RES_XML_TYPE: u16 = 0x0003;
+pub(crate) const RES_XML_START_NAMESPACE_TYPE: u16 = 0x0100;
+pub(crate) const RES_XML_END_NAMESPACE_TYPE: u16 = 0x0101;
+pub(crate) const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+pub(crate) const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+pub(crate) const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+pub(crate) const RES_TABLE_PACKAGE_TYPE: u16 = 0x0200;
+pub(crate) const RES_TABLE_TYPE_TYPE: u16 = 0x0201;
+
+const STRING_POOL_UTF8_FLAG: u32 = 1 << 8;
+
+/// internal use, one ResChunk_header and its content
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResChunk<'d> {
+    pub(crate) chunk_type: u16,
+    pub(crate) header_size: usize,
+    /// the whole chunk, header included
+    pub(crate) data: &'d [u8],
+}
+
+impl<'d> ResChunk<'d> {
+    /// read the chunk starting at `offset`
+    pub(crate) fn read(data: &'d [u8], offset: usize) -> Result<Self, Error> {
+        let chunk_type = read_u16(data, offset)?;
+        let header_size = read_u16(data, offset + 2)? as usize;
+        let size = read_u32(data, offset + 4)? as usize;
+        if size < 8 || header_size < 8 || header_size > size || offset + size > data.len() {
+            return Err(Error::ResourceError(format!(
+                "invalid chunk 0x{:04X} at 0x{:X}",
+                chunk_type, offset
+            )));
+        }
+        Ok(Self {
+            chunk_type,
+            header_size,
+            data: &data[offset..offset + size],
+        })
+    }
+
+    /// the chunks following the header of this chunk
+    pub(crate) fn children(&self) -> ResChunks<'d> {
+        ResChunks::new(self.data, self.header_size)
+    }
+
+    pub(crate) fn u8_at(&self, offset: usize) -> Result<u8, Error> {
+        read_u8(self.data, offset)
+    }
+
+    pub(crate) fn u16_at(&self, offset: usize) -> Result<u16, Error> {
+        read_u16(self.data, offset)
+    }
+
+    pub(crate) fn u32_at(&self, offset: usize) -> Result<u32, Error> {
+        read_u32(self.data, offset)
+    }
+}
+
+/// internal use, iterator over consecutive chunks
+pub(crate) struct ResChunks<'d> {
+    data: &'d [u8],
+    offset: usize,
+}
+
+impl<'d> ResChunks<'d> {
+    pub(crate) fn new(data: &'d [u8], offset: usize) -> Self {
+        Self { data, offset }
+    }
+}
+
+impl<'d> Iterator for ResChunks<'d> {
+    type Item = Result<ResChunk<'d>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // trailing bytes shorter than a chunk header are padding
+        if self.offset + 8 > self.data.len() {
+            return None;
+        }
+        let chunk = ResChunk::read(self.data, self.offset);
+        match &chunk {
+            Ok(chunk) => self.offset += chunk.data.len(),
+            Err(_) => self.offset = self.data.len(),
+        }
+        Some(chunk)
+    }
+}
+
+/// internal use, decoded ResStringPool
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResStringPool {
+    strings: Vec<String>,
+}
+
+impl ResStringPool {
+    pub(crate) fn parse(chunk: &ResChunk) -> Result<Self, Error> {
+        let string_count = chunk.u32_at(8)? as usize;
+        let flags = chunk.u32_at(16)?;
+        let strings_start = chunk.u32_at(20)? as usize;
+        let utf8 = flags & STRING_POOL_UTF8_FLAG != 0;
+
+        // string_count is untrusted, a too large count fails on the index instead
+        let mut strings = Vec::new();
+        for idx in 0..string_count {
+            let offset = chunk.u32_at(chunk.header_size + idx * 4)? as usize;
+            let start = strings_start + offset;
+            let string = if utf8 {
+                Self::read_utf8(chunk.data, start)?
+            } else {
+                Self::read_utf16(chunk.data, start)?
+            };
+            strings.push(string);
+        }
+        Ok(Self { strings })
+    }
+
+    pub(crate) fn get(&self, idx: u32) -> Option<&str> {
+        self.strings.get(idx as usize).map(String::as_str)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    fn read_utf8(data: &[u8], offset: usize) -> Result<String, Error> {
+        // utf16 length, then utf8 length, each 1 or 2 bytes
+        let (_, offset) = Self::read_utf8_length(data, offset)?;
+        let (length, offset) = Self::read_utf8_length(data, offset)?;
+        let bytes = data.get(offset..offset + length).ok_or_else(|| {
+            Error::ResourceError(format!("string at 0x{:X} is out of bounds", offset))
+        })?;
+        Ok(String::from_utf8_lossy(bytes).to_string())
+    }
+
+    fn read_utf8_length(data: &[u8], offset: usize) -> Result<(usize, usize), Error> {
+        let first = read_u8(data, offset)? as usize;
+        if first & 0x80 != 0 {
+            let second = read_u8(data, offset + 1)? as usize;
+            Ok(((first & 0x7f) << 8 | second, offset + 2))
+        } else {
+            Ok((first, offset + 1))
+        }
+    }
+
+    fn read_utf16(data: &[u8], offset: usize) -> Result<String, Error> {
+        let first = read_u16(data, offset)? as usize;
+        let (length, offset) = if first & 0x8000 != 0 {
+            let second = read_u16(data, offset + 2)? as usize;
+            ((first & 0x7fff) << 16 | second, offset + 4)
+        } else {
+            (first, offset + 2)
+        };
+        let units = (0..length)
+            .map(|i| read_u16(data, offset + i * 2))
+            .collect::<Result<Vec<u16>, Error>>()?;
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+/// A Res_value of a resource entry or a binary xml attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceValue {
+    Null,
+    /// resource id, e.g. `@string/app_name`
+    Reference(u32),
+    /// attribute resource id, e.g. `?attr/colorPrimary`
+    Attribute(u32),
+    String(String),
+    Float(f32),
+    /// complex dimension value, see `TypedValue.complexToFloat`
+    Dimension(u32),
+    /// complex fraction value, see `TypedValue.complexToFloat`
+    Fraction(u32),
+    Int(i32),
+    Bool(bool),
+    /// ARGB color
+    Color(u32),
+    Other {
+        data_type: u8,
+        data: u32,
+    },
+}
+
+impl ResourceValue {
+    /// the string of a `String` value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ResourceValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// the resource id of a `Reference` value
+    pub fn as_reference(&self) -> Option<u32> {
+        match self {
+            ResourceValue::Reference(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// internal use, decode the Res_value at `offset`, strings are resolved in `pool`
+    pub(crate) fn read(data: &[u8], offset: usize, pool: &ResStringPool) -> Result<Self, Error> {
+        let data_type = read_u8(data, offset + 3)?;
+        let value = read_u32(data, offset + 4)?;
+        Ok(Self::decode(data_type, value, pool))
+    }
+
+    pub(crate) fn decode(data_type: u8, data: u32, pool: &ResStringPool) -> Self {
+        match data_type {
+            0x00 => ResourceValue::Null,
+            0x01 | 0x07 => ResourceValue::Reference(data),
+            0x02 | 0x08 => ResourceValue::Attribute(data),
+            0x03 => pool
+                .get(data)
+                .map(|value| ResourceValue::String(value.to_string()))
+                .unwrap_or(ResourceValue::Other { data_type, data }),
+            0x04 => ResourceValue::Float(f32::from_bits(data)),
+            0x05 => ResourceValue::Dimension(data),
+            0x06 => ResourceValue::Fraction(data),
+            0x10 | 0x11 => ResourceValue::Int(data as i32),
+            0x12 => ResourceValue::Bool(data != 0),
+            0x1c..=0x1f => ResourceValue::Color(data),
+            _ => ResourceValue::Other { data_type, data },
+        }
+    }
+}
+
+pub(crate) fn read_u8(data: &[u8], offset: usize) -> Result<u8, Error> {
+    data.get(offset)
+        .copied()
+        .ok_or_else(|| Error::ResourceError(format!("read at 0x{:X} is out of bounds", offset)))
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    match data.get(offset..offset + 2) {
+        Some(bytes) => Ok(u16::from_le_bytes([bytes[0], bytes[1]])),
+        None => Err(Error::ResourceError(format!(
+            "read at 0x{:X} is out of bounds",
+            offset
+        ))),
+    }
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    match data.get(offset..offset + 4) {
+        Some(bytes) => Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => Err(Error::ResourceError(format!(
+            "read at 0x{:X} is out of bounds",
+            offset
+        ))),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod chunk_builder {
+    use super::*;
+
+    /// build a chunk from its type, the header fields after the ResChunk_header and the body
+    pub(crate) fn chunk(chunk_type: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+        let header_size = 8 + header.len();
+        let size = header_size + body.len();
+        let mut data = Vec::with_capacity(size);
+        data.extend_from_slice(&chunk_type.to_le_bytes());
+        data.extend_from_slice(&(header_size as u16).to_le_bytes());
+        data.extend_from_slice(&(size as u32).to_le_bytes());
+        data.extend_from_slice(header);
+        data.extend_from_slice(body);
+        data
+    }
+
+    /// build a utf8 ResStringPool chunk
+    pub(crate) fn string_pool(strings: &[&str]) -> Vec<u8> {
+        let mut offsets = Vec::new();
+        let mut content = Vec::new();
+        for string in strings {
+            offsets.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            content.push(string.chars().count() as u8);
+            content.push(string.len() as u8);
+            content.extend_from_slice(string.as_bytes());
+            content.push(0);
+        }
+        while content.len() % 4 != 0 {
+            content.push(0);
+        }
+        let strings_start = 28 + offsets.len();
+        let mut header = Vec::new();
+        header.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&STRING_POOL_UTF8_FLAG.to_le_bytes());
+        header.extend_from_slice(&(strings_start as u32).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        chunk(RES_STRING_POOL_TYPE, &header, &[offsets, content].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chunk_builder::{chunk, string_pool};
+    use super::*;
+
+    #[test]
+    fn test_string_pool_utf8() {
+        let data = string_pool(&["app_name", "中文"]);
+        let chunk = ResChunk::read(&data, 0).unwrap();
+        let pool = ResStringPool::parse(&chunk).unwrap();
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.get(0), Some("app_name"));
+        assert_eq!(pool.get(1), Some("中文"));
+        assert_eq!(pool.get(2), None);
+    }
+
+    #[test]
+    fn test_string_pool_utf16() {
+        let mut header = Vec::new();
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&32u32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        let mut body = 0u32.to_le_bytes().to_vec();
+        body.extend_from_slice(&[2, 0, b'o', 0, b'k', 0, 0, 0]);
+        let data = chunk(RES_STRING_POOL_TYPE, &header, &body);
+        let pool = ResStringPool::parse(&ResChunk::read(&data, 0).unwrap()).unwrap();
+        assert_eq!(pool.get(0), Some("ok"));
+    }
+
+    #[test]
+    fn test_string_pool_count_out_of_bounds() {
+        let mut header = Vec::new();
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        header.extend_from_slice(&[0; 16]);
+        let data = chunk(RES_STRING_POOL_TYPE, &header, &[]);
+        let chunk = ResChunk::read(&data, 0).unwrap();
+        assert!(ResStringPool::parse(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        let mut data = chunk(RES_TABLE_TYPE, &[0, 0, 0, 0], &[]);
+        data[4] = 0xff;
+        assert!(ResChunk::read(&data, 0).is_err());
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let pool = ResStringPool::default();
+        assert_eq!(
+            ResourceValue::decode(0x01, 0x7f01_0000, &pool),
+            ResourceValue::Reference(0x7f01_0000)
+        );
+        assert_eq!(
+            ResourceValue::decode(0x10, 0xffff_ffff, &pool),
+            ResourceValue::Int(-1)
+        );
+        assert_eq!(
+            ResourceValue::decode(0x12, 0xffff_ffff, &pool),
+            ResourceValue::Bool(true)
+        );
+        assert_eq!(
+            ResourceValue::decode(0x03, 0, &pool),
+            ResourceValue::Other {
+                data_type: 0x03,
+                data: 0
+            }
+        );
+    }
+}
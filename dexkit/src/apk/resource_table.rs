@@ -0,0 +1,384 @@
+use crate::apk::{
+    RES_STRING_POOL_TYPE, RES_TABLE_PACKAGE_TYPE, RES_TABLE_TYPE, RES_TABLE_TYPE_TYPE, ResChunk,
+    ResStringPool, ResourceValue, read_u16, read_u32,
+};
+use crate::errors::Error;
+use std::collections::{BTreeMap, HashMap};
+
+const TYPE_FLAG_SPARSE: u8 = 0x01;
+const TYPE_FLAG_OFFSET16: u8 = 0x02;
+const ENTRY_FLAG_COMPLEX: u16 = 0x0001;
+const ENTRY_FLAG_COMPACT: u16 = 0x0008;
+const NO_ENTRY: u32 = 0xffff_ffff;
+
+/// One resource of a `ResourceTable`, e.g. `R.string.app_name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceEntry {
+    id: u32,
+    package_name: String,
+    type_name: String,
+    name: String,
+    value: Option<ResourceValue>,
+}
+
+impl ResourceEntry {
+    /// resource id, e.g. 0x7f0f0012
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// package of the resource, e.g. "com.example"
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    /// resource type, e.g. "string"
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// entry name, e.g. "app_name"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// "type/name", e.g. "string/app_name"
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.type_name, self.name)
+    }
+
+    /// value of the first configuration defining the resource, usually the default one.
+    /// `None` for bags (styles, arrays, plurals, ...)
+    pub fn value(&self) -> Option<&ResourceValue> {
+        self.value.as_ref()
+    }
+}
+
+/// Resource ids and names decoded from resources.arsc.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceTable {
+    entries: BTreeMap<u32, ResourceEntry>,
+    // "type/name" and "package:type/name" -> id
+    names: HashMap<String, u32>,
+}
+
+impl ResourceTable {
+    /// Parse the content of a resources.arsc file.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        let table = ResChunk::read(data, 0)?;
+        if table.chunk_type != RES_TABLE_TYPE {
+            return Err(Error::ResourceError("not a resource table".to_string()));
+        }
+
+        let mut global_pool = ResStringPool::default();
+        let mut resources = Self::default();
+        for chunk in table.children() {
+            let chunk = chunk?;
+            match chunk.chunk_type {
+                RES_STRING_POOL_TYPE => global_pool = ResStringPool::parse(&chunk)?,
+                RES_TABLE_PACKAGE_TYPE => resources.parse_package(&chunk, &global_pool)?,
+                _ => {}
+            }
+        }
+        Ok(resources)
+    }
+
+    /// number of resources
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// resources in ascending id order
+    pub fn entries(&self) -> impl Iterator<Item = &ResourceEntry> {
+        self.entries.values()
+    }
+
+    /// Get a resource by id.
+    pub fn get(&self, id: u32) -> Option<&ResourceEntry> {
+        self.entries.get(&id)
+    }
+
+    /// Get the "type/name" of a resource id. e.g. 0x7f0f0012 -> "string/login_failed"
+    pub fn get_name(&self, id: u32) -> Option<String> {
+        self.get(id).map(ResourceEntry::full_name)
+    }
+
+    /// Get the id of a resource by name.
+    /// # Arguments
+    /// * `name` - "type/name", optionally prefixed with '@' and / or "package:".
+    ///   e.g. "string/login_failed", "@com.example:layout/activity_main"
+    pub fn get_id(&self, name: &str) -> Option<u32> {
+        self.names
+            .get(name.strip_prefix('@').unwrap_or(name))
+            .copied()
+    }
+
+    /// Get the string value of a resource, references to other resources are followed.
+    pub fn get_string(&self, id: u32) -> Option<&str> {
+        let mut id = id;
+        // bounded, references may form a cycle in a malformed table
+        for _ in 0..8 {
+            match self.get(id)?.value()? {
+                ResourceValue::String(value) => return Some(value),
+                ResourceValue::Reference(reference) => id = *reference,
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// internal use, parse a ResTable_package chunk
+    fn parse_package(
+        &mut self,
+        package: &ResChunk,
+        global_pool: &ResStringPool,
+    ) -> Result<(), Error> {
+        let package_id = package.u32_at(8)?;
+        let package_name = Self::read_package_name(package)?;
+        let type_strings = package.u32_at(268)? as usize;
+        let key_strings = package.u32_at(276)? as usize;
+        let type_id_offset = if package.header_size >= 288 {
+            package.u32_at(284)?
+        } else {
+            0
+        };
+        let type_names = ResStringPool::parse(&ResChunk::read(package.data, type_strings)?)?;
+        let key_names = ResStringPool::parse(&ResChunk::read(package.data, key_strings)?)?;
+
+        for chunk in package.children() {
+            let chunk = chunk?;
+            if chunk.chunk_type != RES_TABLE_TYPE_TYPE {
+                continue;
+            }
+            let type_id = chunk.u8_at(8)? as u32;
+            let type_name = type_id
+                .checked_sub(1 + type_id_offset)
+                .and_then(|idx| type_names.get(idx))
+                .unwrap_or_default();
+            for (entry_idx, offset) in Self::read_entry_offsets(&chunk)? {
+                let id = package_id << 24 | type_id << 16 | entry_idx;
+                if self.entries.contains_key(&id) {
+                    continue;
+                }
+                let (key, value) = Self::read_entry(chunk.data, offset, global_pool)?;
+                let name = key_names.get(key).unwrap_or_default();
+                let entry = ResourceEntry {
+                    id,
+                    package_name: package_name.clone(),
+                    type_name: type_name.to_string(),
+                    name: name.to_string(),
+                    value,
+                };
+                let full_name = entry.full_name();
+                self.names
+                    .insert(format!("{}:{}", package_name, full_name), id);
+                self.names.entry(full_name).or_insert(id);
+                self.entries.insert(id, entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// internal use, the package name is a NUL terminated utf16 string of 128 units
+    fn read_package_name(package: &ResChunk) -> Result<String, Error> {
+        let units = (0..128)
+            .map(|i| package.u16_at(12 + i * 2))
+            .take_while(|unit| !matches!(unit, Ok(0)))
+            .collect::<Result<Vec<u16>, Error>>()?;
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    /// internal use, (entry index, offset of the entry in the chunk) of the defined entries
+    fn read_entry_offsets(chunk: &ResChunk) -> Result<Vec<(u32, usize)>, Error> {
+        let flags = chunk.u8_at(9)?;
+        let entry_count = chunk.u32_at(12)? as usize;
+        let entries_start = chunk.u32_at(16)? as usize;
+        let base = chunk.header_size;
+
+        // entry_count is untrusted, a too large count fails on the offsets instead
+        let mut offsets = Vec::new();
+        for i in 0..entry_count {
+            if flags & TYPE_FLAG_SPARSE != 0 {
+                let idx = chunk.u16_at(base + i * 4)? as u32;
+                let offset = chunk.u16_at(base + i * 4 + 2)? as usize * 4;
+                offsets.push((idx, entries_start + offset));
+            } else if flags & TYPE_FLAG_OFFSET16 != 0 {
+                let offset = chunk.u16_at(base + i * 2)?;
+                if offset != 0xffff {
+                    offsets.push((i as u32, entries_start + offset as usize * 4));
+                }
+            } else {
+                let offset = chunk.u32_at(base + i * 4)?;
+                if offset != NO_ENTRY {
+                    offsets.push((i as u32, entries_start + offset as usize));
+                }
+            }
+        }
+        Ok(offsets)
+    }
+
+    /// internal use, (key string index, value) of the ResTable_entry at `offset`
+    fn read_entry(
+        data: &[u8],
+        offset: usize,
+        pool: &ResStringPool,
+    ) -> Result<(u32, Option<ResourceValue>), Error> {
+        let size = read_u16(data, offset)?;
+        let flags = read_u16(data, offset + 2)?;
+        if flags & ENTRY_FLAG_COMPACT != 0 {
+            // compact entry: key, flags with the data type in the high byte, data
+            let data_type = (flags >> 8) as u8;
+            let value = read_u32(data, offset + 4)?;
+            return Ok((
+                size as u32,
+                Some(ResourceValue::decode(data_type, value, pool)),
+            ));
+        }
+        let key = read_u32(data, offset + 4)?;
+        if flags & ENTRY_FLAG_COMPLEX != 0 {
+            return Ok((key, None));
+        }
+        let value = ResourceValue::read(data, offset + size as usize, pool)?;
+        Ok((key, Some(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apk::chunk_builder::{chunk, string_pool};
+
+    fn type_chunk(type_id: u8, entries: &[Option<(u32, u8, u32)>]) -> Vec<u8> {
+        let header_size = 8 + 12 + 4;
+        let mut offsets = Vec::new();
+        let mut content = Vec::new();
+        for entry in entries {
+            match entry {
+                Some((key, data_type, data)) => {
+                    offsets.extend_from_slice(&(content.len() as u32).to_le_bytes());
+                    content.extend_from_slice(&8u16.to_le_bytes());
+                    content.extend_from_slice(&0u16.to_le_bytes());
+                    content.extend_from_slice(&key.to_le_bytes());
+                    content.extend_from_slice(&8u16.to_le_bytes());
+                    content.push(0);
+                    content.push(*data_type);
+                    content.extend_from_slice(&data.to_le_bytes());
+                }
+                None => offsets.extend_from_slice(&NO_ENTRY.to_le_bytes()),
+            }
+        }
+        let entries_start = header_size + offsets.len();
+        let mut header = vec![type_id, 0, 0, 0];
+        header.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(entries_start as u32).to_le_bytes());
+        // empty ResTable_config, only its size
+        header.extend_from_slice(&4u32.to_le_bytes());
+        chunk(RES_TABLE_TYPE_TYPE, &header, &[offsets, content].concat())
+    }
+
+    fn package_chunk(id: u32, name: &str, types: &[&str], keys: &[&str], body: &[u8]) -> Vec<u8> {
+        let header_size = 288;
+        let type_pool = string_pool(types);
+        let key_pool = string_pool(keys);
+        let mut header = id.to_le_bytes().to_vec();
+        let mut name_units: Vec<u16> = name.encode_utf16().collect();
+        name_units.resize(128, 0);
+        name_units
+            .iter()
+            .for_each(|unit| header.extend_from_slice(&unit.to_le_bytes()));
+        header.extend_from_slice(&(header_size as u32).to_le_bytes());
+        header.extend_from_slice(&(types.len() as u32).to_le_bytes());
+        header.extend_from_slice(&((header_size + type_pool.len()) as u32).to_le_bytes());
+        header.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        chunk(
+            RES_TABLE_PACKAGE_TYPE,
+            &header,
+            &[type_pool, key_pool, body.to_vec()].concat(),
+        )
+    }
+
+    fn table() -> Vec<u8> {
+        let strings = type_chunk(2, &[Some((0, 0x03, 0)), None, Some((1, 0x01, 0x7f02_0000))]);
+        let layouts = type_chunk(1, &[Some((2, 0x03, 1))]);
+        let package = package_chunk(
+            0x7f,
+            "com.example",
+            &["layout", "string"],
+            &["login_failed", "retry", "activity_main"],
+            &[layouts, strings].concat(),
+        );
+        let pool = string_pool(&["Login failed", "res/layout/activity_main.xml"]);
+        chunk(
+            RES_TABLE_TYPE,
+            &1u32.to_le_bytes(),
+            &[pool, package].concat(),
+        )
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let resources = ResourceTable::parse(&table()).unwrap();
+        assert_eq!(resources.len(), 3);
+        assert_eq!(resources.get_id("string/login_failed"), Some(0x7f02_0000));
+        assert_eq!(resources.get_id("@string/retry"), Some(0x7f02_0002));
+        assert_eq!(
+            resources.get_id("com.example:layout/activity_main"),
+            Some(0x7f01_0000)
+        );
+        assert_eq!(resources.get_id("string/missing"), None);
+        assert_eq!(
+            resources.get_name(0x7f01_0000).as_deref(),
+            Some("layout/activity_main")
+        );
+        assert_eq!(resources.get_name(0x7f02_0001), None);
+    }
+
+    #[test]
+    fn test_entry_values() {
+        let resources = ResourceTable::parse(&table()).unwrap();
+        let entry = resources.get(0x7f02_0000).unwrap();
+        assert_eq!(entry.package_name(), "com.example");
+        assert_eq!(entry.type_name(), "string");
+        assert_eq!(entry.name(), "login_failed");
+        assert_eq!(resources.get_string(0x7f02_0000), Some("Login failed"));
+        // string/retry references string/login_failed
+        assert_eq!(resources.get_string(0x7f02_0002), Some("Login failed"));
+        assert_eq!(
+            resources.get_string(0x7f01_0000),
+            Some("res/layout/activity_main.xml")
+        );
+    }
+
+    #[test]
+    fn test_truncated_type_chunk() {
+        // a type chunk with only the ResChunk_header
+        let package = package_chunk(
+            0x7f,
+            "com.example",
+            &["string"],
+            &[],
+            &chunk(RES_TABLE_TYPE_TYPE, &[], &[]),
+        );
+        let pool = string_pool(&[]);
+        let data = chunk(
+            RES_TABLE_TYPE,
+            &1u32.to_le_bytes(),
+            &[pool, package].concat(),
+        );
+        assert!(matches!(
+            ResourceTable::parse(&data),
+            Err(Error::ResourceError(_))
+        ));
+    }
+
+    #[test]
+    fn test_not_a_table() {
+        let data = chunk(RES_STRING_POOL_TYPE, &[0; 20], &[]);
+        assert!(ResourceTable::parse(&data).is_err());
+    }
+}
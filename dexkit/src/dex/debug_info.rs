@@ -0,0 +1,276 @@
+use crate::dex::{CodeItem, DexFile, DexReader};
+use crate::errors::Error;
+use std::ops::RangeInclusive;
+
+/// debug_info_item of a method, decoded from its state machine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub line_start: u32,
+    /// names of the declared parameters, `this` excluded, None when stripped
+    pub parameter_names: Vec<Option<String>>,
+    /// positions in address order, a line may appear several times
+    pub positions: Vec<PositionEntry>,
+    /// local variables started in the method body, parameters included when named
+    pub locals: Vec<LocalVariable>,
+    /// source file set by DBG_SET_FILE, e.g. for code inlined from another file
+    pub source_file: Option<String>,
+}
+
+/// an entry of the line table, `address` is in 16-bit code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionEntry {
+    pub address: u32,
+    pub line: u32,
+}
+
+/// a local variable live in `start_address..end_address`, addresses are in 16-bit code units
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalVariable {
+    pub register: u32,
+    pub name: Option<String>,
+    /// type descriptor, e.g. "Ljava/lang/String;"
+    pub descriptor: Option<String>,
+    /// generic signature, e.g. "Ljava/util/List<Ljava/lang/String;>;"
+    pub signature: Option<String>,
+    pub start_address: u32,
+    pub end_address: u32,
+}
+
+impl DebugInfo {
+    /// the lowest to the highest line of the line table, None without positions
+    pub fn line_range(&self) -> Option<RangeInclusive<u32>> {
+        let min = self.positions.iter().map(|p| p.line).min()?;
+        let max = self.positions.iter().map(|p| p.line).max()?;
+        Some(min..=max)
+    }
+
+    /// the line of the instruction at `address`, None before the first position
+    pub fn line_at(&self, address: u32) -> Option<u32> {
+        self.positions
+            .iter()
+            .take_while(|p| p.address <= address)
+            .last()
+            .map(|p| p.line)
+    }
+
+    /// the line table contains `line`
+    pub fn has_line(&self, line: u32) -> bool {
+        self.positions.iter().any(|p| p.line == line)
+    }
+
+    /// internal use, run the state machine of the debug_info_item at `reader`.
+    /// `code_units` closes the locals still live at DBG_END_SEQUENCE.
+    pub(crate) fn read(
+        reader: &mut DexReader,
+        code_units: u32,
+        string: impl Fn(u32) -> Result<String, Error>,
+        type_descriptor: impl Fn(u32) -> Result<String, Error>,
+    ) -> Result<Self, Error> {
+        let optional =
+            |idx: Option<u32>, f: &dyn Fn(u32) -> Result<String, Error>| idx.map(f).transpose();
+        let line_start = reader.uleb128()?;
+        let parameters_size = reader.uleb128()?;
+        let parameter_names = (0..parameters_size)
+            .map(|_| optional(reader.uleb128p1()?, &string))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut info = DebugInfo {
+            line_start,
+            parameter_names,
+            ..Default::default()
+        };
+        let mut address = 0u32;
+        let mut line = line_start as i64;
+        // register -> index in `info.locals` of its last local
+        let mut live: Vec<(u32, usize)> = Vec::new();
+        let end_local = |info: &mut DebugInfo, live: &mut Vec<(u32, usize)>, register, address| {
+            if let Some(position) = live.iter().position(|&(r, _)| r == register) {
+                let (_, index) = live.remove(position);
+                info.locals[index].end_address = address;
+            }
+        };
+        let advance = |address: u32, delta: u32| {
+            address.checked_add(delta).ok_or_else(|| {
+                Error::DexParseError(format!(
+                    "debug info address 0x{:X} + {} overflows",
+                    address, delta
+                ))
+            })
+        };
+        loop {
+            let opcode = reader.u8()?;
+            match opcode {
+                // DBG_END_SEQUENCE
+                0x00 => break,
+                // DBG_ADVANCE_PC
+                0x01 => address = advance(address, reader.uleb128()?)?,
+                // DBG_ADVANCE_LINE
+                0x02 => line += reader.sleb128()? as i64,
+                // DBG_START_LOCAL, DBG_START_LOCAL_EXTENDED
+                0x03 | 0x04 => {
+                    let register = reader.uleb128()?;
+                    let name = optional(reader.uleb128p1()?, &string)?;
+                    let descriptor = optional(reader.uleb128p1()?, &type_descriptor)?;
+                    let signature = match opcode {
+                        0x04 => optional(reader.uleb128p1()?, &string)?,
+                        _ => None,
+                    };
+                    end_local(&mut info, &mut live, register, address);
+                    live.push((register, info.locals.len()));
+                    info.locals.push(LocalVariable {
+                        register,
+                        name,
+                        descriptor,
+                        signature,
+                        start_address: address,
+                        end_address: code_units,
+                    });
+                }
+                // DBG_END_LOCAL
+                0x05 => {
+                    let register = reader.uleb128()?;
+                    end_local(&mut info, &mut live, register, address);
+                }
+                // DBG_RESTART_LOCAL
+                0x06 => {
+                    let register = reader.uleb128()?;
+                    let last = info.locals.iter().rposition(|l| l.register == register);
+                    if let Some(last) = last
+                        && !live.iter().any(|&(r, _)| r == register)
+                    {
+                        let local = LocalVariable {
+                            start_address: address,
+                            end_address: code_units,
+                            ..info.locals[last].clone()
+                        };
+                        live.push((register, info.locals.len()));
+                        info.locals.push(local);
+                    }
+                }
+                // DBG_SET_PROLOGUE_END, DBG_SET_EPILOGUE_BEGIN
+                0x07 | 0x08 => {}
+                // DBG_SET_FILE
+                0x09 => info.source_file = optional(reader.uleb128p1()?, &string)?,
+                // special opcodes, advance both and emit a position
+                _ => {
+                    let adjusted = (opcode - 0x0a) as u32;
+                    line += -4 + (adjusted % 15) as i64;
+                    address = advance(address, adjusted / 15)?;
+                    info.positions.push(PositionEntry {
+                        address,
+                        line: line.max(0) as u32,
+                    });
+                }
+            }
+        }
+        Ok(info)
+    }
+}
+
+impl DexFile {
+    /// Decode the debug_info_item of a method body, None when stripped.
+    pub fn debug_info(&self, code: &CodeItem) -> Result<Option<DebugInfo>, Error> {
+        if code.debug_info_off == 0 {
+            return Ok(None);
+        }
+        let mut reader = self.reader(code.debug_info_off as usize);
+        DebugInfo::read(
+            &mut reader,
+            code.insns.len() as u32,
+            |idx| self.string(idx),
+            |idx| self.type_descriptor(idx),
+        )
+        .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &[u8], code_units: u32) -> DebugInfo {
+        let mut reader = DexReader::new(data, 0);
+        DebugInfo::read(
+            &mut reader,
+            code_units,
+            |idx| Ok(format!("s{}", idx)),
+            |idx| Ok(format!("Lt{};", idx)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_positions() {
+        // line_start 10, no parameters, lines 10, 12, 11 and 7 at 0, 3, 4 and 5
+        let data = [
+            10, 0, 0x0e, // line += 0
+            0x01, 3, 0x10, // pc += 3, line += 2
+            0x02, 0x7d, 0x1f, // line -= 3, pc += 1, line += 2
+            0x01, 1, 0x0a, // pc += 1, line -= 4
+            0x00,
+        ];
+        let info = read(&data, 8);
+        let lines: Vec<(u32, u32)> = info.positions.iter().map(|p| (p.address, p.line)).collect();
+        assert_eq!(lines, vec![(0, 10), (3, 12), (4, 11), (5, 7)]);
+        assert_eq!(info.line_range(), Some(7..=12));
+        assert_eq!(info.line_at(3), Some(12));
+        assert_eq!(info.line_at(100), Some(7));
+        assert!(info.has_line(11));
+        assert!(!info.has_line(9));
+    }
+
+    #[test]
+    fn test_locals_and_parameters() {
+        // line_start 1, parameters "s1" and stripped
+        let data = [
+            1, 2, 2, 0, // parameter names
+            0x03, 2, 4, 6, // v2 "s3" Lt5; at 0
+            0x01, 4, 0x05, 2, // pc += 4, end v2
+            0x01, 2, 0x06, 2, // pc += 2, restart v2
+            0x04, 3, 1, 1, 8, // v3 "s0" Lt0; "s7" at 6
+            0x09, 10, // source file "s9"
+            0x00,
+        ];
+        let info = read(&data, 9);
+        assert_eq!(info.parameter_names, vec![Some("s1".to_string()), None]);
+        assert_eq!(info.source_file.as_deref(), Some("s9"));
+        let locals: Vec<(u32, &str, u32, u32)> = info
+            .locals
+            .iter()
+            .map(|l| {
+                let name = l.name.as_deref().unwrap();
+                (l.register, name, l.start_address, l.end_address)
+            })
+            .collect();
+        assert_eq!(
+            locals,
+            vec![(2, "s3", 0, 4), (2, "s3", 6, 9), (3, "s0", 6, 9)]
+        );
+        assert_eq!(info.locals[0].descriptor.as_deref(), Some("Lt5;"));
+        assert_eq!(info.locals[2].signature.as_deref(), Some("s7"));
+    }
+
+    #[test]
+    fn test_address_overflow() {
+        let overflow = |data: &[u8]| {
+            let mut reader = DexReader::new(data, 0);
+            let result = DebugInfo::read(
+                &mut reader,
+                1,
+                |idx| Ok(format!("s{}", idx)),
+                |idx| Ok(format!("Lt{};", idx)),
+            );
+            matches!(result, Err(Error::DexParseError(_)))
+        };
+        // pc += u32::MAX, then pc += 1
+        assert!(overflow(&[
+            1, 0, 0x01, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x01, 1, 0x00
+        ]));
+        // pc += u32::MAX, then a special opcode advancing pc by 1
+        assert!(overflow(&[
+            1, 0, 0x01, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x19, 0x00
+        ]));
+        // pc += u32::MAX alone still fits
+        assert!(!overflow(&[1, 0, 0x01, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x00]));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::dex::{ClassDef, DexFile, DexWriter};
+use crate::errors::Error;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// Classes written by `DexkitBridge::export_classes` besides the requested ones.
+/// Only classes defined in the loaded dex files can be written, the others stay references.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportDependencies {
+    /// only the requested classes
+    #[default]
+    None,
+    /// superclasses and interfaces, transitively
+    Supertypes,
+    /// every class referenced by the exported classes, transitively
+    All,
+}
+
+/// internal use, the classes to export for `roots`, (dex id, type index) of each requested class.
+/// A class defined in several dex files is taken from the first one, as the runtime does.
+pub(crate) fn export_selection<'d>(
+    dex_files: &'d [DexFile],
+    roots: &[(u32, u32)],
+    dependencies: ExportDependencies,
+) -> Result<Vec<(&'d DexFile, ClassDef)>, Error> {
+    let mut selected = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut queue = VecDeque::new();
+    for &(dex_id, type_idx) in roots {
+        let dex = dex_files
+            .iter()
+            .find(|dex| dex.dex_id() == dex_id)
+            .ok_or_else(|| Error::DexWriteError(format!("dex {} is not loaded", dex_id)))?;
+        let class_def = dex.class_def_by_type(type_idx)?.ok_or_else(|| {
+            Error::DexWriteError(format!(
+                "type {} is not defined in dex {}",
+                type_idx, dex_id
+            ))
+        })?;
+        if visited.insert(dex.type_descriptor(type_idx)?) {
+            queue.push_back((dex, class_def));
+        }
+    }
+    if dependencies == ExportDependencies::None {
+        return Ok(queue.into_iter().collect());
+    }
+
+    let mut definitions: HashMap<String, (&DexFile, ClassDef)> = HashMap::new();
+    for dex in dex_files {
+        for class_def in dex.class_defs()? {
+            definitions
+                .entry(dex.type_descriptor(class_def.class_idx)?)
+                .or_insert((dex, class_def));
+        }
+    }
+    while let Some((dex, class_def)) = queue.pop_front() {
+        let referenced = match dependencies {
+            ExportDependencies::All => DexWriter::referenced_types(dex, &class_def)?,
+            _ => class_def
+                .superclass_idx
+                .into_iter()
+                .chain(dex.class_interfaces(&class_def)?)
+                .map(|type_idx| dex.type_descriptor(type_idx))
+                .collect::<Result<BTreeSet<_>, Error>>()?,
+        };
+        for descriptor in referenced {
+            // array types are exported through their element class
+            let element = descriptor.trim_start_matches('[');
+            if let Some(&definition) = definitions.get(element)
+                && visited.insert(element.to_string())
+            {
+                queue.push_back(definition);
+            }
+        }
+        selected.push((dex, class_def));
+    }
+    Ok(selected)
+}
@@ -0,0 +1,895 @@
+use crate::apk::ApkArchive;
+use crate::dex::{DexReader, EncodedAnnotation, EncodedValue, Instructions};
+use crate::errors::Error;
+use crate::uitls::MUtf8;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::Path;
+
+/// `NO_INDEX` marker of the dex format
+pub const DEX_NO_INDEX: u32 = 0xffff_ffff;
+
+const DEX_MAGIC: &[u8] = b"dex\n";
+const ENDIAN_CONSTANT: u32 = 0x1234_5678;
+const HEADER_SIZE: usize = 0x70;
+
+/// header_item of a dex file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DexHeader {
+    /// format version from the magic, e.g. "035"
+    pub version: String,
+    pub checksum: u32,
+    pub signature: [u8; 20],
+    pub file_size: u32,
+    pub header_size: u32,
+    pub endian_tag: u32,
+    pub link_size: u32,
+    pub link_off: u32,
+    pub map_off: u32,
+    pub string_ids_size: u32,
+    pub string_ids_off: u32,
+    pub type_ids_size: u32,
+    pub type_ids_off: u32,
+    pub proto_ids_size: u32,
+    pub proto_ids_off: u32,
+    pub field_ids_size: u32,
+    pub field_ids_off: u32,
+    pub method_ids_size: u32,
+    pub method_ids_off: u32,
+    pub class_defs_size: u32,
+    pub class_defs_off: u32,
+    pub data_size: u32,
+    pub data_off: u32,
+}
+
+/// map_item of the map_list, one section of a dex file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapItem {
+    pub item_type: u16,
+    /// number of items in the section
+    pub size: u32,
+    pub offset: u32,
+}
+
+impl MapItem {
+    /// name of `item_type` in the dex format, e.g. "code_item"
+    pub fn type_name(&self) -> &'static str {
+        match self.item_type {
+            0x0000 => "header_item",
+            0x0001 => "string_id_item",
+            0x0002 => "type_id_item",
+            0x0003 => "proto_id_item",
+            0x0004 => "field_id_item",
+            0x0005 => "method_id_item",
+            0x0006 => "class_def_item",
+            0x0007 => "call_site_id_item",
+            0x0008 => "method_handle_item",
+            0x1000 => "map_list",
+            0x1001 => "type_list",
+            0x1002 => "annotation_set_ref_list",
+            0x1003 => "annotation_set_item",
+            0x2000 => "class_data_item",
+            0x2001 => "code_item",
+            0x2002 => "string_data_item",
+            0x2003 => "debug_info_item",
+            0x2004 => "annotation_item",
+            0x2005 => "encoded_array_item",
+            0x2006 => "annotations_directory_item",
+            0xf000 => "hiddenapi_class_data_item",
+            _ => "unknown",
+        }
+    }
+}
+
+/// field_id_item, indexes into the id tables of the same dex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldId {
+    pub class_idx: u32,
+    pub type_idx: u32,
+    pub name_idx: u32,
+}
+
+/// method_id_item, indexes into the id tables of the same dex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodId {
+    pub class_idx: u32,
+    pub proto_idx: u32,
+    pub name_idx: u32,
+}
+
+/// proto_id_item, indexes into the id tables of the same dex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtoId {
+    pub shorty_idx: u32,
+    pub return_type_idx: u32,
+    pub parameters_off: u32,
+}
+
+/// class_def_item, `None` for `NO_INDEX` references
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassDef {
+    pub class_idx: u32,
+    pub access_flags: u32,
+    pub superclass_idx: Option<u32>,
+    pub interfaces_off: u32,
+    pub source_file_idx: Option<u32>,
+    pub annotations_off: u32,
+    pub class_data_off: u32,
+    pub static_values_off: u32,
+}
+
+/// encoded_field of a class_data_item, with the absolute field index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodedField {
+    pub field_idx: u32,
+    pub access_flags: u32,
+}
+
+/// encoded_method of a class_data_item, with the absolute method index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodedMethod {
+    pub method_idx: u32,
+    pub access_flags: u32,
+    pub code_off: u32,
+}
+
+/// class_data_item, the members defined by a class
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassDataItem {
+    pub static_fields: Vec<EncodedField>,
+    pub instance_fields: Vec<EncodedField>,
+    pub direct_methods: Vec<EncodedMethod>,
+    pub virtual_methods: Vec<EncodedMethod>,
+}
+
+impl ClassDataItem {
+    /// direct and virtual methods
+    pub fn methods(&self) -> impl Iterator<Item = &EncodedMethod> {
+        self.direct_methods
+            .iter()
+            .chain(self.virtual_methods.iter())
+    }
+
+    /// static and instance fields
+    pub fn fields(&self) -> impl Iterator<Item = &EncodedField> {
+        self.static_fields.iter().chain(self.instance_fields.iter())
+    }
+}
+
+/// code_item, the body of a method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeItem {
+    /// offset of the code_item in the dex file
+    pub offset: u32,
+    pub registers_size: u16,
+    pub ins_size: u16,
+    pub outs_size: u16,
+    pub tries_size: u16,
+    pub debug_info_off: u32,
+    pub insns: Vec<u16>,
+    pub tries: Vec<TryItem>,
+    /// encoded_catch_handler_list, referenced by `TryItem::handler_off`
+    pub handlers: Vec<CatchHandler>,
+}
+
+/// try_item of a code_item, addresses are in 16-bit code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TryItem {
+    pub start_addr: u32,
+    pub insn_count: u16,
+    /// offset of the handler in the encoded_catch_handler_list, see `CatchHandler::offset`
+    pub handler_off: u16,
+}
+
+/// encoded_catch_handler of a code_item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchHandler {
+    /// offset of the handler in the encoded_catch_handler_list
+    pub offset: u32,
+    /// (type_ids index of the caught exception, handler address)
+    pub catches: Vec<(u32, u32)>,
+    pub catch_all_addr: Option<u32>,
+}
+
+/// annotations_directory_item of a class, offsets of annotation_set_item and
+/// annotation_set_ref_list, 0 for none
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnotationsDirectory {
+    pub class_annotations_off: u32,
+    /// (field_ids index, annotation_set_item offset)
+    pub fields: Vec<(u32, u32)>,
+    /// (method_ids index, annotation_set_item offset)
+    pub methods: Vec<(u32, u32)>,
+    /// (method_ids index, annotation_set_ref_list offset)
+    pub parameters: Vec<(u32, u32)>,
+}
+
+/// annotation_item, `visibility` is 0 build, 1 runtime, 2 system
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationItem {
+    pub visibility: u8,
+    pub annotation: EncodedAnnotation,
+}
+
+impl CodeItem {
+    /// decode the instructions of the method body
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions::new(&self.insns)
+    }
+}
+
+/// A dex file parsed on the Rust side.
+///
+/// Only the header is read eagerly, ids and items are decoded on access.
+/// The `dex_id` matches the id DexKit assigns to the same dex, see `DexFile::from_apk`.
+pub struct DexFile {
+    dex_id: u32,
+    name: String,
+    data: Vec<u8>,
+    header: DexHeader,
+    // type_ids index -> class_defs index, built on first lookup
+    class_def_index: OnceCell<HashMap<u32, u32>>,
+}
+
+impl Debug for DexFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DexFile")
+            .field("dex_id", &self.dex_id)
+            .field("name", &self.name)
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+impl DexFile {
+    /// Parse a dex file from its bytes.
+    /// # Arguments
+    /// * `dex_id` - The id of the dex in its container.
+    /// * `name` - The name of the dex, e.g. "classes2.dex"
+    /// * `data` - The content of the dex file.
+    pub fn parse<S: Into<String>>(dex_id: u32, name: S, data: Vec<u8>) -> Result<Self, Error> {
+        let header = Self::parse_header(&data)?;
+        Ok(Self {
+            dex_id,
+            name: name.into(),
+            data,
+            header,
+            class_def_index: OnceCell::new(),
+        })
+    }
+
+    /// Read all `classes*.dex` of an apk, in the order DexKit loads them:
+    /// "classes.dex", "classes2.dex", "classes3.dex", ...
+    pub fn from_apk<P: AsRef<Path>>(apk_path: P) -> Result<Vec<Self>, Error> {
+        let mut archive = ApkArchive::open(apk_path)?;
+        let names = archive.dex_entry_names();
+        let mut dex_files = Vec::with_capacity(names.len());
+        for (dex_id, name) in names.into_iter().enumerate() {
+            let data = archive.read(&name)?;
+            dex_files.push(Self::parse(dex_id as u32, name, data)?);
+        }
+        Ok(dex_files)
+    }
+
+    /// id of the dex, see `BaseData::dex_id`
+    pub fn dex_id(&self) -> u32 {
+        self.dex_id
+    }
+
+    /// entry name of the dex in its container, e.g. "classes2.dex"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// raw content of the dex file
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn header(&self) -> &DexHeader {
+        &self.header
+    }
+
+    /// Decode the map_list, the sections of the dex in file order.
+    pub fn map_items(&self) -> Result<Vec<MapItem>, Error> {
+        if self.header.map_off == 0 {
+            return Ok(Vec::new());
+        }
+        let mut reader = self.reader(self.header.map_off as usize);
+        let size = reader.u32()?;
+        (0..size)
+            .map(|_| -> Result<MapItem, Error> {
+                let item_type = reader.u16()?;
+                reader.u16()?;
+                Ok(MapItem {
+                    item_type,
+                    size: reader.u32()?,
+                    offset: reader.u32()?,
+                })
+            })
+            .collect()
+    }
+
+    /// number of entries in string_ids
+    pub fn string_count(&self) -> u32 {
+        self.header.string_ids_size
+    }
+
+    /// Decode the string at `idx` of string_ids.
+    pub fn string(&self, idx: u32) -> Result<String, Error> {
+        let off = self.id_item_off(
+            idx,
+            self.header.string_ids_size,
+            self.header.string_ids_off,
+            4,
+            "string",
+        )?;
+        let string_data_off = self.reader(off).u32()? as usize;
+        let mut reader = self.reader(string_data_off);
+        let utf16_size = reader.uleb128()? as usize;
+        let bytes = &self.data[reader.pos()..];
+        // MUtf8 does not combine surrogate pairs, decode them through utf16 instead
+        MUtf8::decode(bytes).or_else(|_| Ok(Self::decode_mutf8_lossy(bytes, utf16_size)))
+    }
+
+    /// Decode all strings of string_ids, in index order.
+    pub fn strings(&self) -> Result<Vec<String>, Error> {
+        (0..self.string_count())
+            .map(|idx| self.string(idx))
+            .collect()
+    }
+
+    /// number of entries in type_ids
+    pub fn type_count(&self) -> u32 {
+        self.header.type_ids_size
+    }
+
+    /// Get the descriptor of the type at `idx` of type_ids. e.g. "Ljava/lang/String;"
+    pub fn type_descriptor(&self, idx: u32) -> Result<String, Error> {
+        let off = self.id_item_off(
+            idx,
+            self.header.type_ids_size,
+            self.header.type_ids_off,
+            4,
+            "type",
+        )?;
+        let descriptor_idx = self.reader(off).u32()?;
+        self.string(descriptor_idx)
+    }
+
+    /// number of entries in proto_ids
+    pub fn proto_count(&self) -> u32 {
+        self.header.proto_ids_size
+    }
+
+    pub fn proto_id(&self, idx: u32) -> Result<ProtoId, Error> {
+        let off = self.id_item_off(
+            idx,
+            self.header.proto_ids_size,
+            self.header.proto_ids_off,
+            12,
+            "proto",
+        )?;
+        let mut reader = self.reader(off);
+        Ok(ProtoId {
+            shorty_idx: reader.u32()?,
+            return_type_idx: reader.u32()?,
+            parameters_off: reader.u32()?,
+        })
+    }
+
+    /// Get the type_ids indexes of the parameters of a proto.
+    pub fn proto_parameters(&self, proto: &ProtoId) -> Result<Vec<u32>, Error> {
+        self.type_list(proto.parameters_off)
+    }
+
+    /// number of entries in field_ids
+    pub fn field_count(&self) -> u32 {
+        self.header.field_ids_size
+    }
+
+    pub fn field_id(&self, idx: u32) -> Result<FieldId, Error> {
+        let off = self.id_item_off(
+            idx,
+            self.header.field_ids_size,
+            self.header.field_ids_off,
+            8,
+            "field",
+        )?;
+        let mut reader = self.reader(off);
+        Ok(FieldId {
+            class_idx: reader.u16()? as u32,
+            type_idx: reader.u16()? as u32,
+            name_idx: reader.u32()?,
+        })
+    }
+
+    /// number of entries in method_ids
+    pub fn method_count(&self) -> u32 {
+        self.header.method_ids_size
+    }
+
+    pub fn method_id(&self, idx: u32) -> Result<MethodId, Error> {
+        let off = self.id_item_off(
+            idx,
+            self.header.method_ids_size,
+            self.header.method_ids_off,
+            8,
+            "method",
+        )?;
+        let mut reader = self.reader(off);
+        Ok(MethodId {
+            class_idx: reader.u16()? as u32,
+            proto_idx: reader.u16()? as u32,
+            name_idx: reader.u32()?,
+        })
+    }
+
+    /// number of entries in class_defs
+    pub fn class_def_count(&self) -> u32 {
+        self.header.class_defs_size
+    }
+
+    pub fn class_def(&self, idx: u32) -> Result<ClassDef, Error> {
+        let off = self.id_item_off(
+            idx,
+            self.header.class_defs_size,
+            self.header.class_defs_off,
+            32,
+            "class_def",
+        )?;
+        let mut reader = self.reader(off);
+        let no_index = |idx: u32| (idx != DEX_NO_INDEX).then_some(idx);
+        Ok(ClassDef {
+            class_idx: reader.u32()?,
+            access_flags: reader.u32()?,
+            superclass_idx: no_index(reader.u32()?),
+            interfaces_off: reader.u32()?,
+            source_file_idx: no_index(reader.u32()?),
+            annotations_off: reader.u32()?,
+            class_data_off: reader.u32()?,
+            static_values_off: reader.u32()?,
+        })
+    }
+
+    pub fn class_defs(&self) -> Result<Vec<ClassDef>, Error> {
+        (0..self.class_def_count())
+            .map(|idx| self.class_def(idx))
+            .collect()
+    }
+
+    /// Get the class_def of the class whose type_ids index is `type_idx`,
+    /// `None` if the type is not defined in this dex.
+    pub fn class_def_by_type(&self, type_idx: u32) -> Result<Option<ClassDef>, Error> {
+        let index = match self.class_def_index.get() {
+            Some(index) => index,
+            None => {
+                let index = self
+                    .class_defs()?
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, class_def)| (class_def.class_idx, idx as u32))
+                    .collect();
+                self.class_def_index.get_or_init(|| index)
+            }
+        };
+        index
+            .get(&type_idx)
+            .map(|&idx| self.class_def(idx))
+            .transpose()
+    }
+
+    /// Get the encoded_method of the method at `method_idx` of method_ids,
+    /// `None` if the method is not defined in this dex.
+    pub fn encoded_method(&self, method_idx: u32) -> Result<Option<EncodedMethod>, Error> {
+        let method_id = self.method_id(method_idx)?;
+        let Some(class_def) = self.class_def_by_type(method_id.class_idx)? else {
+            return Ok(None);
+        };
+        let Some(class_data) = self.class_data(&class_def)? else {
+            return Ok(None);
+        };
+        Ok(class_data
+            .methods()
+            .find(|method| method.method_idx == method_idx)
+            .copied())
+    }
+
+    /// Decode the body of the method at `method_idx` of method_ids,
+    /// `None` for abstract, native and external methods.
+    pub fn method_code(&self, method_idx: u32) -> Result<Option<CodeItem>, Error> {
+        match self.encoded_method(method_idx)? {
+            Some(method) => self.code_item(method.code_off),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the type_ids indexes of the interfaces a class implements.
+    pub fn class_interfaces(&self, class_def: &ClassDef) -> Result<Vec<u32>, Error> {
+        self.type_list(class_def.interfaces_off)
+    }
+
+    /// Decode the class_data_item of a class, `None` for classes without members.
+    pub fn class_data(&self, class_def: &ClassDef) -> Result<Option<ClassDataItem>, Error> {
+        if class_def.class_data_off == 0 {
+            return Ok(None);
+        }
+        let mut reader = self.reader(class_def.class_data_off as usize);
+        let static_fields_size = reader.uleb128()?;
+        let instance_fields_size = reader.uleb128()?;
+        let direct_methods_size = reader.uleb128()?;
+        let virtual_methods_size = reader.uleb128()?;
+
+        let mut read_fields = |size: u32| -> Result<Vec<EncodedField>, Error> {
+            let mut field_idx = 0u32;
+            (0..size)
+                .map(|_| -> Result<EncodedField, Error> {
+                    field_idx = field_idx.wrapping_add(reader.uleb128()?);
+                    Ok(EncodedField {
+                        field_idx,
+                        access_flags: reader.uleb128()?,
+                    })
+                })
+                .collect()
+        };
+        let static_fields = read_fields(static_fields_size)?;
+        let instance_fields = read_fields(instance_fields_size)?;
+
+        let mut read_methods = |size: u32| -> Result<Vec<EncodedMethod>, Error> {
+            let mut method_idx = 0u32;
+            (0..size)
+                .map(|_| -> Result<EncodedMethod, Error> {
+                    method_idx = method_idx.wrapping_add(reader.uleb128()?);
+                    Ok(EncodedMethod {
+                        method_idx,
+                        access_flags: reader.uleb128()?,
+                        code_off: reader.uleb128()?,
+                    })
+                })
+                .collect()
+        };
+        let direct_methods = read_methods(direct_methods_size)?;
+        let virtual_methods = read_methods(virtual_methods_size)?;
+
+        Ok(Some(ClassDataItem {
+            static_fields,
+            instance_fields,
+            direct_methods,
+            virtual_methods,
+        }))
+    }
+
+    /// Decode the initial values of the static fields of a class,
+    /// in the order of `ClassDataItem::static_fields`. Trailing fields with default values are omitted.
+    pub fn static_values(&self, class_def: &ClassDef) -> Result<Vec<EncodedValue>, Error> {
+        if class_def.static_values_off == 0 {
+            return Ok(Vec::new());
+        }
+        EncodedValue::read_array(&mut self.reader(class_def.static_values_off as usize))
+    }
+
+    /// Decode the code_item at `code_off`, `None` for abstract and native methods.
+    pub fn code_item(&self, code_off: u32) -> Result<Option<CodeItem>, Error> {
+        if code_off == 0 {
+            return Ok(None);
+        }
+        let mut reader = self.reader(code_off as usize);
+        let registers_size = reader.u16()?;
+        let ins_size = reader.u16()?;
+        let outs_size = reader.u16()?;
+        let tries_size = reader.u16()?;
+        let debug_info_off = reader.u32()?;
+        let insns_size = reader.u32()? as usize;
+        let insns = reader
+            .bytes(insns_size * 2)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        let mut tries = Vec::with_capacity(tries_size as usize);
+        let mut handlers = Vec::new();
+        if tries_size > 0 {
+            // padding to keep the try_items 4-byte aligned
+            if insns_size % 2 == 1 {
+                reader.u16()?;
+            }
+            for _ in 0..tries_size {
+                tries.push(TryItem {
+                    start_addr: reader.u32()?,
+                    insn_count: reader.u16()?,
+                    handler_off: reader.u16()?,
+                });
+            }
+            let list_start = reader.pos();
+            let size = reader.uleb128()?;
+            for _ in 0..size {
+                let offset = (reader.pos() - list_start) as u32;
+                let catch_size = reader.sleb128()?;
+                let catches = (0..catch_size.unsigned_abs())
+                    .map(|_| -> Result<(u32, u32), Error> {
+                        Ok((reader.uleb128()?, reader.uleb128()?))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let catch_all_addr = if catch_size <= 0 {
+                    Some(reader.uleb128()?)
+                } else {
+                    None
+                };
+                handlers.push(CatchHandler {
+                    offset,
+                    catches,
+                    catch_all_addr,
+                });
+            }
+        }
+        Ok(Some(CodeItem {
+            offset: code_off,
+            registers_size,
+            ins_size,
+            outs_size,
+            tries_size,
+            debug_info_off,
+            insns,
+            tries,
+            handlers,
+        }))
+    }
+
+    /// Decode the annotations_directory_item of a class, `None` for classes without annotations.
+    pub fn annotations_directory(
+        &self,
+        class_def: &ClassDef,
+    ) -> Result<Option<AnnotationsDirectory>, Error> {
+        if class_def.annotations_off == 0 {
+            return Ok(None);
+        }
+        let mut reader = self.reader(class_def.annotations_off as usize);
+        let class_annotations_off = reader.u32()?;
+        let fields_size = reader.u32()?;
+        let methods_size = reader.u32()?;
+        let parameters_size = reader.u32()?;
+        let mut read_pairs = |size: u32| -> Result<Vec<(u32, u32)>, Error> {
+            (0..size)
+                .map(|_| -> Result<(u32, u32), Error> { Ok((reader.u32()?, reader.u32()?)) })
+                .collect()
+        };
+        Ok(Some(AnnotationsDirectory {
+            class_annotations_off,
+            fields: read_pairs(fields_size)?,
+            methods: read_pairs(methods_size)?,
+            parameters: read_pairs(parameters_size)?,
+        }))
+    }
+
+    /// Decode the annotation_set_item at `off`, empty for offset 0.
+    pub fn annotation_set(&self, off: u32) -> Result<Vec<AnnotationItem>, Error> {
+        if off == 0 {
+            return Ok(Vec::new());
+        }
+        let mut reader = self.reader(off as usize);
+        let size = reader.u32()?;
+        (0..size)
+            .map(|_| -> Result<AnnotationItem, Error> {
+                let mut item = self.reader(reader.u32()? as usize);
+                Ok(AnnotationItem {
+                    visibility: item.u8()?,
+                    annotation: EncodedValue::read_annotation(&mut item)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Decode the annotation_set_ref_list at `off`, the annotation_set_item offsets
+    /// of each parameter.
+    pub fn annotation_set_ref_list(&self, off: u32) -> Result<Vec<u32>, Error> {
+        if off == 0 {
+            return Ok(Vec::new());
+        }
+        let mut reader = self.reader(off as usize);
+        let size = reader.u32()?;
+        (0..size).map(|_| reader.u32()).collect()
+    }
+
+    /// Raw `dalvik.annotation.Signature` of a class, None for non-generic classes.
+    pub fn class_signature(&self, class_def: &ClassDef) -> Result<Option<String>, Error> {
+        match self.annotations_directory(class_def)? {
+            Some(directory) => self.signature_annotation(directory.class_annotations_off),
+            None => Ok(None),
+        }
+    }
+
+    /// Raw `dalvik.annotation.Signature` of a method defined by `class_def`.
+    pub fn method_signature(
+        &self,
+        class_def: &ClassDef,
+        method_idx: u32,
+    ) -> Result<Option<String>, Error> {
+        let Some(directory) = self.annotations_directory(class_def)? else {
+            return Ok(None);
+        };
+        match directory.methods.iter().find(|(idx, _)| *idx == method_idx) {
+            Some(&(_, off)) => self.signature_annotation(off),
+            None => Ok(None),
+        }
+    }
+
+    /// Raw `dalvik.annotation.Signature` of a field defined by `class_def`.
+    pub fn field_signature(
+        &self,
+        class_def: &ClassDef,
+        field_idx: u32,
+    ) -> Result<Option<String>, Error> {
+        let Some(directory) = self.annotations_directory(class_def)? else {
+            return Ok(None);
+        };
+        match directory.fields.iter().find(|(idx, _)| *idx == field_idx) {
+            Some(&(_, off)) => self.signature_annotation(off),
+            None => Ok(None),
+        }
+    }
+
+    /// Exception type descriptors of the `dalvik.annotation.Throws` of a method defined by
+    /// `class_def`, the `throws` clause of the source, empty if none.
+    pub fn method_throws(
+        &self,
+        class_def: &ClassDef,
+        method_idx: u32,
+    ) -> Result<Vec<String>, Error> {
+        let Some(directory) = self.annotations_directory(class_def)? else {
+            return Ok(Vec::new());
+        };
+        let Some(&(_, off)) = directory.methods.iter().find(|(idx, _)| *idx == method_idx) else {
+            return Ok(Vec::new());
+        };
+        for item in self.annotation_set(off)? {
+            let annotation = &item.annotation;
+            if self.type_descriptor(annotation.type_idx)? != "Ldalvik/annotation/Throws;" {
+                continue;
+            }
+            for (name_idx, value) in &annotation.elements {
+                if let EncodedValue::Array(types) = value
+                    && self.string(*name_idx)? == "value"
+                {
+                    return types
+                        .iter()
+                        .filter_map(|value| match value {
+                            EncodedValue::Type(idx) => Some(self.type_descriptor(*idx)),
+                            _ => None,
+                        })
+                        .collect();
+                }
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// internal use, the Signature annotation of the annotation_set_item at `off`,
+    /// its value is split into an array of strings
+    fn signature_annotation(&self, off: u32) -> Result<Option<String>, Error> {
+        for item in self.annotation_set(off)? {
+            let annotation = &item.annotation;
+            if self.type_descriptor(annotation.type_idx)? != "Ldalvik/annotation/Signature;" {
+                continue;
+            }
+            for (name_idx, value) in &annotation.elements {
+                if self.string(*name_idx)? != "value" {
+                    continue;
+                }
+                let EncodedValue::Array(parts) = value else {
+                    continue;
+                };
+                let mut signature = String::new();
+                for part in parts {
+                    if let EncodedValue::String(idx) = part {
+                        signature.push_str(&self.string(*idx)?);
+                    }
+                }
+                return Ok(Some(signature));
+            }
+        }
+        Ok(None)
+    }
+
+    /// internal use, cursor at `pos` of the dex file
+    pub(crate) fn reader(&self, pos: usize) -> DexReader<'_> {
+        DexReader::new(&self.data, pos)
+    }
+
+    /// internal use, decode a type_list, empty for offset 0
+    fn type_list(&self, off: u32) -> Result<Vec<u32>, Error> {
+        if off == 0 {
+            return Ok(Vec::new());
+        }
+        let mut reader = self.reader(off as usize);
+        let size = reader.u32()?;
+        (0..size)
+            .map(|_| -> Result<u32, Error> { Ok(reader.u16()? as u32) })
+            .collect()
+    }
+
+    /// internal use, offset of the `idx` item of an id table
+    fn id_item_off(
+        &self,
+        idx: u32,
+        size: u32,
+        table_off: u32,
+        item_size: usize,
+        kind: &str,
+    ) -> Result<usize, Error> {
+        if idx >= size {
+            return Err(Error::DexParseError(format!(
+                "{} index {} out of range, {}: {} ids",
+                kind, idx, self.name, size
+            )));
+        }
+        Ok(table_off as usize + idx as usize * item_size)
+    }
+
+    /// internal use, decode MUTF-8 through utf16 code units, used for surrogate pairs
+    fn decode_mutf8_lossy(bytes: &[u8], utf16_size: usize) -> String {
+        let mut units: Vec<u16> = Vec::with_capacity(utf16_size);
+        let mut i = 0;
+        while i < bytes.len() && bytes[i] != 0 {
+            let a = bytes[i] as u16;
+            let b = bytes.get(i + 1).map_or(0, |&b| b as u16 & 0x3f);
+            let c = bytes.get(i + 2).map_or(0, |&c| c as u16 & 0x3f);
+            if a < 0x80 {
+                units.push(a);
+                i += 1;
+            } else if a & 0xe0 == 0xc0 {
+                units.push((a & 0x1f) << 6 | b);
+                i += 2;
+            } else {
+                units.push((a & 0x0f) << 12 | b << 6 | c);
+                i += 3;
+            }
+        }
+        String::from_utf16_lossy(&units)
+    }
+
+    /// internal use, parse and validate the header_item
+    fn parse_header(data: &[u8]) -> Result<DexHeader, Error> {
+        if data.len() < HEADER_SIZE || !data.starts_with(DEX_MAGIC) {
+            return Err(Error::DexParseError("not a dex file".to_string()));
+        }
+        let version = String::from_utf8_lossy(&data[4..7]).to_string();
+        let mut reader = DexReader::new(data, 8);
+        let checksum = reader.u32()?;
+        let mut signature = [0u8; 20];
+        signature.copy_from_slice(reader.bytes(20)?);
+        let file_size = reader.u32()?;
+        let header_size = reader.u32()?;
+        let endian_tag = reader.u32()?;
+        if endian_tag != ENDIAN_CONSTANT {
+            return Err(Error::DexParseError(format!(
+                "unsupported endian tag 0x{:X}",
+                endian_tag
+            )));
+        }
+        Ok(DexHeader {
+            version,
+            checksum,
+            signature,
+            file_size,
+            header_size,
+            endian_tag,
+            link_size: reader.u32()?,
+            link_off: reader.u32()?,
+            map_off: reader.u32()?,
+            string_ids_size: reader.u32()?,
+            string_ids_off: reader.u32()?,
+            type_ids_size: reader.u32()?,
+            type_ids_off: reader.u32()?,
+            proto_ids_size: reader.u32()?,
+            proto_ids_off: reader.u32()?,
+            field_ids_size: reader.u32()?,
+            field_ids_off: reader.u32()?,
+            method_ids_size: reader.u32()?,
+            method_ids_off: reader.u32()?,
+            class_defs_size: reader.u32()?,
+            class_defs_off: reader.u32()?,
+            data_size: reader.u32()?,
+            data_off: reader.u32()?,
+        })
+    }
+}
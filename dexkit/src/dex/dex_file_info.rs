@@ -0,0 +1,198 @@
+use crate::dex::{DexFile, MapItem};
+use crate::errors::Error;
+
+/// Header and map_list summary of one loaded dex, see `DexkitBridge::dex_files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DexFileInfo {
+    index: u32,
+    name: String,
+    version: String,
+    checksum: u32,
+    signature: [u8; 20],
+    file_size: u32,
+    string_count: u32,
+    type_count: u32,
+    proto_count: u32,
+    field_count: u32,
+    method_count: u32,
+    class_count: u32,
+    map_items: Vec<MapItem>,
+}
+
+impl DexFileInfo {
+    /// internal use
+    pub(crate) fn from_dex(dex: &DexFile) -> Result<Self, Error> {
+        let header = dex.header();
+        Ok(Self {
+            index: dex.dex_id(),
+            name: dex.name().to_string(),
+            version: header.version.clone(),
+            checksum: header.checksum,
+            signature: header.signature,
+            file_size: header.file_size,
+            string_count: header.string_ids_size,
+            type_count: header.type_ids_size,
+            proto_count: header.proto_ids_size,
+            field_count: header.field_ids_size,
+            method_count: header.method_ids_size,
+            class_count: header.class_defs_size,
+            map_items: dex.map_items()?,
+        })
+    }
+
+    /// index of the dex, the same as `ClassData::dex_index`
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// entry name of the dex in the apk, e.g. "classes2.dex"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// dex format version, e.g. "035"
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// adler32 checksum from the header
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    /// SHA-1 signature from the header
+    pub fn signature(&self) -> &[u8; 20] {
+        &self.signature
+    }
+
+    /// lowercase hex of `signature`
+    pub fn signature_hex(&self) -> String {
+        self.signature
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn file_size(&self) -> u32 {
+        self.file_size
+    }
+
+    pub fn string_count(&self) -> u32 {
+        self.string_count
+    }
+
+    pub fn type_count(&self) -> u32 {
+        self.type_count
+    }
+
+    pub fn proto_count(&self) -> u32 {
+        self.proto_count
+    }
+
+    pub fn field_count(&self) -> u32 {
+        self.field_count
+    }
+
+    pub fn method_count(&self) -> u32 {
+        self.method_count
+    }
+
+    /// number of classes defined in the dex
+    pub fn class_count(&self) -> u32 {
+        self.class_count
+    }
+
+    /// sections of the dex from the map_list, in file order
+    pub fn map_items(&self) -> &[MapItem] {
+        &self.map_items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// header_item followed by a map_list at `map_off` holding `map`
+    fn header_dex(map_off: u32, map: &[(u16, u32, u32)]) -> Vec<u8> {
+        let mut data = b"dex\n039\0".to_vec();
+        let words = |data: &mut Vec<u8>, words: &[u32]| {
+            words
+                .iter()
+                .for_each(|word| data.extend_from_slice(&word.to_le_bytes()))
+        };
+        words(&mut data, &[0xcafe_f00d]);
+        data.extend(1..=20u8);
+        let file_size = 0x70 + 4 + 12 * map.len() as u32;
+        words(&mut data, &[file_size, 0x70, 0x1234_5678, 0, 0, map_off]);
+        // string, type, proto, field, method and class_def ids: size and offset
+        words(&mut data, &[5, 0, 4, 0, 3, 0, 2, 0, 1, 0, 6, 0, 0, 0]);
+        assert_eq!(data.len(), 0x70);
+        words(&mut data, &[map.len() as u32]);
+        for &(item_type, size, offset) in map {
+            data.extend_from_slice(&item_type.to_le_bytes());
+            data.extend_from_slice(&[0, 0]);
+            words(&mut data, &[size, offset]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_from_dex() {
+        let map = [(0x0000, 1, 0), (0x0001, 5, 0x70), (0x1000, 1, 0x70)];
+        let dex = DexFile::parse(2, "classes3.dex", header_dex(0x70, &map)).unwrap();
+        let info = DexFileInfo::from_dex(&dex).unwrap();
+        assert_eq!(info.index(), 2);
+        assert_eq!(info.name(), "classes3.dex");
+        assert_eq!(info.version(), "039");
+        assert_eq!(info.checksum(), 0xcafe_f00d);
+        assert_eq!(
+            info.signature_hex(),
+            "0102030405060708090a0b0c0d0e0f1011121314"
+        );
+        assert_eq!(info.file_size(), 0x70 + 4 + 12 * 3);
+        assert_eq!(info.string_count(), 5);
+        assert_eq!(info.type_count(), 4);
+        assert_eq!(info.proto_count(), 3);
+        assert_eq!(info.field_count(), 2);
+        assert_eq!(info.method_count(), 1);
+        assert_eq!(info.class_count(), 6);
+
+        let items = info.map_items();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[1],
+            MapItem {
+                item_type: 0x0001,
+                size: 5,
+                offset: 0x70
+            }
+        );
+        let names: Vec<_> = items.iter().map(|item| item.type_name()).collect();
+        assert_eq!(names, ["header_item", "string_id_item", "map_list"]);
+    }
+
+    #[test]
+    fn test_map_list() {
+        // no map_list
+        let dex = DexFile::parse(0, "classes.dex", header_dex(0, &[])).unwrap();
+        assert!(DexFileInfo::from_dex(&dex).unwrap().map_items().is_empty());
+
+        let item = |item_type| MapItem {
+            item_type,
+            size: 0,
+            offset: 0,
+        };
+        assert_eq!(item(0x2001).type_name(), "code_item");
+        assert_eq!(item(0xf000).type_name(), "hiddenapi_class_data_item");
+        assert_eq!(item(0x0009).type_name(), "unknown");
+
+        // map_list declaring more items than the file holds
+        let mut data = header_dex(0x70, &[(0x0000, 1, 0)]);
+        data[0x70] = 2;
+        let dex = DexFile::parse(0, "classes.dex", data).unwrap();
+        assert!(matches!(
+            DexFileInfo::from_dex(&dex),
+            Err(Error::DexParseError(_))
+        ));
+    }
+}
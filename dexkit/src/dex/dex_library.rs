@@ -0,0 +1,289 @@
+use crate::apk::ApkArchive;
+use crate::dex::{ClassDef, DexFile};
+use crate::errors::Error;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// internal use, dex files attached with `DexkitBridge::add_library_path`.
+///
+/// Library classes are only used to resolve types missing from the apk, e.g.
+/// `Landroid/app/Activity;` from framework.jar. They are not loaded into DexKit, their ids
+/// are kept out of native queries and `find_*` queries only return them on request,
+/// see `FindClass::set_include_library`.
+#[derive(Debug, Default)]
+pub(crate) struct DexLibrary {
+    dex_files: Vec<DexFile>,
+    /// descriptor -> (position in `dex_files`, class_def), the first definition wins
+    classes: HashMap<String, (usize, ClassDef)>,
+}
+
+impl DexLibrary {
+    /// Load a .dex file, or every `classes*.dex` of a .jar / .apk.
+    /// Dex ids are assigned from `first_dex_id`, returns the number of dex files added.
+    pub(crate) fn add<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        first_dex_id: u32,
+    ) -> Result<usize, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|e| Error::ApkReadError(format!("{}: {}", path.display(), e)))?;
+        let dex_files = if data.starts_with(b"dex\n") {
+            let name = path
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+            vec![DexFile::parse(first_dex_id, name, data)?]
+        } else {
+            let mut archive = ApkArchive::open(path)?;
+            let mut dex_files = Vec::new();
+            for (i, name) in archive.dex_entry_names().into_iter().enumerate() {
+                let data = archive.read(&name)?;
+                dex_files.push(DexFile::parse(first_dex_id + i as u32, name, data)?);
+            }
+            dex_files
+        };
+
+        let added = dex_files.len();
+        for dex in dex_files {
+            self.insert(dex)?;
+        }
+        Ok(added)
+    }
+
+    /// Add a parsed dex file, classes already defined by an earlier dex are skipped.
+    pub(crate) fn insert(&mut self, dex: DexFile) -> Result<(), Error> {
+        let position = self.dex_files.len();
+        for class_def in dex.class_defs()? {
+            self.classes
+                .entry(dex.type_descriptor(class_def.class_idx)?)
+                .or_insert((position, class_def));
+        }
+        self.dex_files.push(dex);
+        Ok(())
+    }
+
+    pub(crate) fn dex_files(&self) -> &[DexFile] {
+        &self.dex_files
+    }
+
+    /// the library dex with the given dex id
+    pub(crate) fn dex_file(&self, dex_id: u32) -> Option<&DexFile> {
+        self.dex_files.iter().find(|dex| dex.dex_id() == dex_id)
+    }
+
+    /// the library class with the given descriptor, e.g. "Landroid/app/Activity;"
+    pub(crate) fn find_class(&self, descriptor: &str) -> Option<(&DexFile, ClassDef)> {
+        self.classes
+            .get(descriptor)
+            .map(|&(position, class_def)| (&self.dex_files[position], class_def))
+    }
+
+    /// every library class, in the order the dex files were added
+    pub(crate) fn classes(&self) -> Vec<(&DexFile, ClassDef)> {
+        let mut classes: Vec<_> = self.classes.values().collect();
+        classes.sort_by_key(|&&(position, class_def)| (position, class_def.class_idx));
+        classes
+            .into_iter()
+            .map(|&(position, class_def)| (&self.dex_files[position], class_def))
+            .collect()
+    }
+
+    /// number of library classes
+    pub(crate) fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexkitBridge;
+    use crate::query::FindClass;
+    use crate::query::matchers::ClassMatcher;
+    use crate::result::ClassData;
+    use crate::result::base::BaseData;
+    use std::collections::BTreeSet;
+
+    /// (descriptor, super class, interfaces) of a class without members
+    type TestClass<'s> = (&'s str, Option<&'s str>, &'s [&'s str]);
+
+    /// a dex holding the string, type and class_def tables of `classes`
+    fn test_dex(dex_id: u32, classes: &[TestClass]) -> DexFile {
+        let types: BTreeSet<&str> = classes
+            .iter()
+            .flat_map(|&(class, super_class, interfaces)| {
+                [class]
+                    .into_iter()
+                    .chain(super_class)
+                    .chain(interfaces.iter().copied())
+            })
+            .collect();
+        let types: Vec<&str> = types.into_iter().collect();
+        let type_idx = |descriptor: &str| types.iter().position(|&t| t == descriptor).unwrap();
+        let put = |data: &mut Vec<u8>, at: usize, value: u32| {
+            data[at..at + 4].copy_from_slice(&value.to_le_bytes())
+        };
+
+        let string_ids_off = 0x70;
+        let type_ids_off = string_ids_off + 4 * types.len();
+        let class_defs_off = type_ids_off + 4 * types.len();
+        let mut data = vec![0u8; class_defs_off + 32 * classes.len()];
+        for (i, &(class, super_class, interfaces)) in classes.iter().enumerate() {
+            let off = class_defs_off + 32 * i;
+            put(&mut data, off, type_idx(class) as u32);
+            put(&mut data, off + 4, 0x1);
+            put(
+                &mut data,
+                off + 8,
+                super_class.map_or(u32::MAX, |s| type_idx(s) as u32),
+            );
+            put(&mut data, off + 16, u32::MAX);
+            if !interfaces.is_empty() {
+                let type_list_off = data.len().next_multiple_of(4);
+                put(&mut data, off + 12, type_list_off as u32);
+                data.resize(type_list_off, 0);
+                data.extend((interfaces.len() as u32).to_le_bytes());
+                for interface in interfaces {
+                    data.extend((type_idx(*interface) as u16).to_le_bytes());
+                }
+            }
+        }
+        for (i, descriptor) in types.iter().enumerate() {
+            let string_data_off = data.len() as u32;
+            put(&mut data, string_ids_off + 4 * i, string_data_off);
+            put(&mut data, type_ids_off + 4 * i, i as u32);
+            data.push(descriptor.len() as u8);
+            data.extend(descriptor.as_bytes());
+            data.push(0);
+        }
+
+        data[..8].copy_from_slice(b"dex\n035\0");
+        let header = [
+            (32, data.len()),
+            (36, 0x70),
+            (40, 0x1234_5678),
+            (56, types.len()),
+            (60, string_ids_off),
+            (64, types.len()),
+            (68, type_ids_off),
+            (96, classes.len()),
+            (100, class_defs_off),
+        ];
+        for (at, value) in header {
+            put(&mut data, at, value as u32);
+        }
+        DexFile::parse(dex_id, format!("classes{}.dex", dex_id + 1), data).unwrap()
+    }
+
+    const ACTIVITY: &str = "Landroid/app/Activity;";
+    const WRAPPER: &str = "Landroid/view/ContextThemeWrapper;";
+    const CALLBACK: &str = "Landroid/view/Window$Callback;";
+
+    /// an apk dex 0 with a `MainActivity`, the framework classes in library dex 1
+    fn test_bridge() -> DexkitBridge {
+        let main: TestClass = ("Lcom/example/MainActivity;", Some(ACTIVITY), &[]);
+        let framework: [TestClass; 3] = [
+            (ACTIVITY, Some(WRAPPER), &[CALLBACK]),
+            (WRAPPER, Some("Ljava/lang/Object;"), &[]),
+            (CALLBACK, None, &[]),
+        ];
+        DexkitBridge::for_test(vec![test_dex(0, &[main])])
+            .with_test_library(vec![test_dex(1, &framework)])
+    }
+
+    #[test]
+    fn test_first_definition_wins() {
+        let mut library = DexLibrary::default();
+        library
+            .insert(test_dex(
+                1,
+                &[(ACTIVITY, Some(WRAPPER), &[]), (WRAPPER, None, &[])],
+            ))
+            .unwrap();
+        library
+            .insert(test_dex(2, &[(ACTIVITY, None, &[]), (CALLBACK, None, &[])]))
+            .unwrap();
+        assert_eq!(library.class_count(), 3);
+        assert!(library.dex_file(2).is_some());
+        assert!(library.dex_file(0).is_none());
+
+        let (dex, class_def) = library.find_class(ACTIVITY).unwrap();
+        assert_eq!(dex.dex_id(), 1);
+        assert!(class_def.superclass_idx.is_some());
+
+        let classes: Vec<_> = library
+            .classes()
+            .into_iter()
+            .map(|(dex, class_def)| dex.type_descriptor(class_def.class_idx).unwrap())
+            .collect();
+        assert_eq!(classes, [ACTIVITY, WRAPPER, CALLBACK]);
+    }
+
+    #[test]
+    fn test_library_supertypes() {
+        let bridge = test_bridge();
+        let main = bridge.raw_dex_file(0).unwrap();
+        let super_idx = main.class_defs().unwrap()[0].superclass_idx.unwrap();
+
+        // the super class of an apk class missing from the apk
+        let activity = bridge.get_library_type(0, super_idx).unwrap();
+        assert_eq!(activity.descriptor(), ACTIVITY);
+        assert!(activity.is_library());
+        assert_eq!(activity.dex_id(), 1);
+
+        // supertypes of library classes resolve within the library
+        let wrapper = activity.supper_class().unwrap();
+        assert_eq!(wrapper.descriptor(), WRAPPER);
+        assert!(wrapper.is_library());
+        assert!(wrapper.supper_class().is_none());
+        let interfaces: Vec<_> = activity
+            .interfaces()
+            .iter()
+            .map(|c| c.descriptor())
+            .collect();
+        assert_eq!(interfaces, [CALLBACK]);
+
+        // library ids never reach DexKit
+        assert!(activity.methods().is_empty());
+        assert!(activity.annotations().is_empty());
+        assert!(
+            bridge
+                .get_type_by_ids(&[activity.get_mine_encode_id()])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_include_library() {
+        let bridge = test_bridge();
+        let classes = bridge.get_library_classes();
+        let descriptors: Vec<_> = classes.iter().map(|c| c.descriptor()).collect();
+        assert_eq!(descriptors, [ACTIVITY, WRAPPER, CALLBACK]);
+        let activity: ClassData = classes[0].clone();
+
+        // a scope of library classes only skips the native search
+        let query = || FindClass::create().set_search_classes(vec![activity.clone()]);
+        assert!(bridge.find_class(query()).is_empty());
+        let found = bridge.find_class(query().set_include_library(true));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0], activity);
+        let rejected = query()
+            .set_include_library(true)
+            .set_predicate(|class| !class.descriptor().contains("Activity"));
+        assert!(bridge.find_class(rejected).is_empty());
+        let with_matcher = query()
+            .set_include_library(true)
+            .set_matcher(ClassMatcher::create());
+        assert!(bridge.find_class(with_matcher).is_empty());
+
+        let in_view = FindClass::create().set_search_packages(vec!["android.view"]);
+        assert!(in_view.library_match(&classes[1]));
+        assert!(!in_view.library_match(&activity));
+        let upper_case = FindClass::create()
+            .set_search_packages(vec!["ANDROID/APP"])
+            .set_ignore_packages_case(true);
+        assert!(upper_case.library_match(&activity));
+        let excluded = FindClass::create().set_exclude_packages(vec!["android"]);
+        assert!(!excluded.library_match(&activity));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::errors::Error;
+
+/// internal use, little-endian cursor over the bytes of a dex file
+pub(crate) struct DexReader<'d> {
+    data: &'d [u8],
+    pos: usize,
+}
+
+impl<'d> DexReader<'d> {
+    pub(crate) fn new(data: &'d [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'d [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len());
+        let Some(end) = end else {
+            return Err(Error::DexParseError(format!(
+                "read of {} bytes at 0x{:X} is out of bounds",
+                len, self.pos
+            )));
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn uleb128(&mut self) -> Result<u32, Error> {
+        let mut result = 0u32;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            result |= ((byte & 0x7f) as u32) << (i * 7);
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(Error::DexParseError(format!(
+            "invalid uleb128 at 0x{:X}",
+            self.pos
+        )))
+    }
+
+    /// uleb128 value minus one, `None` for the encoded -1 (NO_INDEX)
+    pub(crate) fn uleb128p1(&mut self) -> Result<Option<u32>, Error> {
+        Ok(self.uleb128()?.checked_sub(1))
+    }
+
+    pub(crate) fn sleb128(&mut self) -> Result<i32, Error> {
+        let mut result = 0i32;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            result |= ((byte & 0x7f) as i32) << (i * 7);
+            if byte & 0x80 == 0 {
+                let shift = 32 - 7 * (i + 1);
+                if shift > 0 {
+                    result = (result << shift) >> shift;
+                }
+                return Ok(result);
+            }
+        }
+        Err(Error::DexParseError(format!(
+            "invalid sleb128 at 0x{:X}",
+            self.pos
+        )))
+    }
+
+    /// little-endian value of `size` bytes, zero extended
+    pub(crate) fn sized_u64(&mut self, size: usize) -> Result<u64, Error> {
+        let bytes = self.bytes(size)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &byte| (acc << 8) | byte as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uleb128() {
+        let data = [0x00, 0x7f, 0x80, 0x7f, 0xb4, 0x07];
+        let mut reader = DexReader::new(&data, 0);
+        assert_eq!(reader.uleb128().unwrap(), 0);
+        assert_eq!(reader.uleb128().unwrap(), 127);
+        assert_eq!(reader.uleb128().unwrap(), 16256);
+        assert_eq!(reader.uleb128().unwrap(), 948);
+        assert_eq!(reader.pos(), data.len());
+    }
+
+    #[test]
+    fn test_uleb128p1() {
+        let data = [0x00, 0x01];
+        let mut reader = DexReader::new(&data, 0);
+        assert_eq!(reader.uleb128p1().unwrap(), None);
+        assert_eq!(reader.uleb128p1().unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_sleb128() {
+        let data = [0x00, 0x01, 0x7f, 0x80, 0x7f];
+        let mut reader = DexReader::new(&data, 0);
+        assert_eq!(reader.sleb128().unwrap(), 0);
+        assert_eq!(reader.sleb128().unwrap(), 1);
+        assert_eq!(reader.sleb128().unwrap(), -1);
+        assert_eq!(reader.sleb128().unwrap(), -128);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let data = [0x01, 0x02];
+        let mut reader = DexReader::new(&data, 1);
+        assert!(reader.u16().is_err());
+        assert!(reader.u8().is_ok());
+    }
+
+    #[test]
+    fn test_sized_u64() {
+        let data = [0x34, 0x12, 0xff];
+        let mut reader = DexReader::new(&data, 0);
+        assert_eq!(reader.sized_u64(2).unwrap(), 0x1234);
+        assert_eq!(reader.sized_u64(1).unwrap(), 0xff);
+    }
+}
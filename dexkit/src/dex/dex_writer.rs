@@ -0,0 +1,1675 @@
+use crate::dex::{
+    AnnotationItem, CatchHandler, ClassDef, DEX_NO_INDEX, DexFile, EncodedAnnotation, EncodedValue,
+    Instructions, TryItem,
+};
+use crate::errors::Error;
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeSet, HashMap};
+
+const HEADER_SIZE: u32 = 0x70;
+const ENDIAN_CONSTANT: u32 = 0x1234_5678;
+
+// map_item types, see `MapItem::type_name`
+const TYPE_HEADER_ITEM: u16 = 0x0000;
+const TYPE_STRING_ID_ITEM: u16 = 0x0001;
+const TYPE_TYPE_ID_ITEM: u16 = 0x0002;
+const TYPE_PROTO_ID_ITEM: u16 = 0x0003;
+const TYPE_FIELD_ID_ITEM: u16 = 0x0004;
+const TYPE_METHOD_ID_ITEM: u16 = 0x0005;
+const TYPE_CLASS_DEF_ITEM: u16 = 0x0006;
+const TYPE_MAP_LIST: u16 = 0x1000;
+const TYPE_TYPE_LIST: u16 = 0x1001;
+const TYPE_ANNOTATION_SET_REF_LIST: u16 = 0x1002;
+const TYPE_ANNOTATION_SET_ITEM: u16 = 0x1003;
+const TYPE_CLASS_DATA_ITEM: u16 = 0x2000;
+const TYPE_CODE_ITEM: u16 = 0x2001;
+const TYPE_STRING_DATA_ITEM: u16 = 0x2002;
+const TYPE_DEBUG_INFO_ITEM: u16 = 0x2003;
+const TYPE_ANNOTATION_ITEM: u16 = 0x2004;
+const TYPE_ENCODED_ARRAY_ITEM: u16 = 0x2005;
+const TYPE_ANNOTATIONS_DIRECTORY_ITEM: u16 = 0x2006;
+
+/// (return type descriptor, parameter type descriptors)
+type ProtoKey = (String, Vec<String>);
+/// (class descriptor, name, type descriptor)
+type FieldKey = (String, String, String);
+/// (class descriptor, name, proto)
+type MethodKey = (String, String, ProtoKey);
+
+/// internal use, writes classes of loaded dex files into a new dex file.
+///
+/// Every index is rebuilt from the referenced values, so the classes may come from
+/// different dex files. Classes referenced but not written stay external references.
+pub(crate) struct DexWriter;
+
+impl DexWriter {
+    pub(crate) fn write(classes: &[(&DexFile, ClassDef)]) -> Result<Vec<u8>, Error> {
+        let mut pools = Pools::default();
+        for (dex, class_def) in classes {
+            ClassModel::build(dex, class_def, &mut Remap::Collect(&mut pools))?;
+        }
+        let indexes = Indexes::new(pools)?;
+        let mut remap = Remap::Resolve(&indexes);
+        let models = classes
+            .iter()
+            .map(|(dex, class_def)| ClassModel::build(dex, class_def, &mut remap))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let version = classes
+            .iter()
+            .map(|(dex, _)| dex.header().version.as_str())
+            .max()
+            .unwrap_or("035");
+        DexLayout::write(version, &indexes, Self::order_classes(models))
+    }
+
+    /// descriptors of the types referenced by a class, the class itself included
+    pub(crate) fn referenced_types(
+        dex: &DexFile,
+        class_def: &ClassDef,
+    ) -> Result<BTreeSet<String>, Error> {
+        let mut pools = Pools::default();
+        ClassModel::build(dex, class_def, &mut Remap::Collect(&mut pools))?;
+        Ok(pools.types)
+    }
+
+    /// superclasses and interfaces must be defined before their subclasses
+    fn order_classes(models: Vec<ClassModel>) -> Vec<ClassModel> {
+        let positions: HashMap<u32, usize> = models
+            .iter()
+            .enumerate()
+            .map(|(pos, model)| (model.class_idx, pos))
+            .collect();
+        let mut visited = vec![false; models.len()];
+        let mut order = Vec::with_capacity(models.len());
+        // iterative post-order walk, a malformed hierarchy cycle is written in input order
+        for start in 0..models.len() {
+            let mut stack = vec![(start, false)];
+            while let Some((pos, expanded)) = stack.pop() {
+                if expanded {
+                    order.push(pos);
+                    continue;
+                }
+                if visited[pos] {
+                    continue;
+                }
+                visited[pos] = true;
+                stack.push((pos, true));
+                let model = &models[pos];
+                for supertype in model.superclass_idx.iter().chain(&model.interfaces) {
+                    if let Some(&parent) = positions.get(supertype)
+                        && !visited[parent]
+                    {
+                        stack.push((parent, false));
+                    }
+                }
+            }
+        }
+        let mut models: Vec<Option<ClassModel>> = models.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .filter_map(|pos| models[pos].take())
+            .collect()
+    }
+}
+
+/// internal use, values referenced by the written classes
+#[derive(Debug, Default)]
+struct Pools {
+    strings: BTreeSet<String>,
+    types: BTreeSet<String>,
+    protos: BTreeSet<ProtoKey>,
+    fields: BTreeSet<FieldKey>,
+    methods: BTreeSet<MethodKey>,
+}
+
+/// internal use, indexes of the new dex, in the order required by the dex format
+#[derive(Debug)]
+struct Indexes {
+    strings: Vec<String>,
+    string_index: HashMap<String, u32>,
+    /// string index of each type descriptor
+    types: Vec<u32>,
+    type_index: HashMap<String, u32>,
+    /// (shorty string index, return type index, parameter type indexes)
+    protos: Vec<(u32, u32, Vec<u32>)>,
+    proto_index: HashMap<ProtoKey, u32>,
+    /// (class type index, type index, name string index)
+    fields: Vec<(u32, u32, u32)>,
+    field_index: HashMap<FieldKey, u32>,
+    /// (class type index, proto index, name string index)
+    methods: Vec<(u32, u32, u32)>,
+    method_index: HashMap<MethodKey, u32>,
+}
+
+impl Indexes {
+    fn new(pools: Pools) -> Result<Self, Error> {
+        for (kind, size) in [
+            ("types", pools.types.len()),
+            ("protos", pools.protos.len()),
+            ("fields", pools.fields.len()),
+            ("methods", pools.methods.len()),
+        ] {
+            if size > 0x1_0000 {
+                return Err(Error::DexWriteError(format!(
+                    "{} {} do not fit in a single dex",
+                    size, kind
+                )));
+            }
+        }
+
+        // string_ids are sorted by utf16 code units, type_ids by string index,
+        // the other ids by their indexes in the tables they reference
+        let mut strings: Vec<String> = pools.strings.into_iter().collect();
+        strings.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+        let string_index: HashMap<String, u32> = Self::index_of(&strings);
+
+        let mut types: Vec<String> = pools.types.into_iter().collect();
+        types.sort_by_key(|descriptor| string_index[descriptor]);
+        let type_index: HashMap<String, u32> = Self::index_of(&types);
+
+        let mut protos: Vec<(ProtoKey, (u32, Vec<u32>))> = pools
+            .protos
+            .into_iter()
+            .map(|proto| {
+                let key = (
+                    type_index[&proto.0],
+                    proto.1.iter().map(|param| type_index[param]).collect(),
+                );
+                (proto, key)
+            })
+            .collect();
+        protos.sort_by(|a, b| a.1.cmp(&b.1));
+        let proto_index: HashMap<ProtoKey, u32> = protos
+            .iter()
+            .enumerate()
+            .map(|(idx, (proto, _))| (proto.clone(), idx as u32))
+            .collect();
+
+        let mut fields: Vec<(FieldKey, (u32, u32, u32))> = pools
+            .fields
+            .into_iter()
+            .map(|field| {
+                let key = (
+                    type_index[&field.0],
+                    string_index[&field.1],
+                    type_index[&field.2],
+                );
+                (field, key)
+            })
+            .collect();
+        fields.sort_by_key(|(_, key)| *key);
+
+        let mut methods: Vec<(MethodKey, (u32, u32, u32))> = pools
+            .methods
+            .into_iter()
+            .map(|method| {
+                let key = (
+                    type_index[&method.0],
+                    string_index[&method.1],
+                    proto_index[&method.2],
+                );
+                (method, key)
+            })
+            .collect();
+        methods.sort_by_key(|(_, key)| *key);
+
+        Ok(Self {
+            types: types
+                .iter()
+                .map(|descriptor| string_index[descriptor])
+                .collect(),
+            protos: protos
+                .iter()
+                .map(|((return_type, params), (return_idx, param_idxs))| {
+                    let shorty = Self::shorty(return_type, params);
+                    (string_index[&shorty], *return_idx, param_idxs.clone())
+                })
+                .collect(),
+            fields: fields
+                .iter()
+                .map(|(_, (class, name, field_type))| (*class, *field_type, *name))
+                .collect(),
+            field_index: fields
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (field, _))| (field, idx as u32))
+                .collect(),
+            methods: methods
+                .iter()
+                .map(|(_, (class, name, proto))| (*class, *proto, *name))
+                .collect(),
+            method_index: methods
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (method, _))| (method, idx as u32))
+                .collect(),
+            strings,
+            string_index,
+            type_index,
+            proto_index,
+        })
+    }
+
+    fn index_of(values: &[String]) -> HashMap<String, u32> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| (value.clone(), idx as u32))
+            .collect()
+    }
+
+    /// shorty descriptor of a proto, e.g. "VLI" for `void (String, int)`
+    fn shorty(return_type: &str, params: &[String]) -> String {
+        std::iter::once(return_type)
+            .chain(params.iter().map(String::as_str))
+            .map(|descriptor| match descriptor.as_bytes()[0] {
+                b'[' => 'L',
+                first => first as char,
+            })
+            .collect()
+    }
+}
+
+/// internal use, maps indexes of a source dex to the new dex.
+/// `Collect` records the referenced values, `Resolve` looks up their new indexes.
+enum Remap<'p> {
+    Collect(&'p mut Pools),
+    Resolve(&'p Indexes),
+}
+
+impl Remap<'_> {
+    fn string(&mut self, dex: &DexFile, idx: u32) -> Result<u32, Error> {
+        self.string_value(dex.string(idx)?)
+    }
+
+    fn type_(&mut self, dex: &DexFile, idx: u32) -> Result<u32, Error> {
+        self.type_value(dex.type_descriptor(idx)?)
+    }
+
+    fn proto(&mut self, dex: &DexFile, idx: u32) -> Result<u32, Error> {
+        let proto = Self::proto_key(dex, idx)?;
+        self.proto_value(proto)
+    }
+
+    fn field(&mut self, dex: &DexFile, idx: u32) -> Result<u32, Error> {
+        let field_id = dex.field_id(idx)?;
+        let field = (
+            dex.type_descriptor(field_id.class_idx)?,
+            dex.string(field_id.name_idx)?,
+            dex.type_descriptor(field_id.type_idx)?,
+        );
+        match self {
+            Remap::Collect(pools) => {
+                pools.strings.insert(field.1.clone());
+                for descriptor in [&field.0, &field.2] {
+                    pools.strings.insert(descriptor.clone());
+                    pools.types.insert(descriptor.clone());
+                }
+                pools.fields.insert(field);
+                Ok(0)
+            }
+            Remap::Resolve(indexes) => Self::lookup(&indexes.field_index, &field, "field"),
+        }
+    }
+
+    fn method(&mut self, dex: &DexFile, idx: u32) -> Result<u32, Error> {
+        let method_id = dex.method_id(idx)?;
+        let method = (
+            dex.type_descriptor(method_id.class_idx)?,
+            dex.string(method_id.name_idx)?,
+            Self::proto_key(dex, method_id.proto_idx)?,
+        );
+        match self {
+            Remap::Collect(_) => {
+                self.type_value(method.0.clone())?;
+                self.string_value(method.1.clone())?;
+                self.proto_value(method.2.clone())?;
+                if let Remap::Collect(pools) = self {
+                    pools.methods.insert(method);
+                }
+                Ok(0)
+            }
+            Remap::Resolve(indexes) => Self::lookup(&indexes.method_index, &method, "method"),
+        }
+    }
+
+    fn string_value(&mut self, value: String) -> Result<u32, Error> {
+        match self {
+            Remap::Collect(pools) => {
+                pools.strings.insert(value);
+                Ok(0)
+            }
+            Remap::Resolve(indexes) => Self::lookup(&indexes.string_index, &value, "string"),
+        }
+    }
+
+    fn type_value(&mut self, descriptor: String) -> Result<u32, Error> {
+        match self {
+            Remap::Collect(pools) => {
+                pools.strings.insert(descriptor.clone());
+                pools.types.insert(descriptor);
+                Ok(0)
+            }
+            Remap::Resolve(indexes) => Self::lookup(&indexes.type_index, &descriptor, "type"),
+        }
+    }
+
+    fn proto_value(&mut self, proto: ProtoKey) -> Result<u32, Error> {
+        match self {
+            Remap::Collect(pools) => {
+                pools.strings.insert(Indexes::shorty(&proto.0, &proto.1));
+                for descriptor in std::iter::once(&proto.0).chain(&proto.1) {
+                    pools.strings.insert(descriptor.clone());
+                    pools.types.insert(descriptor.clone());
+                }
+                pools.protos.insert(proto);
+                Ok(0)
+            }
+            Remap::Resolve(indexes) => Self::lookup(&indexes.proto_index, &proto, "proto"),
+        }
+    }
+
+    fn proto_key(dex: &DexFile, idx: u32) -> Result<ProtoKey, Error> {
+        let proto_id = dex.proto_id(idx)?;
+        let params = dex
+            .proto_parameters(&proto_id)?
+            .into_iter()
+            .map(|type_idx| dex.type_descriptor(type_idx))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok((dex.type_descriptor(proto_id.return_type_idx)?, params))
+    }
+
+    fn lookup<K: std::hash::Hash + Eq>(
+        index: &HashMap<K, u32>,
+        key: &K,
+        kind: &str,
+    ) -> Result<u32, Error> {
+        index
+            .get(key)
+            .copied()
+            .ok_or_else(|| Error::DexWriteError(format!("{} was not collected", kind)))
+    }
+
+    /// index operand of an instruction, `limit` is the largest encodable value
+    fn operand(idx: u32, limit: u32, kind: &str) -> Result<u32, Error> {
+        if idx > limit {
+            return Err(Error::DexWriteError(format!(
+                "{} index {} does not fit in the instruction",
+                kind, idx
+            )));
+        }
+        Ok(idx)
+    }
+
+    fn value(&mut self, dex: &DexFile, value: &EncodedValue) -> Result<EncodedValue, Error> {
+        Ok(match value {
+            EncodedValue::String(idx) => EncodedValue::String(self.string(dex, *idx)?),
+            EncodedValue::Type(idx) => EncodedValue::Type(self.type_(dex, *idx)?),
+            EncodedValue::Field(idx) => EncodedValue::Field(self.field(dex, *idx)?),
+            EncodedValue::Enum(idx) => EncodedValue::Enum(self.field(dex, *idx)?),
+            EncodedValue::Method(idx) => EncodedValue::Method(self.method(dex, *idx)?),
+            EncodedValue::MethodType(idx) => EncodedValue::MethodType(self.proto(dex, *idx)?),
+            EncodedValue::MethodHandle(_) => {
+                return Err(Error::DexWriteError(
+                    "method handles are not supported".to_string(),
+                ));
+            }
+            EncodedValue::Array(values) => EncodedValue::Array(
+                values
+                    .iter()
+                    .map(|value| self.value(dex, value))
+                    .collect::<Result<Vec<_>, Error>>()?,
+            ),
+            EncodedValue::Annotation(annotation) => {
+                EncodedValue::Annotation(self.annotation(dex, annotation)?)
+            }
+            other => other.clone(),
+        })
+    }
+
+    fn annotation(
+        &mut self,
+        dex: &DexFile,
+        annotation: &EncodedAnnotation,
+    ) -> Result<EncodedAnnotation, Error> {
+        let mut elements = annotation
+            .elements
+            .iter()
+            .map(|(name_idx, value)| -> Result<(u32, EncodedValue), Error> {
+                Ok((self.string(dex, *name_idx)?, self.value(dex, value)?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        // elements are sorted by name string index
+        elements.sort_by_key(|(name_idx, _)| *name_idx);
+        Ok(EncodedAnnotation {
+            type_idx: self.type_(dex, annotation.type_idx)?,
+            elements,
+        })
+    }
+
+    fn annotation_set(&mut self, dex: &DexFile, off: u32) -> Result<Vec<AnnotationItem>, Error> {
+        let mut items = dex
+            .annotation_set(off)?
+            .iter()
+            .map(|item| -> Result<AnnotationItem, Error> {
+                Ok(AnnotationItem {
+                    visibility: item.visibility,
+                    annotation: self.annotation(dex, &item.annotation)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        // entries are sorted by type index
+        items.sort_by_key(|item| item.annotation.type_idx);
+        Ok(items)
+    }
+}
+
+/// internal use, a class with the indexes of the new dex
+#[derive(Debug)]
+struct ClassModel {
+    class_idx: u32,
+    access_flags: u32,
+    superclass_idx: Option<u32>,
+    interfaces: Vec<u32>,
+    source_file_idx: Option<u32>,
+    annotations: Option<DirectoryModel>,
+    /// (field index, access flags)
+    static_fields: Vec<(u32, u32)>,
+    instance_fields: Vec<(u32, u32)>,
+    direct_methods: Vec<MethodModel>,
+    virtual_methods: Vec<MethodModel>,
+    static_values: Vec<EncodedValue>,
+}
+
+#[derive(Debug)]
+struct MethodModel {
+    method_idx: u32,
+    access_flags: u32,
+    code: Option<CodeModel>,
+}
+
+#[derive(Debug)]
+struct CodeModel {
+    registers_size: u16,
+    ins_size: u16,
+    outs_size: u16,
+    insns: Vec<u16>,
+    tries: Vec<TryItem>,
+    handlers: Vec<CatchHandler>,
+    debug_info: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Default)]
+struct DirectoryModel {
+    class_annotations: Option<Vec<AnnotationItem>>,
+    fields: Vec<(u32, Vec<AnnotationItem>)>,
+    methods: Vec<(u32, Vec<AnnotationItem>)>,
+    /// annotation sets of each parameter, `None` for offset 0
+    parameters: Vec<(u32, Vec<Option<Vec<AnnotationItem>>>)>,
+}
+
+impl ClassModel {
+    fn build(dex: &DexFile, class_def: &ClassDef, remap: &mut Remap) -> Result<Self, Error> {
+        let class_data = dex.class_data(class_def)?.unwrap_or_default();
+        let values = dex.static_values(class_def)?;
+
+        // static values follow the order of the static fields, keep them paired while sorting
+        let mut static_fields = Vec::with_capacity(class_data.static_fields.len());
+        for (pos, field) in class_data.static_fields.iter().enumerate() {
+            let field_idx = remap.field(dex, field.field_idx)?;
+            let value = match values.get(pos) {
+                Some(value) => Some(remap.value(dex, value)?),
+                None => None,
+            };
+            let field_type = dex.type_descriptor(dex.field_id(field.field_idx)?.type_idx)?;
+            static_fields.push((field_idx, field.access_flags, value, field_type));
+        }
+        static_fields.sort_by_key(|field| field.0);
+        let value_count = static_fields
+            .iter()
+            .rposition(|field| field.2.is_some())
+            .map_or(0, |pos| pos + 1);
+        let static_values = static_fields[..value_count]
+            .iter()
+            .map(|(_, _, value, field_type)| {
+                value
+                    .clone()
+                    .unwrap_or_else(|| Self::default_value(field_type))
+            })
+            .collect();
+
+        let mut instance_fields = class_data
+            .instance_fields
+            .iter()
+            .map(|field| -> Result<(u32, u32), Error> {
+                Ok((remap.field(dex, field.field_idx)?, field.access_flags))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        instance_fields.sort_by_key(|field| field.0);
+
+        let mut methods =
+            |encoded: &[crate::dex::EncodedMethod]| -> Result<Vec<MethodModel>, Error> {
+                let mut methods = encoded
+                    .iter()
+                    .map(|method| -> Result<MethodModel, Error> {
+                        Ok(MethodModel {
+                            method_idx: remap.method(dex, method.method_idx)?,
+                            access_flags: method.access_flags,
+                            code: CodeModel::build(dex, method.code_off, remap)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                methods.sort_by_key(|method| method.method_idx);
+                Ok(methods)
+            };
+        let direct_methods = methods(&class_data.direct_methods)?;
+        let virtual_methods = methods(&class_data.virtual_methods)?;
+
+        let annotations = match dex.annotations_directory(class_def)? {
+            Some(directory) => Some(DirectoryModel::build(dex, &directory, remap)?),
+            None => None,
+        };
+
+        Ok(Self {
+            class_idx: remap.type_(dex, class_def.class_idx)?,
+            access_flags: class_def.access_flags,
+            superclass_idx: class_def
+                .superclass_idx
+                .map(|idx| remap.type_(dex, idx))
+                .transpose()?,
+            interfaces: dex
+                .class_interfaces(class_def)?
+                .into_iter()
+                .map(|idx| remap.type_(dex, idx))
+                .collect::<Result<Vec<_>, Error>>()?,
+            source_file_idx: class_def
+                .source_file_idx
+                .map(|idx| remap.string(dex, idx))
+                .transpose()?,
+            annotations,
+            static_fields: static_fields
+                .iter()
+                .map(|(field_idx, access_flags, _, _)| (*field_idx, *access_flags))
+                .collect(),
+            instance_fields,
+            direct_methods,
+            virtual_methods,
+            static_values,
+        })
+    }
+
+    fn has_members(&self) -> bool {
+        !(self.static_fields.is_empty()
+            && self.instance_fields.is_empty()
+            && self.direct_methods.is_empty()
+            && self.virtual_methods.is_empty())
+    }
+
+    /// value of a static field without initializer
+    fn default_value(descriptor: &str) -> EncodedValue {
+        match descriptor {
+            "Z" => EncodedValue::Boolean(false),
+            "B" => EncodedValue::Byte(0),
+            "S" => EncodedValue::Short(0),
+            "C" => EncodedValue::Char(0),
+            "I" => EncodedValue::Int(0),
+            "J" => EncodedValue::Long(0),
+            "F" => EncodedValue::Float(0.0),
+            "D" => EncodedValue::Double(0.0),
+            _ => EncodedValue::Null,
+        }
+    }
+}
+
+impl CodeModel {
+    fn build(dex: &DexFile, code_off: u32, remap: &mut Remap) -> Result<Option<Self>, Error> {
+        let Some(code) = dex.code_item(code_off)? else {
+            return Ok(None);
+        };
+        let mut insns = code.insns.clone();
+        for instruction in Instructions::new(&code.insns) {
+            if instruction.is_payload() {
+                continue;
+            }
+            let at = instruction.offset() as usize;
+            let units = instruction.units();
+            let operand = |index: usize| -> Result<u32, Error> {
+                units
+                    .get(index)
+                    .map(|&unit| unit as u32)
+                    .ok_or_else(|| Error::DexWriteError(format!("truncated instruction at {}", at)))
+            };
+            match instruction.opcode() {
+                // const-string
+                0x1a => {
+                    let idx = remap.string(dex, operand(1)?)?;
+                    insns[at + 1] = Remap::operand(idx, 0xffff, "string")? as u16;
+                }
+                // const-string/jumbo
+                0x1b => {
+                    let idx = remap.string(dex, operand(1)? | operand(2)? << 16)?;
+                    insns[at + 1] = idx as u16;
+                    insns[at + 2] = (idx >> 16) as u16;
+                }
+                // const-class, check-cast, instance-of, new-instance, new-array,
+                // filled-new-array, filled-new-array/range
+                0x1c | 0x1f | 0x20 | 0x22 | 0x23 | 0x24 | 0x25 => {
+                    let idx = remap.type_(dex, operand(1)?)?;
+                    insns[at + 1] = Remap::operand(idx, 0xffff, "type")? as u16;
+                }
+                // iget*, iput*, sget*, sput*
+                0x52..=0x6d => {
+                    let idx = remap.field(dex, operand(1)?)?;
+                    insns[at + 1] = Remap::operand(idx, 0xffff, "field")? as u16;
+                }
+                // invoke-*, invoke-*/range
+                0x6e..=0x72 | 0x74..=0x78 => {
+                    let idx = remap.method(dex, operand(1)?)?;
+                    insns[at + 1] = Remap::operand(idx, 0xffff, "method")? as u16;
+                }
+                // invoke-polymorphic, invoke-polymorphic/range
+                0xfa | 0xfb => {
+                    let idx = remap.method(dex, operand(1)?)?;
+                    insns[at + 1] = Remap::operand(idx, 0xffff, "method")? as u16;
+                    let idx = remap.proto(dex, operand(3)?)?;
+                    insns[at + 3] = Remap::operand(idx, 0xffff, "proto")? as u16;
+                }
+                // const-method-type
+                0xff => {
+                    let idx = remap.proto(dex, operand(1)?)?;
+                    insns[at + 1] = Remap::operand(idx, 0xffff, "proto")? as u16;
+                }
+                // invoke-custom, invoke-custom/range, const-method-handle
+                0xfc..=0xfe => {
+                    return Err(Error::DexWriteError(
+                        "call sites and method handles are not supported".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let handlers = code
+            .handlers
+            .iter()
+            .map(|handler| -> Result<CatchHandler, Error> {
+                Ok(CatchHandler {
+                    offset: handler.offset,
+                    catches: handler
+                        .catches
+                        .iter()
+                        .map(|&(type_idx, addr)| -> Result<(u32, u32), Error> {
+                            Ok((remap.type_(dex, type_idx)?, addr))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?,
+                    catch_all_addr: handler.catch_all_addr,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let debug_info = if code.debug_info_off == 0 {
+            None
+        } else {
+            Some(Self::debug_info(dex, code.debug_info_off, remap)?)
+        };
+
+        Ok(Some(Self {
+            registers_size: code.registers_size,
+            ins_size: code.ins_size,
+            outs_size: code.outs_size,
+            insns,
+            tries: code.tries,
+            handlers,
+            debug_info,
+        }))
+    }
+
+    /// copy a debug_info_item with the string and type indexes of the new dex
+    fn debug_info(dex: &DexFile, off: u32, remap: &mut Remap) -> Result<Vec<u8>, Error> {
+        let mut reader = dex.reader(off as usize);
+        let mut out = DexBuffer::default();
+        let string = |remap: &mut Remap, out: &mut DexBuffer, idx: Option<u32>| {
+            out.uleb128p1(idx.map(|idx| remap.string(dex, idx)).transpose()?);
+            Ok::<(), Error>(())
+        };
+
+        out.uleb128(reader.uleb128()?);
+        let parameters_size = reader.uleb128()?;
+        out.uleb128(parameters_size);
+        for _ in 0..parameters_size {
+            string(remap, &mut out, reader.uleb128p1()?)?;
+        }
+        loop {
+            let opcode = reader.u8()?;
+            out.u8(opcode);
+            match opcode {
+                // DBG_END_SEQUENCE
+                0x00 => break,
+                // DBG_ADVANCE_PC, DBG_END_LOCAL, DBG_RESTART_LOCAL
+                0x01 | 0x05 | 0x06 => out.uleb128(reader.uleb128()?),
+                // DBG_ADVANCE_LINE
+                0x02 => out.sleb128(reader.sleb128()?),
+                // DBG_START_LOCAL, DBG_START_LOCAL_EXTENDED
+                0x03 | 0x04 => {
+                    out.uleb128(reader.uleb128()?);
+                    string(remap, &mut out, reader.uleb128p1()?)?;
+                    let type_idx = reader.uleb128p1()?;
+                    out.uleb128p1(type_idx.map(|idx| remap.type_(dex, idx)).transpose()?);
+                    if opcode == 0x04 {
+                        string(remap, &mut out, reader.uleb128p1()?)?;
+                    }
+                }
+                // DBG_SET_FILE
+                0x09 => string(remap, &mut out, reader.uleb128p1()?)?,
+                // DBG_SET_PROLOGUE_END, DBG_SET_EPILOGUE_BEGIN, special opcodes
+                _ => {}
+            }
+        }
+        Ok(out.data)
+    }
+}
+
+impl DirectoryModel {
+    fn build(
+        dex: &DexFile,
+        directory: &crate::dex::AnnotationsDirectory,
+        remap: &mut Remap,
+    ) -> Result<Self, Error> {
+        let class_annotations = match directory.class_annotations_off {
+            0 => None,
+            off => Some(remap.annotation_set(dex, off)?),
+        };
+        let mut fields = directory
+            .fields
+            .iter()
+            .map(
+                |&(field_idx, off)| -> Result<(u32, Vec<AnnotationItem>), Error> {
+                    Ok((
+                        remap.field(dex, field_idx)?,
+                        remap.annotation_set(dex, off)?,
+                    ))
+                },
+            )
+            .collect::<Result<Vec<_>, Error>>()?;
+        fields.sort_by_key(|(field_idx, _)| *field_idx);
+        let mut methods = directory
+            .methods
+            .iter()
+            .map(
+                |&(method_idx, off)| -> Result<(u32, Vec<AnnotationItem>), Error> {
+                    Ok((
+                        remap.method(dex, method_idx)?,
+                        remap.annotation_set(dex, off)?,
+                    ))
+                },
+            )
+            .collect::<Result<Vec<_>, Error>>()?;
+        methods.sort_by_key(|(method_idx, _)| *method_idx);
+        let mut parameters = Vec::with_capacity(directory.parameters.len());
+        for &(method_idx, off) in &directory.parameters {
+            let sets = dex
+                .annotation_set_ref_list(off)?
+                .into_iter()
+                .map(|set_off| match set_off {
+                    0 => Ok(None),
+                    set_off => remap.annotation_set(dex, set_off).map(Some),
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            parameters.push((remap.method(dex, method_idx)?, sets));
+        }
+        parameters.sort_by_key(|(method_idx, _)| *method_idx);
+        Ok(Self {
+            class_annotations,
+            fields,
+            methods,
+            parameters,
+        })
+    }
+}
+
+/// internal use, little-endian output buffer, the counterpart of `DexReader`
+#[derive(Debug, Default)]
+struct DexBuffer {
+    data: Vec<u8>,
+}
+
+impl DexBuffer {
+    fn pos(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    fn align(&mut self, alignment: usize) {
+        while !self.data.len().is_multiple_of(alignment) {
+            self.data.push(0);
+        }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u16(&mut self, at: u32, value: u16) {
+        let at = at as usize;
+        self.data[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_u32(&mut self, at: u32, value: u32) {
+        let at = at as usize;
+        self.data[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn uleb128(&mut self, mut value: u32) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.data.push(byte);
+                return;
+            }
+            self.data.push(byte | 0x80);
+        }
+    }
+
+    fn uleb128p1(&mut self, value: Option<u32>) {
+        self.uleb128(value.map_or(0, |value| value + 1));
+    }
+
+    fn sleb128(&mut self, mut value: i32) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+            if done {
+                self.data.push(byte);
+                return;
+            }
+            self.data.push(byte | 0x80);
+        }
+    }
+
+    /// string_data_item, MUTF-8 with its length in utf16 code units.
+    /// `MUtf8::encode` is not used, it is limited to 65535 bytes.
+    fn string_data(&mut self, value: &str) {
+        self.uleb128(value.encode_utf16().count() as u32);
+        for unit in value.encode_utf16() {
+            match unit {
+                0x01..=0x7f => self.u8(unit as u8),
+                0x00 | 0x80..=0x7ff => {
+                    self.u8(0xc0 | (unit >> 6) as u8);
+                    self.u8(0x80 | (unit & 0x3f) as u8);
+                }
+                _ => {
+                    self.u8(0xe0 | (unit >> 12) as u8);
+                    self.u8(0x80 | ((unit >> 6) & 0x3f) as u8);
+                    self.u8(0x80 | (unit & 0x3f) as u8);
+                }
+            }
+        }
+        self.u8(0);
+    }
+
+    fn encoded_value(&mut self, value: &EncodedValue) {
+        match value {
+            EncodedValue::Byte(value) => {
+                self.u8(0x00);
+                self.u8(*value as u8);
+            }
+            EncodedValue::Short(value) => self.signed(0x02, *value as i64),
+            EncodedValue::Char(value) => self.unsigned(0x03, *value as u64),
+            EncodedValue::Int(value) => self.signed(0x04, *value as i64),
+            EncodedValue::Long(value) => self.signed(0x06, *value),
+            EncodedValue::Float(value) => self.right_zero_extended(0x10, value.to_bits() as u64, 4),
+            EncodedValue::Double(value) => self.right_zero_extended(0x11, value.to_bits(), 8),
+            EncodedValue::MethodType(idx) => self.unsigned(0x15, *idx as u64),
+            EncodedValue::MethodHandle(idx) => self.unsigned(0x16, *idx as u64),
+            EncodedValue::String(idx) => self.unsigned(0x17, *idx as u64),
+            EncodedValue::Type(idx) => self.unsigned(0x18, *idx as u64),
+            EncodedValue::Field(idx) => self.unsigned(0x19, *idx as u64),
+            EncodedValue::Method(idx) => self.unsigned(0x1a, *idx as u64),
+            EncodedValue::Enum(idx) => self.unsigned(0x1b, *idx as u64),
+            EncodedValue::Array(values) => {
+                self.u8(0x1c);
+                self.encoded_array(values);
+            }
+            EncodedValue::Annotation(annotation) => {
+                self.u8(0x1d);
+                self.encoded_annotation(annotation);
+            }
+            EncodedValue::Null => self.u8(0x1e),
+            EncodedValue::Boolean(value) => self.u8(0x1f | (*value as u8) << 5),
+        }
+    }
+
+    fn encoded_array(&mut self, values: &[EncodedValue]) {
+        self.uleb128(values.len() as u32);
+        values.iter().for_each(|value| self.encoded_value(value));
+    }
+
+    fn encoded_annotation(&mut self, annotation: &EncodedAnnotation) {
+        self.uleb128(annotation.type_idx);
+        self.uleb128(annotation.elements.len() as u32);
+        for (name_idx, value) in &annotation.elements {
+            self.uleb128(*name_idx);
+            self.encoded_value(value);
+        }
+    }
+
+    /// value_type with the fewest bytes that sign-extend back to `value`
+    fn signed(&mut self, value_type: u8, value: i64) {
+        let size = (1..8)
+            .find(|&size| {
+                let shift = 64 - size * 8;
+                (value << shift) >> shift == value
+            })
+            .unwrap_or(8);
+        self.sized(value_type, value as u64, size);
+    }
+
+    /// value_type with the fewest bytes that zero-extend back to `value`
+    fn unsigned(&mut self, value_type: u8, value: u64) {
+        let size = ((64 - value.leading_zeros() as usize).div_ceil(8)).max(1);
+        self.sized(value_type, value, size);
+    }
+
+    /// float and double drop their low-order zero bytes
+    fn right_zero_extended(&mut self, value_type: u8, mut bits: u64, mut size: usize) {
+        while size > 1 && bits & 0xff == 0 {
+            bits >>= 8;
+            size -= 1;
+        }
+        self.sized(value_type, bits, size);
+    }
+
+    fn sized(&mut self, value_type: u8, value: u64, size: usize) {
+        self.u8(value_type | ((size - 1) as u8) << 5);
+        self.data.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+}
+
+/// internal use, lays out the sections of the new dex
+struct DexLayout {
+    out: DexBuffer,
+    /// (type, size, offset) of each non-empty section, in file order
+    map: Vec<(u16, u32, u32)>,
+}
+
+impl DexLayout {
+    fn write(version: &str, indexes: &Indexes, classes: Vec<ClassModel>) -> Result<Vec<u8>, Error> {
+        let mut layout = Self {
+            out: DexBuffer::default(),
+            map: vec![(TYPE_HEADER_ITEM, 1, 0)],
+        };
+
+        let id_sections = [
+            (TYPE_STRING_ID_ITEM, indexes.strings.len(), 4),
+            (TYPE_TYPE_ID_ITEM, indexes.types.len(), 4),
+            (TYPE_PROTO_ID_ITEM, indexes.protos.len(), 12),
+            (TYPE_FIELD_ID_ITEM, indexes.fields.len(), 8),
+            (TYPE_METHOD_ID_ITEM, indexes.methods.len(), 8),
+            (TYPE_CLASS_DEF_ITEM, classes.len(), 32),
+        ];
+        let mut id_offsets = [0u32; 6];
+        let mut pos = HEADER_SIZE;
+        for (i, &(item_type, size, item_size)) in id_sections.iter().enumerate() {
+            if size > 0 {
+                id_offsets[i] = pos;
+                layout.map.push((item_type, size as u32, pos));
+            }
+            pos += (size * item_size) as u32;
+        }
+        let [
+            string_ids_off,
+            type_ids_off,
+            proto_ids_off,
+            field_ids_off,
+            method_ids_off,
+            class_defs_off,
+        ] = id_offsets;
+        let data_off = pos;
+        layout.out.data.resize(data_off as usize, 0);
+
+        // string_data_item
+        let string_offsets =
+            layout.section(TYPE_STRING_DATA_ITEM, 1, &indexes.strings, |out, value| {
+                out.string_data(value);
+                Ok(())
+            })?;
+
+        // type_list of proto parameters and class interfaces
+        let mut type_lists: Vec<Vec<u32>> = indexes
+            .protos
+            .iter()
+            .map(|(_, _, params)| params.clone())
+            .chain(classes.iter().map(|class| class.interfaces.clone()))
+            .filter(|list| !list.is_empty())
+            .collect();
+        type_lists.sort();
+        type_lists.dedup();
+        let type_list_offsets: HashMap<Vec<u32>, u32> = type_lists
+            .iter()
+            .cloned()
+            .zip(layout.section(TYPE_TYPE_LIST, 4, &type_lists, |out, list| {
+                out.u32(list.len() as u32);
+                list.iter().for_each(|&type_idx| out.u16(type_idx as u16));
+                Ok(())
+            })?)
+            .collect();
+        let type_list_off = |list: &Vec<u32>| match list.is_empty() {
+            true => 0,
+            false => type_list_offsets[list],
+        };
+
+        // annotation_item, annotation_set_item and annotation_set_ref_list, deduplicated
+        let directories: Vec<&DirectoryModel> = classes
+            .iter()
+            .filter_map(|class| class.annotations.as_ref())
+            .collect();
+        let all_sets: Vec<&Vec<AnnotationItem>> = directories
+            .iter()
+            .flat_map(|directory| {
+                directory
+                    .class_annotations
+                    .iter()
+                    .chain(directory.fields.iter().map(|(_, set)| set))
+                    .chain(directory.methods.iter().map(|(_, set)| set))
+                    .chain(
+                        directory
+                            .parameters
+                            .iter()
+                            .flat_map(|(_, sets)| sets.iter().flatten()),
+                    )
+            })
+            .collect();
+        let mut annotation_items: Vec<Vec<u8>> = all_sets
+            .iter()
+            .flat_map(|set| set.iter())
+            .map(|item| {
+                let mut out = DexBuffer::default();
+                out.u8(item.visibility);
+                out.encoded_annotation(&item.annotation);
+                out.data
+            })
+            .collect();
+        annotation_items.sort();
+        annotation_items.dedup();
+        let annotation_item_offsets: HashMap<Vec<u8>, u32> = annotation_items
+            .iter()
+            .cloned()
+            .zip(
+                layout.section(TYPE_ANNOTATION_ITEM, 1, &annotation_items, |out, item| {
+                    out.data.extend_from_slice(item);
+                    Ok(())
+                })?,
+            )
+            .collect();
+
+        let mut annotation_sets: Vec<Vec<u32>> = all_sets
+            .iter()
+            .map(|set| {
+                set.iter()
+                    .map(|item| {
+                        let mut out = DexBuffer::default();
+                        out.u8(item.visibility);
+                        out.encoded_annotation(&item.annotation);
+                        annotation_item_offsets[&out.data]
+                    })
+                    .collect()
+            })
+            .collect();
+        let set_keys = annotation_sets.clone();
+        annotation_sets.sort();
+        annotation_sets.dedup();
+        let set_offsets: HashMap<Vec<u32>, u32> = annotation_sets
+            .iter()
+            .cloned()
+            .zip(
+                layout.section(TYPE_ANNOTATION_SET_ITEM, 4, &annotation_sets, |out, set| {
+                    out.u32(set.len() as u32);
+                    set.iter().for_each(|&off| out.u32(off));
+                    Ok(())
+                })?,
+            )
+            .collect();
+        // offset of each set, in the order `all_sets` visits them
+        let mut set_offs = set_keys.iter().map(|key| set_offsets[key]);
+
+        let mut directory_offsets = Vec::with_capacity(directories.len());
+        let mut ref_lists = Vec::new();
+        for directory in &directories {
+            let class_off = directory
+                .class_annotations
+                .as_ref()
+                .map(|_| set_offs.next());
+            let fields: Vec<(u32, u32)> = directory
+                .fields
+                .iter()
+                .map(|(field_idx, _)| (*field_idx, set_offs.next().unwrap_or(0)))
+                .collect();
+            let methods: Vec<(u32, u32)> = directory
+                .methods
+                .iter()
+                .map(|(method_idx, _)| (*method_idx, set_offs.next().unwrap_or(0)))
+                .collect();
+            let parameters: Vec<(u32, Vec<u32>)> = directory
+                .parameters
+                .iter()
+                .map(|(method_idx, sets)| {
+                    let offs = sets
+                        .iter()
+                        .map(|set| set.as_ref().map_or(0, |_| set_offs.next().unwrap_or(0)))
+                        .collect();
+                    (*method_idx, offs)
+                })
+                .collect();
+            ref_lists.extend(parameters.iter().map(|(_, offs)| offs.clone()));
+            directory_offsets.push((
+                class_off.flatten().unwrap_or(0),
+                fields,
+                methods,
+                parameters,
+            ));
+        }
+        let ref_list_offsets =
+            layout.section(TYPE_ANNOTATION_SET_REF_LIST, 4, &ref_lists, |out, list| {
+                out.u32(list.len() as u32);
+                list.iter().for_each(|&off| out.u32(off));
+                Ok(())
+            })?;
+        let mut ref_list_offs = ref_list_offsets.into_iter();
+        let directory_offsets = layout.section(
+            TYPE_ANNOTATIONS_DIRECTORY_ITEM,
+            4,
+            &directory_offsets,
+            |out, (class_off, fields, methods, parameters)| {
+                out.u32(*class_off);
+                out.u32(fields.len() as u32);
+                out.u32(methods.len() as u32);
+                out.u32(parameters.len() as u32);
+                for &(idx, off) in fields.iter().chain(methods) {
+                    out.u32(idx);
+                    out.u32(off);
+                }
+                for (method_idx, _) in parameters {
+                    out.u32(*method_idx);
+                    out.u32(ref_list_offs.next().unwrap_or(0));
+                }
+                Ok(())
+            },
+        )?;
+        let mut directory_offs = directory_offsets.into_iter();
+
+        // debug_info_item and code_item, in method order
+        let codes: Vec<&CodeModel> = classes
+            .iter()
+            .flat_map(|class| class.direct_methods.iter().chain(&class.virtual_methods))
+            .filter_map(|method| method.code.as_ref())
+            .collect();
+        let debug_infos: Vec<&Vec<u8>> = codes
+            .iter()
+            .filter_map(|code| code.debug_info.as_ref())
+            .collect();
+        let mut debug_info_offs = layout
+            .section(TYPE_DEBUG_INFO_ITEM, 1, &debug_infos, |out, info| {
+                out.data.extend_from_slice(info);
+                Ok(())
+            })?
+            .into_iter();
+        let mut code_offs = layout
+            .section(TYPE_CODE_ITEM, 4, &codes, |out, code| {
+                let debug_info_off = match code.debug_info {
+                    Some(_) => debug_info_offs.next().unwrap_or(0),
+                    None => 0,
+                };
+                Self::code_item(out, code, debug_info_off)
+            })?
+            .into_iter();
+
+        // class_data_item
+        let class_data: Vec<Vec<u8>> = classes
+            .iter()
+            .filter(|class| class.has_members())
+            .map(|class| Self::class_data(class, &mut code_offs))
+            .collect();
+        let mut class_data_offs = layout
+            .section(TYPE_CLASS_DATA_ITEM, 1, &class_data, |out, data| {
+                out.data.extend_from_slice(data);
+                Ok(())
+            })?
+            .into_iter();
+
+        // encoded_array_item of static values
+        let static_values: Vec<&Vec<EncodedValue>> = classes
+            .iter()
+            .map(|class| &class.static_values)
+            .filter(|values| !values.is_empty())
+            .collect();
+        let mut static_values_offs = layout
+            .section(TYPE_ENCODED_ARRAY_ITEM, 1, &static_values, |out, values| {
+                out.encoded_array(values);
+                Ok(())
+            })?
+            .into_iter();
+
+        // map_list
+        layout.out.align(4);
+        let map_off = layout.out.pos();
+        layout.map.push((TYPE_MAP_LIST, 1, map_off));
+        layout.out.u32(layout.map.len() as u32);
+        for &(item_type, size, offset) in &layout.map {
+            layout.out.u16(item_type);
+            layout.out.u16(0);
+            layout.out.u32(size);
+            layout.out.u32(offset);
+        }
+        layout.out.align(4);
+
+        // id sections
+        let out = &mut layout.out;
+        for (i, off) in string_offsets.iter().enumerate() {
+            out.put_u32(string_ids_off + i as u32 * 4, *off);
+        }
+        for (i, string_idx) in indexes.types.iter().enumerate() {
+            out.put_u32(type_ids_off + i as u32 * 4, *string_idx);
+        }
+        for (i, (shorty_idx, return_type_idx, params)) in indexes.protos.iter().enumerate() {
+            let at = proto_ids_off + i as u32 * 12;
+            out.put_u32(at, *shorty_idx);
+            out.put_u32(at + 4, *return_type_idx);
+            out.put_u32(at + 8, type_list_off(params));
+        }
+        for (i, (class_idx, type_idx, name_idx)) in indexes.fields.iter().enumerate() {
+            let at = field_ids_off + i as u32 * 8;
+            out.put_u16(at, *class_idx as u16);
+            out.put_u16(at + 2, *type_idx as u16);
+            out.put_u32(at + 4, *name_idx);
+        }
+        for (i, (class_idx, proto_idx, name_idx)) in indexes.methods.iter().enumerate() {
+            let at = method_ids_off + i as u32 * 8;
+            out.put_u16(at, *class_idx as u16);
+            out.put_u16(at + 2, *proto_idx as u16);
+            out.put_u32(at + 4, *name_idx);
+        }
+        for (i, class) in classes.iter().enumerate() {
+            let at = class_defs_off + i as u32 * 32;
+            out.put_u32(at, class.class_idx);
+            out.put_u32(at + 4, class.access_flags);
+            out.put_u32(at + 8, class.superclass_idx.unwrap_or(DEX_NO_INDEX));
+            out.put_u32(at + 12, type_list_off(&class.interfaces));
+            out.put_u32(at + 16, class.source_file_idx.unwrap_or(DEX_NO_INDEX));
+            let annotations_off = match class.annotations {
+                Some(_) => directory_offs.next().unwrap_or(0),
+                None => 0,
+            };
+            out.put_u32(at + 20, annotations_off);
+            let class_data_off = match class.has_members() {
+                true => class_data_offs.next().unwrap_or(0),
+                false => 0,
+            };
+            out.put_u32(at + 24, class_data_off);
+            let static_values_off = match class.static_values.is_empty() {
+                true => 0,
+                false => static_values_offs.next().unwrap_or(0),
+            };
+            out.put_u32(at + 28, static_values_off);
+        }
+
+        // header
+        let file_size = out.pos();
+        let mut magic = [0u8; 8];
+        magic[..4].copy_from_slice(b"dex\n");
+        magic[4..7].copy_from_slice(&version.as_bytes()[..3]);
+        out.data[..8].copy_from_slice(&magic);
+        out.put_u32(32, file_size);
+        out.put_u32(36, HEADER_SIZE);
+        out.put_u32(40, ENDIAN_CONSTANT);
+        out.put_u32(52, map_off);
+        let counts = [
+            indexes.strings.len(),
+            indexes.types.len(),
+            indexes.protos.len(),
+            indexes.fields.len(),
+            indexes.methods.len(),
+            classes.len(),
+        ];
+        for (i, (count, off)) in counts.iter().zip(id_offsets).enumerate() {
+            out.put_u32(56 + i as u32 * 8, *count as u32);
+            out.put_u32(60 + i as u32 * 8, off);
+        }
+        out.put_u32(104, file_size - data_off);
+        out.put_u32(108, data_off);
+
+        let signature = Sha1::digest(&out.data[32..]);
+        out.data[12..32].copy_from_slice(&signature);
+        let checksum = Self::adler32(&out.data[12..]);
+        out.put_u32(8, checksum);
+        Ok(layout.out.data)
+    }
+
+    /// write the items of a data section, returns the offset of each item
+    fn section<T>(
+        &mut self,
+        item_type: u16,
+        alignment: usize,
+        items: &[T],
+        mut write: impl FnMut(&mut DexBuffer, &T) -> Result<(), Error>,
+    ) -> Result<Vec<u32>, Error> {
+        let mut offsets = Vec::with_capacity(items.len());
+        for item in items {
+            self.out.align(alignment);
+            offsets.push(self.out.pos());
+            write(&mut self.out, item)?;
+        }
+        if let Some(&first) = offsets.first() {
+            self.map.push((item_type, items.len() as u32, first));
+        }
+        Ok(offsets)
+    }
+
+    fn code_item(out: &mut DexBuffer, code: &CodeModel, debug_info_off: u32) -> Result<(), Error> {
+        out.u16(code.registers_size);
+        out.u16(code.ins_size);
+        out.u16(code.outs_size);
+        out.u16(code.tries.len() as u16);
+        out.u32(debug_info_off);
+        out.u32(code.insns.len() as u32);
+        code.insns.iter().for_each(|&unit| out.u16(unit));
+        if code.tries.is_empty() {
+            return Ok(());
+        }
+        if code.insns.len() % 2 == 1 {
+            out.u16(0);
+        }
+
+        // handler offsets change with the uleb128 sizes of the new indexes
+        let mut handlers = DexBuffer::default();
+        handlers.uleb128(code.handlers.len() as u32);
+        let mut handler_offsets = HashMap::with_capacity(code.handlers.len());
+        for handler in &code.handlers {
+            handler_offsets.insert(handler.offset, handlers.pos());
+            let catch_size = handler.catches.len() as i32;
+            handlers.sleb128(match handler.catch_all_addr {
+                Some(_) => -catch_size,
+                None => catch_size,
+            });
+            for &(type_idx, addr) in &handler.catches {
+                handlers.uleb128(type_idx);
+                handlers.uleb128(addr);
+            }
+            if let Some(addr) = handler.catch_all_addr {
+                handlers.uleb128(addr);
+            }
+        }
+        for try_item in &code.tries {
+            let handler_off = handler_offsets
+                .get(&(try_item.handler_off as u32))
+                .copied()
+                .ok_or_else(|| {
+                    Error::DexWriteError(format!(
+                        "try_item references a missing handler at {}",
+                        try_item.handler_off
+                    ))
+                })?;
+            out.u32(try_item.start_addr);
+            out.u16(try_item.insn_count);
+            out.u16(handler_off as u16);
+        }
+        out.data.extend_from_slice(&handlers.data);
+        Ok(())
+    }
+
+    fn class_data(class: &ClassModel, code_offs: &mut impl Iterator<Item = u32>) -> Vec<u8> {
+        let mut out = DexBuffer::default();
+        out.uleb128(class.static_fields.len() as u32);
+        out.uleb128(class.instance_fields.len() as u32);
+        out.uleb128(class.direct_methods.len() as u32);
+        out.uleb128(class.virtual_methods.len() as u32);
+        for fields in [&class.static_fields, &class.instance_fields] {
+            let mut prev = 0;
+            for &(field_idx, access_flags) in fields {
+                out.uleb128(field_idx - prev);
+                out.uleb128(access_flags);
+                prev = field_idx;
+            }
+        }
+        for methods in [&class.direct_methods, &class.virtual_methods] {
+            let mut prev = 0;
+            for method in methods {
+                out.uleb128(method.method_idx - prev);
+                out.uleb128(method.access_flags);
+                out.uleb128(match method.code {
+                    Some(_) => code_offs.next().unwrap_or(0),
+                    None => 0,
+                });
+                prev = method.method_idx;
+            }
+        }
+        out.data
+    }
+
+    fn adler32(data: &[u8]) -> u32 {
+        const MOD: u32 = 65521;
+        let (mut a, mut b) = (1u32, 0u32);
+        for chunk in data.chunks(5552) {
+            for &byte in chunk {
+                a += byte as u32;
+                b += a;
+            }
+            a %= MOD;
+            b %= MOD;
+        }
+        b << 16 | a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{DexReader, ExportDependencies, export_selection};
+
+    #[test]
+    fn test_leb128() {
+        let mut out = DexBuffer::default();
+        out.uleb128(0);
+        out.uleb128(948);
+        out.uleb128(u32::MAX);
+        out.uleb128p1(None);
+        out.sleb128(-1);
+        out.sleb128(-128);
+        out.sleb128(63);
+        out.sleb128(64);
+        let mut reader = DexReader::new(&out.data, 0);
+        assert_eq!(reader.uleb128().unwrap(), 0);
+        assert_eq!(reader.uleb128().unwrap(), 948);
+        assert_eq!(reader.uleb128().unwrap(), u32::MAX);
+        assert_eq!(reader.uleb128p1().unwrap(), None);
+        assert_eq!(reader.sleb128().unwrap(), -1);
+        assert_eq!(reader.sleb128().unwrap(), -128);
+        assert_eq!(reader.sleb128().unwrap(), 63);
+        assert_eq!(reader.sleb128().unwrap(), 64);
+        assert_eq!(reader.pos(), out.data.len());
+    }
+
+    #[test]
+    fn test_encoded_value() {
+        let values = [
+            EncodedValue::Byte(-1),
+            EncodedValue::Short(-129),
+            EncodedValue::Char(0xffff),
+            EncodedValue::Int(i32::MIN),
+            EncodedValue::Int(127),
+            EncodedValue::Long(-1),
+            EncodedValue::Long(i64::MAX),
+            EncodedValue::Float(1.0),
+            EncodedValue::Double(-2.5),
+            EncodedValue::String(0x1_0000),
+            EncodedValue::Enum(0),
+            EncodedValue::Array(vec![EncodedValue::Null, EncodedValue::Boolean(true)]),
+            EncodedValue::Annotation(EncodedAnnotation {
+                type_idx: 3,
+                elements: vec![(1, EncodedValue::Boolean(false))],
+            }),
+        ];
+        let mut out = DexBuffer::default();
+        values.iter().for_each(|value| out.encoded_value(value));
+        let mut reader = DexReader::new(&out.data, 0);
+        for value in &values {
+            assert_eq!(&EncodedValue::read(&mut reader).unwrap(), value);
+        }
+        assert_eq!(reader.pos(), out.data.len());
+        // 1.0f is 0x3f800000, only the high-order bytes are stored
+        let mut out = DexBuffer::default();
+        out.encoded_value(&EncodedValue::Float(1.0));
+        assert_eq!(out.data, [0x10 | 1 << 5, 0x80, 0x3f]);
+    }
+
+    #[test]
+    fn test_string_data() {
+        let mut out = DexBuffer::default();
+        out.string_data("a\0\u{e9}\u{1f600}");
+        assert_eq!(
+            out.data,
+            [
+                0x05, b'a', 0xc0, 0x80, 0xc3, 0xa9, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80, 0x00
+            ]
+        );
+    }
+
+    fn sample_dex() -> Vec<u8> {
+        let class = "Lcom/example/Foo;".to_string();
+        let object = "Ljava/lang/Object;".to_string();
+        let string = "Ljava/lang/String;".to_string();
+        let tag = "Lcom/example/Tag;".to_string();
+        let run = (
+            class.clone(),
+            "run".to_string(),
+            ("V".to_string(), Vec::new()),
+        );
+        let field = (class.clone(), "NAME".to_string(), string.clone());
+
+        let mut pools = Pools::default();
+        let mut remap = Remap::Collect(&mut pools);
+        for descriptor in [&class, &object, &string, &tag] {
+            remap.type_value(descriptor.clone()).unwrap();
+        }
+        for value in ["hello", "value", "Foo.java"] {
+            remap.string_value(value.to_string()).unwrap();
+        }
+        remap.proto_value(run.2.clone()).unwrap();
+        remap.string_value(run.1.clone()).unwrap();
+        remap.string_value(field.1.clone()).unwrap();
+        pools.methods.insert(run.clone());
+        pools.fields.insert(field.clone());
+        let indexes = Indexes::new(pools).unwrap();
+
+        let hello = indexes.string_index["hello"];
+        let model = ClassModel {
+            class_idx: indexes.type_index[&class],
+            access_flags: 0x1,
+            superclass_idx: Some(indexes.type_index[&object]),
+            interfaces: Vec::new(),
+            source_file_idx: Some(indexes.string_index["Foo.java"]),
+            annotations: Some(DirectoryModel {
+                class_annotations: Some(vec![AnnotationItem {
+                    visibility: 1,
+                    annotation: EncodedAnnotation {
+                        type_idx: indexes.type_index[&tag],
+                        elements: vec![(
+                            indexes.string_index["value"],
+                            EncodedValue::String(hello),
+                        )],
+                    },
+                }]),
+                ..Default::default()
+            }),
+            static_fields: vec![(indexes.field_index[&field], 0x19)],
+            instance_fields: Vec::new(),
+            direct_methods: vec![MethodModel {
+                method_idx: indexes.method_index[&run],
+                access_flags: 0x9,
+                code: Some(CodeModel {
+                    registers_size: 1,
+                    ins_size: 0,
+                    outs_size: 0,
+                    // const-string v0, "hello"; return-void
+                    insns: vec![0x001a, hello as u16, 0x000e],
+                    tries: Vec::new(),
+                    handlers: Vec::new(),
+                    debug_info: None,
+                }),
+            }],
+            virtual_methods: Vec::new(),
+            static_values: vec![EncodedValue::String(hello)],
+        };
+        DexLayout::write("035", &indexes, vec![model]).unwrap()
+    }
+
+    #[test]
+    fn test_write_dex() {
+        let data = sample_dex();
+        let dex = DexFile::parse(0, "classes.dex", data.clone()).unwrap();
+        let header = dex.header();
+        assert_eq!(header.version, "035");
+        assert_eq!(header.file_size as usize, data.len());
+        assert_eq!(header.checksum, DexLayout::adler32(&data[12..]));
+        assert_eq!(
+            header.signature.as_slice(),
+            Sha1::digest(&data[32..]).as_slice()
+        );
+
+        let class_defs = dex.class_defs().unwrap();
+        assert_eq!(class_defs.len(), 1);
+        let class_def = class_defs[0];
+        assert_eq!(
+            dex.type_descriptor(class_def.class_idx).unwrap(),
+            "Lcom/example/Foo;"
+        );
+        let values = dex.static_values(&class_def).unwrap();
+        let [EncodedValue::String(hello)] = values[..] else {
+            panic!("unexpected static values {:?}", values);
+        };
+        assert_eq!(dex.string(hello).unwrap(), "hello");
+
+        let class_data = dex.class_data(&class_def).unwrap().unwrap();
+        let method = class_data.direct_methods[0];
+        let method_id = dex.method_id(method.method_idx).unwrap();
+        assert_eq!(dex.string(method_id.name_idx).unwrap(), "run");
+        let code = dex.code_item(method.code_off).unwrap().unwrap();
+        assert_eq!(code.insns, [0x001a, hello as u16, 0x000e]);
+
+        let directory = dex.annotations_directory(&class_def).unwrap().unwrap();
+        let annotations = dex.annotation_set(directory.class_annotations_off).unwrap();
+        assert_eq!(
+            dex.type_descriptor(annotations[0].annotation.type_idx)
+                .unwrap(),
+            "Lcom/example/Tag;"
+        );
+
+        // exporting the class again gives the same file
+        let exported = DexWriter::write(&[(&dex, class_def)]).unwrap();
+        assert_eq!(exported, data);
+        let referenced = DexWriter::referenced_types(&dex, &class_def).unwrap();
+        assert!(referenced.contains("Lcom/example/Tag;"));
+    }
+
+    #[test]
+    fn test_export_selection() {
+        let dex = DexFile::parse(0, "classes.dex", sample_dex()).unwrap();
+        let dex_files = [dex];
+        let class_idx = dex_files[0].class_defs().unwrap()[0].class_idx;
+        let selection =
+            export_selection(&dex_files, &[(0, class_idx)], ExportDependencies::All).unwrap();
+        assert_eq!(selection.len(), 1);
+        assert!(
+            export_selection(&dex_files, &[(0, class_idx + 1)], ExportDependencies::None).is_err()
+        );
+        assert!(export_selection(&dex_files, &[(1, class_idx)], ExportDependencies::None).is_err());
+    }
+}
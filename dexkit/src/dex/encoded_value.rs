@@ -0,0 +1,199 @@
+use crate::dex::DexReader;
+use crate::errors::Error;
+
+/// encoded_value of the dex format, references are indexes into the id tables of the same dex
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodedValue {
+    Byte(i8),
+    Short(i16),
+    Char(u16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// proto_ids index
+    MethodType(u32),
+    /// method_handles index
+    MethodHandle(u32),
+    /// string_ids index
+    String(u32),
+    /// type_ids index
+    Type(u32),
+    /// field_ids index
+    Field(u32),
+    /// method_ids index
+    Method(u32),
+    /// field_ids index of the enum constant
+    Enum(u32),
+    Array(Vec<EncodedValue>),
+    Annotation(EncodedAnnotation),
+    Null,
+    Boolean(bool),
+}
+
+/// encoded_annotation, `elements` are (string_ids index of the name, value)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedAnnotation {
+    pub type_idx: u32,
+    pub elements: Vec<(u32, EncodedValue)>,
+}
+
+impl EncodedValue {
+    /// string_ids indexes referenced by the value, including nested arrays and annotations
+    pub fn string_indexes(&self) -> Vec<u32> {
+        let mut indexes = Vec::new();
+        self.collect_string_indexes(&mut indexes);
+        indexes
+    }
+
+    fn collect_string_indexes(&self, indexes: &mut Vec<u32>) {
+        match self {
+            EncodedValue::String(idx) => indexes.push(*idx),
+            EncodedValue::Array(values) => values
+                .iter()
+                .for_each(|value| value.collect_string_indexes(indexes)),
+            EncodedValue::Annotation(annotation) => annotation
+                .elements
+                .iter()
+                .for_each(|(_, value)| value.collect_string_indexes(indexes)),
+            _ => {}
+        }
+    }
+
+    /// The default value of a field of type `descriptor`: 0, false or null.
+    /// Static fields past the end of the static_values of their class hold it.
+    pub fn default_of(descriptor: &str) -> Self {
+        match descriptor {
+            "Z" => EncodedValue::Boolean(false),
+            "B" => EncodedValue::Byte(0),
+            "S" => EncodedValue::Short(0),
+            "C" => EncodedValue::Char(0),
+            "I" => EncodedValue::Int(0),
+            "J" => EncodedValue::Long(0),
+            "F" => EncodedValue::Float(0.0),
+            "D" => EncodedValue::Double(0.0),
+            _ => EncodedValue::Null,
+        }
+    }
+
+    /// internal use, decode an encoded_value
+    pub(crate) fn read(reader: &mut DexReader) -> Result<Self, Error> {
+        let header = reader.u8()?;
+        let value_arg = (header >> 5) as usize;
+        let value_type = header & 0x1f;
+        let size = value_arg + 1;
+        let value = match value_type {
+            0x00 => EncodedValue::Byte(reader.u8()? as i8),
+            0x02 => EncodedValue::Short(Self::read_signed(reader, size)? as i16),
+            0x03 => EncodedValue::Char(reader.sized_u64(size)? as u16),
+            0x04 => EncodedValue::Int(Self::read_signed(reader, size)? as i32),
+            0x06 => EncodedValue::Long(Self::read_signed(reader, size)?),
+            0x10 => {
+                let bits = reader.sized_u64(size)? << ((4 - size.min(4)) * 8);
+                EncodedValue::Float(f32::from_bits(bits as u32))
+            }
+            0x11 => {
+                let bits = reader.sized_u64(size)? << ((8 - size) * 8);
+                EncodedValue::Double(f64::from_bits(bits))
+            }
+            0x15 => EncodedValue::MethodType(reader.sized_u64(size)? as u32),
+            0x16 => EncodedValue::MethodHandle(reader.sized_u64(size)? as u32),
+            0x17 => EncodedValue::String(reader.sized_u64(size)? as u32),
+            0x18 => EncodedValue::Type(reader.sized_u64(size)? as u32),
+            0x19 => EncodedValue::Field(reader.sized_u64(size)? as u32),
+            0x1a => EncodedValue::Method(reader.sized_u64(size)? as u32),
+            0x1b => EncodedValue::Enum(reader.sized_u64(size)? as u32),
+            0x1c => EncodedValue::Array(Self::read_array(reader)?),
+            0x1d => EncodedValue::Annotation(Self::read_annotation(reader)?),
+            0x1e => EncodedValue::Null,
+            0x1f => EncodedValue::Boolean(value_arg != 0),
+            _ => {
+                return Err(Error::DexParseError(format!(
+                    "invalid encoded value type 0x{:X} at 0x{:X}",
+                    value_type,
+                    reader.pos() - 1
+                )));
+            }
+        };
+        Ok(value)
+    }
+
+    /// internal use, decode an encoded_array
+    pub(crate) fn read_array(reader: &mut DexReader) -> Result<Vec<Self>, Error> {
+        let size = reader.uleb128()?;
+        (0..size).map(|_| Self::read(reader)).collect()
+    }
+
+    /// internal use, decode an encoded_annotation
+    pub(crate) fn read_annotation(reader: &mut DexReader) -> Result<EncodedAnnotation, Error> {
+        let type_idx = reader.uleb128()?;
+        let size = reader.uleb128()?;
+        let elements = (0..size)
+            .map(|_| -> Result<(u32, EncodedValue), Error> {
+                let name_idx = reader.uleb128()?;
+                Ok((name_idx, Self::read(reader)?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(EncodedAnnotation { type_idx, elements })
+    }
+
+    /// internal use, little-endian value of `size` bytes, sign extended
+    fn read_signed(reader: &mut DexReader, size: usize) -> Result<i64, Error> {
+        let shift = (8 - size.min(8)) * 8;
+        Ok(((reader.sized_u64(size)? << shift) as i64) >> shift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &[u8]) -> EncodedValue {
+        EncodedValue::read(&mut DexReader::new(data, 0)).unwrap()
+    }
+
+    #[test]
+    fn test_read_int() {
+        // VALUE_INT, 1 byte, -1
+        assert_eq!(read(&[0x04, 0xff]), EncodedValue::Int(-1));
+        // VALUE_INT, 2 bytes, 0x1234
+        assert_eq!(read(&[0x24, 0x34, 0x12]), EncodedValue::Int(0x1234));
+        // VALUE_LONG, 1 byte, -2
+        assert_eq!(read(&[0x06, 0xfe]), EncodedValue::Long(-2));
+    }
+
+    #[test]
+    fn test_read_float() {
+        // VALUE_FLOAT, 2 bytes, 1.0f (0x3f800000) right zero extended
+        assert_eq!(read(&[0x30, 0x80, 0x3f]), EncodedValue::Float(1.0));
+        // VALUE_DOUBLE, 2 bytes, 2.0 (0x4000000000000000)
+        assert_eq!(read(&[0x31, 0x00, 0x40]), EncodedValue::Double(2.0));
+    }
+
+    #[test]
+    fn test_read_array() {
+        // VALUE_ARRAY of [VALUE_STRING 5, VALUE_NULL, VALUE_BOOLEAN true]
+        let value = read(&[0x1c, 0x03, 0x17, 0x05, 0x1e, 0x3f]);
+        assert_eq!(
+            value,
+            EncodedValue::Array(vec![
+                EncodedValue::String(5),
+                EncodedValue::Null,
+                EncodedValue::Boolean(true),
+            ])
+        );
+        assert_eq!(value.string_indexes(), vec![5]);
+    }
+
+    #[test]
+    fn test_default_of() {
+        assert_eq!(EncodedValue::default_of("I"), EncodedValue::Int(0));
+        assert_eq!(EncodedValue::default_of("Z"), EncodedValue::Boolean(false));
+        assert_eq!(EncodedValue::default_of("J"), EncodedValue::Long(0));
+        assert_eq!(
+            EncodedValue::default_of("Ljava/lang/String;"),
+            EncodedValue::Null
+        );
+        assert_eq!(EncodedValue::default_of("[I"), EncodedValue::Null);
+    }
+}
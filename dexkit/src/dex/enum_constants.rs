@@ -0,0 +1,244 @@
+use crate::dex::{ClassDef, CodeItem, DexFile, EncodedValue};
+use crate::errors::Error;
+use std::collections::HashMap;
+
+/// An enum constant recovered from the static initializer of its class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumConstantItem {
+    /// field_ids index of the static field holding the constant
+    pub field_idx: u32,
+    /// the name passed to `Enum(String, int)`, kept when the field is renamed
+    pub name: String,
+    pub ordinal: u32,
+}
+
+impl CodeItem {
+    /// internal use, (field_ids index, string_ids index of the name, ordinal) of every
+    /// `new-instance`, `invoke-direct <init>(name, ordinal, ...)`, `sput-object` sequence,
+    /// in code order. `is_constructor` tells if a method_ids index is an `<init>`.
+    pub(crate) fn enum_constant_writes(
+        &self,
+        is_constructor: impl Fn(u32) -> bool,
+    ) -> Vec<(u32, u32, u32)> {
+        // register -> string_ids index / literal / (name, ordinal) of a constructed instance
+        let mut strings: HashMap<u16, u32> = HashMap::new();
+        let mut literals: HashMap<u16, i64> = HashMap::new();
+        let mut constructed: HashMap<u16, (u32, u32)> = HashMap::new();
+        let mut writes = Vec::new();
+        for instruction in self.instructions() {
+            let units = instruction.units();
+            let register_aa = units[0] >> 8;
+            for register in instruction.written_registers() {
+                strings.remove(&register);
+                literals.remove(&register);
+                constructed.remove(&register);
+            }
+            match instruction.opcode() {
+                // const-string vAA, const-string/jumbo vAA
+                0x1a | 0x1b => {
+                    if let Some(idx) = instruction.string_index() {
+                        strings.insert(register_aa, idx);
+                    }
+                }
+                // const/4 vA, const/16 vAA, const vAA
+                opcode @ (0x12..=0x14) => {
+                    let register = match opcode {
+                        0x12 => register_aa & 0xf,
+                        _ => register_aa,
+                    };
+                    if let Some(EncodedValue::Int(value)) = instruction.literal() {
+                        literals.insert(register, value as i64);
+                    }
+                }
+                // invoke-direct {vC, vD, vE, vF, vG}, invoke-direct/range {vCCCC .. vNNNN}
+                opcode @ (0x70 | 0x76) if units.len() >= 3 && is_constructor(units[1] as u32) => {
+                    let registers: Vec<u16> = match opcode {
+                        0x70 => {
+                            let count = (units[0] >> 12) as usize;
+                            let args = [
+                                units[2] & 0xf,
+                                (units[2] >> 4) & 0xf,
+                                (units[2] >> 8) & 0xf,
+                                units[2] >> 12,
+                                (units[0] >> 8) & 0xf,
+                            ];
+                            args[..count.min(5)].to_vec()
+                        }
+                        _ => (units[2]..units[2].saturating_add(register_aa)).collect(),
+                    };
+                    if let [instance, name, ordinal, ..] = registers[..]
+                        && let Some(&name) = strings.get(&name)
+                        && let Some(&ordinal) = literals.get(&ordinal)
+                        && ordinal >= 0
+                    {
+                        constructed.insert(instance, (name, ordinal as u32));
+                    }
+                }
+                // sput-object vAA, field@BBBB
+                0x69 if units.len() >= 2 => {
+                    if let Some(&(name, ordinal)) = constructed.get(&register_aa) {
+                        writes.push((units[1] as u32, name, ordinal));
+                    }
+                }
+                _ => {}
+            }
+        }
+        writes
+    }
+}
+
+impl DexFile {
+    /// Recover the constants of an enum class from its `<clinit>`, ordered by ordinal.
+    /// Empty for classes without static initializer. Only fields of the enum type declared
+    /// by the class itself are reported, e.g. not the `$VALUES` array.
+    pub fn enum_constants(&self, class_def: &ClassDef) -> Result<Vec<EnumConstantItem>, Error> {
+        let Some(class_data) = self.class_data(class_def)? else {
+            return Ok(Vec::new());
+        };
+        let mut clinit = None;
+        for method in &class_data.direct_methods {
+            let method_id = self.method_id(method.method_idx)?;
+            if self.string(method_id.name_idx)? == "<clinit>" {
+                clinit = self.code_item(method.code_off)?;
+                break;
+            }
+        }
+        let Some(code) = clinit else {
+            return Ok(Vec::new());
+        };
+
+        let is_constructor = |method_idx: u32| {
+            self.method_id(method_idx)
+                .and_then(|method_id| self.string(method_id.name_idx))
+                .is_ok_and(|name| name == "<init>")
+        };
+        let mut constants = Vec::new();
+        for (field_idx, name_idx, ordinal) in code.enum_constant_writes(is_constructor) {
+            let field_id = self.field_id(field_idx)?;
+            if field_id.class_idx != class_def.class_idx
+                || field_id.type_idx != class_def.class_idx
+                || constants
+                    .iter()
+                    .any(|c: &EnumConstantItem| c.field_idx == field_idx)
+            {
+                continue;
+            }
+            constants.push(EnumConstantItem {
+                field_idx,
+                name: self.string(name_idx)?,
+                ordinal,
+            });
+        }
+        constants.sort_by_key(|c| c.ordinal);
+        Ok(constants)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_item(insns: Vec<u16>) -> CodeItem {
+        CodeItem {
+            offset: 0,
+            registers_size: 4,
+            ins_size: 0,
+            outs_size: 3,
+            tries_size: 0,
+            debug_info_off: 0,
+            insns,
+            tries: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_enum_constant_writes() {
+        let insns = vec![
+            // new-instance v0, type@1
+            0x0022, 0x0001, //
+            // const-string v1, string@7 "RED"
+            0x011a, 0x0007, //
+            // const/4 v2, #0
+            0x0212, //
+            // invoke-direct {v0, v1, v2}, method@3 <init>
+            0x3070, 0x0003, 0x0210, //
+            // sput-object v0, field@10
+            0x0069, 0x000a, //
+            // new-instance v0, type@2, a constant with a body
+            0x0022, 0x0002, //
+            // const-string v1, string@8 "GREEN"
+            0x011a, 0x0008, //
+            // const/4 v2, #1
+            0x1212, //
+            // invoke-direct/range {v0 .. v2}, method@4 <init>
+            0x0376, 0x0004, 0x0000, //
+            // sput-object v0, field@11
+            0x0069, 0x000b, //
+            // invoke-direct {v0, v1, v2}, method@5 not a constructor
+            0x3070, 0x0005, 0x0210, //
+            // sput-object v3, field@12, never constructed
+            0x0369, 0x000c, //
+            // return-void
+            0x000e,
+        ];
+        let code = code_item(insns);
+        let writes = code.enum_constant_writes(|idx| idx == 3 || idx == 4);
+        assert_eq!(writes, vec![(10, 7, 0), (11, 8, 1)]);
+    }
+
+    #[test]
+    fn test_overwritten_registers() {
+        let insns = vec![
+            // new-instance v0, type@1
+            0x0022, 0x0001, //
+            // const-string v1, string@7
+            0x011a, 0x0007, //
+            // const/4 v2, #0
+            0x0212, //
+            // invoke-direct {v0, v1, v2}, method@3 <init>
+            0x3070, 0x0003, 0x0210, //
+            // sget-object v0, field@20 replaces the constructed instance
+            0x0062, 0x0014, //
+            // sput-object v0, field@10
+            0x0069, 0x000a, //
+            // new-instance v0, type@1
+            0x0022, 0x0001, //
+            // const-string v1, string@8
+            0x011a, 0x0008, //
+            // move-result-object v1 replaces the name
+            0x010c, //
+            // const/4 v2, #1
+            0x1212, //
+            // invoke-direct {v0, v1, v2}, method@3 <init>
+            0x3070, 0x0003, 0x0210, //
+            // sput-object v0, field@11
+            0x0069, 0x000b, //
+            // const-string v1, string@9
+            0x011a, 0x0009, //
+            // const/4 v2, #2
+            0x2212, //
+            // const-wide/16 v1, #0 replaces the name and the ordinal
+            0x0116, 0x0000, //
+            // new-instance v0, type@1
+            0x0022, 0x0001, //
+            // invoke-direct {v0, v1, v2}, method@3 <init>
+            0x3070, 0x0003, 0x0210, //
+            // sput-object v0, field@12
+            0x0069, 0x000c, //
+            // const-string v1, string@9
+            0x011a, 0x0009, //
+            // const/4 v2, #2
+            0x2212, //
+            // invoke-direct {v0, v1, v2}, method@3 <init>
+            0x3070, 0x0003, 0x0210, //
+            // sput-object v0, field@13
+            0x0069, 0x000d, //
+            // return-void
+            0x000e,
+        ];
+        let code = code_item(insns);
+        let writes = code.enum_constant_writes(|idx| idx == 3);
+        assert_eq!(writes, vec![(13, 9, 2)]);
+    }
+}
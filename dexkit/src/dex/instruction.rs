@@ -0,0 +1,413 @@
+use crate::dex::EncodedValue;
+use std::ops::Range;
+
+/// One dalvik instruction of a method body, or a switch / array payload.
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction<'c> {
+    offset: u32,
+    units: &'c [u16],
+}
+
+impl<'c> Instruction<'c> {
+    /// offset of the instruction in the method body, in 16-bit code units
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// raw code units of the instruction, including operands
+    pub fn units(&self) -> &'c [u16] {
+        self.units
+    }
+
+    /// opcode of the instruction, payloads report `0x00` (nop)
+    pub fn opcode(&self) -> u8 {
+        (self.units[0] & 0xff) as u8
+    }
+
+    /// is a packed-switch, sparse-switch or fill-array-data payload
+    pub fn is_payload(&self) -> bool {
+        matches!(self.units[0], 0x0100 | 0x0200 | 0x0300)
+    }
+
+    /// string_ids index referenced by const-string and const-string/jumbo
+    pub fn string_index(&self) -> Option<u32> {
+        if !self.is_complete() {
+            return None;
+        }
+        match self.opcode() {
+            0x1a => Some(self.units[1] as u32),
+            0x1b => Some(self.u32_at(1)),
+            _ => None,
+        }
+    }
+
+    /// type_ids index referenced by const-class, check-cast, instance-of, new-instance,
+    /// new-array and filled-new-array
+    pub fn type_index(&self) -> Option<u32> {
+        if !self.is_complete() {
+            return None;
+        }
+        match self.opcode() {
+            0x1c | 0x1f | 0x20 | 0x22 | 0x23 | 0x24 | 0x25 => Some(self.units[1] as u32),
+            _ => None,
+        }
+    }
+
+    /// Number loaded by a const* instruction, as the `Int` or `Long` of its bits.
+    /// The dex format does not record the type of a literal, see `literals` for the
+    /// floating point readings.
+    pub fn literal(&self) -> Option<EncodedValue> {
+        if !self.is_complete() {
+            return None;
+        }
+        let literal = match self.opcode() {
+            // const/4 vA, #+B
+            0x12 => EncodedValue::Int(((self.units[0] as i16) >> 12) as i32),
+            // const/16 vAA, #+BBBB
+            0x13 => EncodedValue::Int(self.units[1] as i16 as i32),
+            // const vAA, #+BBBBBBBB
+            0x14 => EncodedValue::Int(self.u32_at(1) as i32),
+            // const/high16 vAA, #+BBBB0000
+            0x15 => EncodedValue::Int(((self.units[1] as u32) << 16) as i32),
+            // const-wide/16 vAA, #+BBBB
+            0x16 => EncodedValue::Long(self.units[1] as i16 as i64),
+            // const-wide/32 vAA, #+BBBBBBBB
+            0x17 => EncodedValue::Long(self.u32_at(1) as i32 as i64),
+            // const-wide vAA, #+BBBBBBBBBBBBBBBB
+            0x18 => {
+                EncodedValue::Long((self.u32_at(1) as u64 | (self.u32_at(3) as u64) << 32) as i64)
+            }
+            // const-wide/high16 vAA, #+BBBB000000000000
+            0x19 => EncodedValue::Long(((self.units[1] as u64) << 48) as i64),
+            _ => return None,
+        };
+        Some(literal)
+    }
+
+    /// Numbers loaded by a const* instruction, one per type the literal may have.
+    /// The dex format does not record the type of a literal, so the forms D8 emits for
+    /// floating point constants are read both ways: const and const/high16 as `Int` and
+    /// `Float`, const-wide and const-wide/high16 as `Long` and `Double`. const/high16 also
+    /// holds ints whose low 16 bits are zero, e.g. resource ids like `0x7f020000`.
+    /// The narrower forms only hold small integers and are read as `Int` or `Long`.
+    pub fn literals(&self) -> impl Iterator<Item = EncodedValue> + use<> {
+        let literal = self.literal();
+        let float = match literal {
+            Some(EncodedValue::Int(bits)) if matches!(self.opcode(), 0x14 | 0x15) => {
+                Some(EncodedValue::Float(f32::from_bits(bits as u32)))
+            }
+            Some(EncodedValue::Long(bits)) if matches!(self.opcode(), 0x18 | 0x19) => {
+                Some(EncodedValue::Double(f64::from_bits(bits as u64)))
+            }
+            _ => None,
+        };
+        literal.into_iter().chain(float)
+    }
+
+    /// internal use, registers the instruction stores a result into, two for wide results,
+    /// empty for instructions without a destination register
+    pub(crate) fn written_registers(&self) -> Range<u16> {
+        if self.is_payload() || !self.is_complete() {
+            return 0..0;
+        }
+        let opcode = self.opcode();
+        let register = match opcode {
+            // move/16, move-wide/16, move-object/16 vAAAA, vBBBB
+            0x03 | 0x06 | 0x09 => self.units[1],
+            // 12x, 11n and 22* formats: vA
+            0x01
+            | 0x04
+            | 0x07
+            | 0x12
+            | 0x20
+            | 0x21
+            | 0x23
+            | 0x52..=0x58
+            | 0x7b..=0x8f
+            | 0xb0..=0xd7 => (self.units[0] >> 8) & 0xf,
+            // 11x, 21*, 22x, 22b, 23x, 31* and 51l formats: vAA
+            0x02
+            | 0x05
+            | 0x08
+            | 0x0a..=0x0d
+            | 0x13..=0x1c
+            | 0x22
+            | 0x2d..=0x31
+            | 0x44..=0x4a
+            | 0x60..=0x66
+            | 0x90..=0xaf
+            | 0xd8..=0xe2
+            | 0xfe
+            | 0xff => self.units[0] >> 8,
+            _ => return 0..0,
+        };
+        let wide = matches!(
+            opcode,
+            0x04..=0x06
+                | 0x0b
+                | 0x16..=0x19
+                | 0x45
+                | 0x53
+                | 0x61
+                | 0x7d
+                | 0x7e
+                | 0x80
+                | 0x81
+                | 0x83
+                | 0x86
+                | 0x88
+                | 0x89
+                | 0x8b
+                | 0x9b..=0xa5
+                | 0xab..=0xaf
+                | 0xbb..=0xc5
+                | 0xcb..=0xcf
+        );
+        register..register.saturating_add(if wide { 2 } else { 1 })
+    }
+
+    /// internal use, the instruction is not truncated by the end of the method body
+    fn is_complete(&self) -> bool {
+        self.is_payload() || self.units.len() >= OPCODE_WIDTH[self.opcode() as usize] as usize
+    }
+
+    /// internal use, 32-bit operand starting at code unit `index`
+    fn u32_at(&self, index: usize) -> u32 {
+        self.units[index] as u32 | (self.units[index + 1] as u32) << 16
+    }
+
+    /// width in code units of the instruction starting at `units[0]`, 1 for invalid data
+    pub(crate) fn width(units: &[u16]) -> usize {
+        let unit = units[0];
+        let width = match unit {
+            // packed-switch-payload: ident, size, first_key, targets
+            0x0100 => units.get(1).map_or(1, |&size| 4 + size as usize * 2),
+            // sparse-switch-payload: ident, size, keys, targets
+            0x0200 => units.get(1).map_or(1, |&size| 2 + size as usize * 4),
+            // fill-array-data-payload: ident, element_width, size, data
+            0x0300 => match (units.get(1), units.get(2), units.get(3)) {
+                (Some(&element_width), Some(&low), Some(&high)) => {
+                    let size = low as usize | (high as usize) << 16;
+                    4 + (size * element_width as usize).div_ceil(2)
+                }
+                _ => 1,
+            },
+            _ => OPCODE_WIDTH[(unit & 0xff) as usize] as usize,
+        };
+        width.max(1)
+    }
+}
+
+/// Iterator over the instructions of a method body.
+pub struct Instructions<'c> {
+    insns: &'c [u16],
+    pos: usize,
+}
+
+impl<'c> Instructions<'c> {
+    pub fn new(insns: &'c [u16]) -> Self {
+        Self { insns, pos: 0 }
+    }
+}
+
+impl<'c> Iterator for Instructions<'c> {
+    type Item = Instruction<'c>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.insns.len() {
+            return None;
+        }
+        let rest = &self.insns[self.pos..];
+        let width = Instruction::width(rest).min(rest.len());
+        let instruction = Instruction {
+            offset: self.pos as u32,
+            units: &rest[..width],
+        };
+        self.pos += width;
+        Some(instruction)
+    }
+}
+
+/// instruction width in code units, indexed by opcode
+#[rustfmt::skip]
+static OPCODE_WIDTH: [u8; 256] = [
+    // 0x00: nop, move*, move-result*, return*
+    1, 1, 2, 3, 1, 2, 3, 1, 2, 3, 1, 1, 1, 1, 1, 1,
+    // 0x10: return*, const*, const-string*, const-class, monitor-*, check-cast
+    1, 1, 1, 2, 3, 2, 2, 3, 5, 2, 2, 3, 2, 1, 1, 2,
+    // 0x20: instance-of, array-length, new-*, filled-new-array*, fill-array-data, throw, goto*, switch
+    2, 1, 2, 2, 3, 3, 3, 1, 1, 2, 3, 3, 3, 2, 2, 2,
+    // 0x30: cmp*, if-*, unused
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1,
+    // 0x40: unused, aget*, aput*
+    1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    // 0x50: aput*, iget*, iput*
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    // 0x60: sget*, sput*, invoke-*
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3,
+    // 0x70: invoke-*, unused, invoke-*/range, unused, unop
+    3, 3, 3, 1, 3, 3, 3, 3, 3, 1, 1, 1, 1, 1, 1, 1,
+    // 0x80: unop
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    // 0x90: binop
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    // 0xa0: binop
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    // 0xb0: binop/2addr
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    // 0xc0: binop/2addr
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    // 0xd0: binop/lit16, binop/lit8
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    // 0xe0: binop/lit8, unused
+    2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    // 0xf0: unused, invoke-polymorphic*, invoke-custom*, const-method-handle, const-method-type
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 4, 4, 3, 3, 2, 2,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_width() {
+        // const-string v0, string@0x0001
+        assert_eq!(Instruction::width(&[0x001a, 0x0001]), 2);
+        // const-wide v0, #long
+        assert_eq!(Instruction::width(&[0x0018, 0, 0, 0, 0]), 5);
+        // invoke-virtual {v0}, method@0
+        assert_eq!(Instruction::width(&[0x106e, 0, 0]), 3);
+        // return-void
+        assert_eq!(Instruction::width(&[0x000e]), 1);
+    }
+
+    #[test]
+    fn test_payload_width() {
+        // packed-switch-payload with 2 targets
+        assert_eq!(Instruction::width(&[0x0100, 2, 0, 0, 0, 0, 0, 0]), 8);
+        // sparse-switch-payload with 1 key
+        assert_eq!(Instruction::width(&[0x0200, 1, 0, 0, 0, 0]), 6);
+        // fill-array-data-payload with 3 bytes
+        assert_eq!(Instruction::width(&[0x0300, 1, 3, 0, 0, 0]), 6);
+    }
+
+    #[test]
+    fn test_instructions() {
+        // const-string v0, string@0x0005; const-string/jumbo v1, string@0x00010002; return-void
+        let insns = [0x001a, 0x0005, 0x011b, 0x0002, 0x0001, 0x000e];
+        let instructions: Vec<_> = Instructions::new(&insns).collect();
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[0].string_index(), Some(5));
+        assert_eq!(instructions[1].offset(), 2);
+        assert_eq!(instructions[1].string_index(), Some(0x0001_0002));
+        assert_eq!(instructions[2].opcode(), 0x0e);
+        assert_eq!(instructions[2].string_index(), None);
+    }
+
+    #[test]
+    fn test_written_registers() {
+        let written = |insns: &[u16]| Instructions::new(insns).next().unwrap().written_registers();
+        // move-result-object v3
+        assert_eq!(written(&[0x030c]), 3..4);
+        // const/4 v2, #0
+        assert_eq!(written(&[0x0212]), 2..3);
+        // iget-object v1, v0, field@0
+        assert_eq!(written(&[0x0154, 0x0000]), 1..2);
+        // move/16 v300, v0
+        assert_eq!(written(&[0x0009, 300, 0]), 300..301);
+        // const-wide/16 v4, #1
+        assert_eq!(written(&[0x0416, 0x0001]), 4..6);
+        // shl-long v2, v4, v6 and ushr-long v2, v4, v6
+        assert_eq!(written(&[0x02a3, 0x0604]), 2..4);
+        assert_eq!(written(&[0x02a5, 0x0604]), 2..4);
+        // shr-long/2addr v4, v6 and ushr-long/2addr v4, v6
+        assert_eq!(written(&[0x64c4]), 4..6);
+        assert_eq!(written(&[0x64c5]), 4..6);
+        // add-float v2, v4, v6 and add-int/2addr v4, v6 stay narrow
+        assert_eq!(written(&[0x02a6, 0x0604]), 2..3);
+        assert_eq!(written(&[0x64b0]), 4..5);
+        // throw v0
+        assert!(written(&[0x0027]).is_empty());
+        // invoke-direct {v0}, method@1
+        assert!(written(&[0x1070, 0x0001, 0x0000]).is_empty());
+    }
+
+    #[test]
+    fn test_literal() {
+        // const/4 v0, #-1
+        let literal = |insns: &[u16]| Instructions::new(insns).next().unwrap().literal();
+        assert_eq!(literal(&[0xf012]), Some(EncodedValue::Int(-1)));
+        // const/16 v0, #-2
+        assert_eq!(literal(&[0x0013, 0xfffe]), Some(EncodedValue::Int(-2)));
+        // const v0, #0x12345678
+        assert_eq!(
+            literal(&[0x0014, 0x5678, 0x1234]),
+            Some(EncodedValue::Int(0x1234_5678))
+        );
+        // const/high16 v0, #0x3f800000
+        assert_eq!(
+            literal(&[0x0015, 0x3f80]),
+            Some(EncodedValue::Int(0x3f80_0000))
+        );
+        // const-wide v0, #0x0102030405060708
+        assert_eq!(
+            literal(&[0x0018, 0x0708, 0x0506, 0x0304, 0x0102]),
+            Some(EncodedValue::Long(0x0102_0304_0506_0708))
+        );
+        // const-wide/high16 v0, #0x4000000000000000
+        assert_eq!(
+            literal(&[0x0019, 0x4000]),
+            Some(EncodedValue::Long(0x4000_0000_0000_0000))
+        );
+        // truncated const v0
+        assert_eq!(literal(&[0x0014, 0x5678]), None);
+        // return-void
+        assert_eq!(literal(&[0x000e]), None);
+    }
+
+    #[test]
+    fn test_literals() {
+        let literals = |insns: &[u16]| {
+            Instructions::new(insns)
+                .next()
+                .unwrap()
+                .literals()
+                .collect::<Vec<_>>()
+        };
+        // const/high16 v0, #1.0f
+        assert_eq!(
+            literals(&[0x0015, 0x3f80]),
+            vec![EncodedValue::Int(0x3f80_0000), EncodedValue::Float(1.0)]
+        );
+        // const/high16 v0, #0x7f020000, a resource id
+        assert_eq!(
+            literals(&[0x0015, 0x7f02])[0],
+            EncodedValue::Int(0x7f02_0000)
+        );
+        // const-wide/high16 v0, #2.0
+        assert_eq!(
+            literals(&[0x0019, 0x4000]),
+            vec![
+                EncodedValue::Long(0x4000_0000_0000_0000),
+                EncodedValue::Double(2.0)
+            ]
+        );
+        // const v0, #0.1f
+        assert_eq!(
+            literals(&[0x0014, 0xcccd, 0x3dcc]),
+            vec![EncodedValue::Int(0x3dcc_cccd), EncodedValue::Float(0.1)]
+        );
+        // const-wide v0, #0.1
+        assert_eq!(
+            literals(&[0x0018, 0x999a, 0x9999, 0x9999, 0x3fb9]),
+            vec![
+                EncodedValue::Long(0x3fb9_9999_9999_999a),
+                EncodedValue::Double(0.1)
+            ]
+        );
+        // const/16 v0, #-2
+        assert_eq!(literals(&[0x0013, 0xfffe]), vec![EncodedValue::Int(-2)]);
+        // return-void
+        assert!(literals(&[0x000e]).is_empty());
+    }
+}
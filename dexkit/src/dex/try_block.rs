@@ -0,0 +1,206 @@
+use crate::dex::{CodeItem, DexFile};
+use crate::errors::Error;
+use std::collections::HashMap;
+
+/// A try block of a method body with its handlers, addresses are in 16-bit code units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryBlock {
+    pub start_address: u32,
+    /// first address after the block
+    pub end_address: u32,
+    /// (caught exception type descriptor, handler address), in handler order
+    pub catches: Vec<(String, u32)>,
+    /// address of the catch-all handler, `finally` blocks compile to one
+    pub catch_all_address: Option<u32>,
+}
+
+impl CodeItem {
+    /// internal use, the try blocks of the method body with the catch types resolved
+    pub(crate) fn try_blocks(
+        &self,
+        type_descriptor: impl Fn(u32) -> Result<String, Error>,
+    ) -> Result<Vec<TryBlock>, Error> {
+        self.tries
+            .iter()
+            .map(|try_item| {
+                let handler = self
+                    .handlers
+                    .iter()
+                    .find(|handler| handler.offset == try_item.handler_off as u32)
+                    .ok_or_else(|| {
+                        Error::DexParseError(format!(
+                            "catch handler at 0x{:X} not found",
+                            try_item.handler_off
+                        ))
+                    })?;
+                let catches = handler
+                    .catches
+                    .iter()
+                    .map(|&(type_idx, addr)| Ok((type_descriptor(type_idx)?, addr)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(TryBlock {
+                    start_address: try_item.start_addr,
+                    end_address: try_item.start_addr + try_item.insn_count as u32,
+                    catches,
+                    catch_all_address: handler.catch_all_addr,
+                })
+            })
+            .collect()
+    }
+
+    /// internal use, type_ids indexes of the exceptions created by new-instance and thrown
+    /// from the same register, in order of the throw instructions, without duplicates.
+    /// A register overwritten by any other instruction no longer holds the new instance.
+    pub(crate) fn thrown_new_types(&self) -> Vec<u32> {
+        // register -> type of the last new-instance into it
+        let mut created: HashMap<u16, u32> = HashMap::new();
+        let mut thrown = Vec::new();
+        for instruction in self.instructions() {
+            let register = instruction.units()[0] >> 8;
+            match instruction.opcode() {
+                // new-instance vAA, type@BBBB
+                0x22 => {
+                    created.remove(&register);
+                    if let Some(type_idx) = instruction.type_index() {
+                        created.insert(register, type_idx);
+                    }
+                }
+                // throw vAA
+                0x27 => {
+                    if let Some(&type_idx) = created.get(&register)
+                        && !thrown.contains(&type_idx)
+                    {
+                        thrown.push(type_idx);
+                    }
+                }
+                _ => {
+                    for register in instruction.written_registers() {
+                        created.remove(&register);
+                    }
+                }
+            }
+        }
+        thrown
+    }
+}
+
+impl DexFile {
+    /// Decode the try blocks of a method body.
+    pub fn try_blocks(&self, code: &CodeItem) -> Result<Vec<TryBlock>, Error> {
+        code.try_blocks(|idx| self.type_descriptor(idx))
+    }
+
+    /// Descriptors of the exceptions a method body creates with new-instance and throws,
+    /// e.g. "Ljava/lang/SecurityException;" for `throw new SecurityException(...)`.
+    pub fn thrown_new_types(&self, code: &CodeItem) -> Result<Vec<String>, Error> {
+        code.thrown_new_types()
+            .into_iter()
+            .map(|idx| self.type_descriptor(idx))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{CatchHandler, TryItem};
+
+    fn code_item(insns: Vec<u16>, tries: Vec<TryItem>, handlers: Vec<CatchHandler>) -> CodeItem {
+        CodeItem {
+            offset: 0,
+            registers_size: 2,
+            ins_size: 0,
+            outs_size: 1,
+            tries_size: tries.len() as u16,
+            debug_info_off: 0,
+            insns,
+            tries,
+            handlers,
+        }
+    }
+
+    #[test]
+    fn test_try_blocks() {
+        let tries = vec![TryItem {
+            start_addr: 2,
+            insn_count: 3,
+            handler_off: 1,
+        }];
+        let handlers = vec![CatchHandler {
+            offset: 1,
+            catches: vec![(7, 6), (9, 8)],
+            catch_all_addr: Some(10),
+        }];
+        let code = code_item(vec![0x000e; 11], tries, handlers);
+        let blocks = code.try_blocks(|idx| Ok(format!("Lt{};", idx))).unwrap();
+        assert_eq!(
+            blocks,
+            vec![TryBlock {
+                start_address: 2,
+                end_address: 5,
+                catches: vec![("Lt7;".to_string(), 6), ("Lt9;".to_string(), 8)],
+                catch_all_address: Some(10),
+            }]
+        );
+
+        let missing = vec![TryItem {
+            start_addr: 0,
+            insn_count: 1,
+            handler_off: 4,
+        }];
+        let code = code_item(vec![0x000e], missing, Vec::new());
+        assert!(code.try_blocks(|idx| Ok(format!("Lt{};", idx))).is_err());
+    }
+
+    #[test]
+    fn test_thrown_new_types() {
+        let insns = vec![
+            // new-instance v0, type@5
+            0x0022, 0x0005, //
+            // invoke-direct {v0}, method@1
+            0x1070, 0x0001, 0x0000, //
+            // new-instance v1, type@6
+            0x0122, 0x0006, //
+            // throw v0
+            0x0027, //
+            // throw v1
+            0x0127, //
+            // throw v0 again
+            0x0027,
+        ];
+        let code = code_item(insns, Vec::new(), Vec::new());
+        assert_eq!(code.thrown_new_types(), vec![5, 6]);
+
+        // throw of a parameter or a caught exception
+        let code = code_item(vec![0x0127], Vec::new(), Vec::new());
+        assert!(code.thrown_new_types().is_empty());
+    }
+
+    #[test]
+    fn test_thrown_new_types_overwritten() {
+        let insns = vec![
+            // new-instance v0, type@5
+            0x0022, 0x0005, //
+            // invoke-static {}, method@2
+            0x0071, 0x0002, 0x0000, //
+            // move-result-object v0
+            0x000c, //
+            // throw v0
+            0x0027, //
+            // new-instance v1, type@6
+            0x0122, 0x0006, //
+            // iget-object v1, v0, field@3
+            0x0154, 0x0003, //
+            // throw v1
+            0x0127, //
+            // new-instance v0, type@7
+            0x0022, 0x0007, //
+            // move-wide v0, v2 overwrites v0 and v1
+            0x2004, //
+            // throw v0
+            0x0027,
+        ];
+        let code = code_item(insns, Vec::new(), Vec::new());
+        assert!(code.thrown_new_types().is_empty());
+    }
+}
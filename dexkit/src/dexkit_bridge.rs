@@ -1,22 +1,41 @@
 use crate::{
+    apk::{AndroidManifest, ApkArchive, ApkInfo, ResourceTable},
+    dex::{DexFile, DexFileInfo, DexLibrary, DexWriter, ExportDependencies, export_selection},
     errors::Error,
     query::{
         BatchFindClassUsingStrings, BatchFindMethodUsingStrings, FindClass, FindField, FindMethod,
+        Logical,
+        base::{IPostMatch, PostMatchCache},
+        matchers::ClassMatcher,
     },
     result::{
-        AnnotationData, ClassData, ClassDataList, FieldData, FieldDataList, MethodData,
-        MethodDataList,
+        AnnotationData, ClassChunks, ClassData, ClassDataList, ClassDataStream, FieldData,
+        FieldDataList, FieldDataStream, MethodData, MethodDataList, MethodDataStream,
+        ResolvedFrame, StringXrefIndex, TypeRef, UsingNumber, base::BaseData,
     },
+    uitls::{DexSignature, StackFrame},
     wrap::{DexClass, DexMethod},
 };
 use std::{
-    collections::HashMap,
+    any::Any,
+    cell::OnceCell,
+    collections::{BTreeSet, HashMap, HashSet},
     ffi::{CString, c_char, c_void},
+    io::Write,
+    panic::AssertUnwindSafe,
+    path::Path,
 };
 
 #[derive(Debug)]
 pub struct DexkitBridge {
     dexkit_handle: dexkit_sys::DexkitHandle,
+    apk_path: String,
+    // dex files parsed on the Rust side, see `raw_dex_files`
+    dex_files: OnceCell<Vec<DexFile>>,
+    resources: OnceCell<ResourceTable>,
+    manifest: OnceCell<AndroidManifest>,
+    // library dex files, see `add_library_path`
+    library: DexLibrary,
 }
 
 impl DexkitBridge {
@@ -24,8 +43,9 @@ impl DexkitBridge {
     /// Panics if the APK path cannot be added.
     pub fn create_apk_path<S: Into<String>>(apk_path: S) -> Result<Self, Error> {
         let dexkit_handle = unsafe { dexkit_sys::dexkit_new() };
+        let apk_path = apk_path.into();
         let c_apk_path =
-            CString::new(apk_path.into()).map_err(|e| Error::BridgeCreateError(e.to_string()))?;
+            CString::new(apk_path.as_str()).map_err(|e| Error::BridgeCreateError(e.to_string()))?;
         let added = unsafe {
             dexkit_sys::dexkit_add_zip_path(dexkit_handle, c_apk_path.as_ptr() as *mut i8, 0)
         };
@@ -33,7 +53,14 @@ impl DexkitBridge {
             return Err(Error::BridgeCreateError("Failed to add APK path".into()));
         }
 
-        Ok(DexkitBridge { dexkit_handle })
+        Ok(DexkitBridge {
+            dexkit_handle,
+            apk_path,
+            dex_files: OnceCell::new(),
+            resources: OnceCell::new(),
+            manifest: OnceCell::new(),
+            library: DexLibrary::default(),
+        })
     }
 
     /// Free the DexkitBridge instance and its resources.
@@ -62,6 +89,30 @@ impl DexkitBridge {
         unsafe { dexkit_sys::dexkit_get_dex_num(self.dexkit_handle) }
     }
 
+    /// Attach a library .dex, .jar or .apk, e.g. framework.jar or a dexed android.jar.
+    /// Library classes resolve supertypes and descriptors missing from the apk, see
+    /// `ClassData::is_library`. They are not loaded into DexKit, `find_*` queries leave them out
+    /// unless asked with `FindClass::set_include_library`.
+    /// Library dex files are numbered after the dex files of the apk.
+    /// Returns the number of dex files added.
+    pub fn add_library_path<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, Error> {
+        let first_dex_id = self.get_dex_num().max(0) as u32 + self.library.dex_files().len() as u32;
+        self.library.add(path, first_dex_id)
+    }
+
+    /// Get the number of classes defined in the attached library dex files.
+    pub fn library_class_count(&self) -> usize {
+        self.library.class_count()
+    }
+
+    /// Get the header and map summary of every loaded dex, in dex index order.
+    pub fn dex_files(&self) -> Result<Vec<DexFileInfo>, Error> {
+        self.raw_dex_files()?
+            .iter()
+            .map(DexFileInfo::from_dex)
+            .collect()
+    }
+
     /// Export all parsed DEX files to the specified output path.
     /// Returns an error if the export fails.
     pub fn export_dex_file(&self, output_path: &str) -> Result<(), Error> {
@@ -81,11 +132,75 @@ impl DexkitBridge {
         Ok(())
     }
 
+    /// Write `classes` into a new dex file, e.g. a `File` or a `Vec<u8>` buffer.
+    /// `dependencies` selects the classes of the loaded dex files written alongside them,
+    /// classes that are not written stay external references.
+    /// Returns an error if a class is not defined in the loaded dex files.
+    pub fn export_classes<W: Write>(
+        &self,
+        classes: &ClassDataList<'_>,
+        dependencies: ExportDependencies,
+        sink: &mut W,
+    ) -> Result<(), Error> {
+        let roots: Vec<(u32, u32)> = classes
+            .iter()
+            .map(|class| (class.dex_id(), class.id()))
+            .collect();
+        let selection = export_selection(self.raw_dex_files()?, &roots, dependencies)?;
+        let data = DexWriter::write(&selection)?;
+        sink.write_all(&data)
+            .map_err(|e| Error::DexWriteError(e.to_string()))
+    }
+
+    /// Enumerate the string_ids of every loaded dex.
+    /// Returns the distinct strings in ascending order.
+    pub fn strings(&self) -> Result<Vec<String>, Error> {
+        let mut strings = BTreeSet::new();
+        for dex in self.raw_dex_files()? {
+            strings.extend(dex.strings()?);
+        }
+        Ok(strings.into_iter().collect())
+    }
+
+    /// Build the cross-reference index of the strings used by the loaded dex files.
+    /// Scans every method body, keep the index instead of rebuilding it per lookup.
+    pub fn string_xref_index(&self) -> Result<StringXrefIndex<'_>, Error> {
+        StringXrefIndex::build(self)
+    }
+
+    /// Get the resource table of the apk, parsed from resources.arsc on first use.
+    pub fn resources(&self) -> Result<&ResourceTable, Error> {
+        if let Some(resources) = self.resources.get() {
+            return Ok(resources);
+        }
+        let data = ApkArchive::open(&self.apk_path)?.read("resources.arsc")?;
+        let resources = ResourceTable::parse(&data)?;
+        Ok(self.resources.get_or_init(|| resources))
+    }
+
+    /// Get the AndroidManifest.xml of the apk, parsed on first use.
+    pub fn manifest(&self) -> Result<&AndroidManifest, Error> {
+        if let Some(manifest) = self.manifest.get() {
+            return Ok(manifest);
+        }
+        let data = ApkArchive::open(&self.apk_path)?.read("AndroidManifest.xml")?;
+        let manifest = AndroidManifest::parse(&data)?;
+        Ok(self.manifest.get_or_init(|| manifest))
+    }
+
+    /// Get the package, version, dex entries and signing certificates of the apk.
+    pub fn apk_info(&self) -> Result<ApkInfo, Error> {
+        ApkInfo::load(&self.apk_path)
+    }
+
     /// Batch find classes based on the provided BatchFindClassUsingStrings query.
     pub fn batch_find_class_using_strings(
         &self,
         batch_find: BatchFindClassUsingStrings,
     ) -> HashMap<String, ClassDataList<'_>> {
+        if batch_find.check().is_err() || !batch_find.has_native_scope() {
+            return HashMap::new();
+        }
         unsafe {
             let mut buffer: Vec<u8> = batch_find.into();
             let mut out_buf: *mut c_void = std::ptr::null_mut();
@@ -114,6 +229,9 @@ impl DexkitBridge {
         &self,
         batch_find: BatchFindMethodUsingStrings,
     ) -> HashMap<String, MethodDataList<'_>> {
+        if batch_find.check().is_err() || !batch_find.has_native_scope() {
+            return HashMap::new();
+        }
         unsafe {
             let mut buffer: Vec<u8> = batch_find.into();
             let mut out_buf: *mut c_void = std::ptr::null_mut();
@@ -139,9 +257,166 @@ impl DexkitBridge {
 
     /// Find classes based on the provided FindClass query.
     /// Returns a ClassDataList containing the results.
-    pub fn find_class(&self, find_class: FindClass) -> ClassDataList<'_> {
+    /// Empty if the query holds a matcher `FindClass::try_set_matcher` rejects.
+    /// A predicate set with `FindClass::set_predicate` is called back by the native search.
+    /// Library classes are only returned on request, see `FindClass::set_include_library`.
+    pub fn find_class(&self, mut find_class: FindClass) -> ClassDataList<'_> {
+        if find_class.check().is_err() {
+            return ClassDataList::new();
+        }
+        if find_class.take_include_library() {
+            let find_first = find_class.is_find_first();
+            let library: Vec<_> = self
+                .get_library_classes()
+                .into_iter()
+                .filter(|class| find_class.library_match(class))
+                .collect();
+            let mut result = self.find_class(find_class);
+            for class in library {
+                if find_first && !result.is_empty() {
+                    break;
+                }
+                result.add(class);
+            }
+            return result;
+        }
+        if !find_class.has_native_scope() {
+            return ClassDataList::new();
+        }
+        let find_first = find_class.is_find_first();
+        let predicate = find_class.take_predicate();
+        let result: Vec<_> = match find_class.take_logical_matcher() {
+            Some(logical) => {
+                let mut scope = None;
+                logical.evaluate(
+                    &mut |matcher| {
+                        self.find_class(find_class.fork(Some(matcher)))
+                            .iter()
+                            .map(|data| (data.get_mine_encode_id(), data.clone()))
+                            .collect()
+                    },
+                    &mut |_| {
+                        let scope = scope.get_or_insert_with(|| {
+                            self.find_class(find_class.fork(None))
+                                .iter()
+                                .map(|data| (data.get_mine_encode_id(), data.clone()))
+                                .collect::<Vec<_>>()
+                        });
+                        scope.clone()
+                    },
+                )
+            }
+            None if predicate.is_none() && !find_class.has_post_match() => {
+                return self.find_class_buffer(find_class.into());
+            }
+            None => {
+                let cache = PostMatchCache::default();
+                let limit = if find_first { 1 } else { usize::MAX };
+                return self.find_class_filtered(find_class.to_buffer(), limit, &|data| {
+                    find_class.post_match(data, &cache)
+                        && predicate.as_ref().is_none_or(|predicate| predicate(data))
+                });
+            }
+        };
+        result
+            .into_iter()
+            .filter(|data| predicate.as_ref().is_none_or(|predicate| predicate(data)))
+            .take(if find_first { 1 } else { usize::MAX })
+            .collect()
+    }
+
+    /// Lazily find classes based on the provided FindClass query, see `DataStream`.
+    /// The search is split into chunks of classes, one native query per chunk.
+    /// Queries with a logical matcher are executed at once, the stream holds all their results.
+    pub fn find_class_stream<'a>(&'a self, mut find_class: FindClass<'a>) -> ClassDataStream<'a> {
+        if find_class.check().is_err() {
+            return DataStream::from_vec(Vec::new());
+        }
+        let find_first = find_class.is_find_first();
+        let stream = if find_class.has_logical_matcher() || find_class.includes_library() {
+            DataStream::from_vec(self.find_class(find_class).into())
+        } else {
+            let predicate = find_class.take_predicate();
+            let mut chunks = ClassChunks::new(self, find_class.take_search_classes());
+            let cache = PostMatchCache::default();
+            DataStream::new(Box::new(move |chunk_size| {
+                if let Some(classes) = chunks.next(chunk_size)? {
+                    find_class.set_chunk_classes(classes);
+                }
+                let result =
+                    self.find_class_filtered(find_class.to_buffer(), usize::MAX, &|data| {
+                        find_class.post_match(data, &cache)
+                            && predicate.as_ref().is_none_or(|predicate| predicate(data))
+                    });
+                Some(result.into())
+            }))
+        };
+        if find_first { stream.limit(1) } else { stream }
+    }
+
+    /// internal use, encode ids of all classes matching `matcher`, post match included
+    pub(crate) fn matching_class_ids(
+        &self,
+        matcher: &ClassMatcher,
+        cache: &PostMatchCache,
+    ) -> HashSet<i64> {
+        self.find_class_buffer(FindClass::matcher_buffer(matcher))
+            .iter()
+            .filter(|&class| matcher.post_match(class, cache))
+            .map(|class| class.get_mine_encode_id())
+            .collect()
+    }
+
+    /// internal use, execute a serialized FindClass query, `accept` is called back by the native
+    /// search for each candidate and at most `limit` accepted candidates are decoded
+    fn find_class_filtered(
+        &self,
+        mut buffer: Vec<u8>,
+        limit: usize,
+        accept: &dyn Fn(&ClassData) -> bool,
+    ) -> ClassDataList<'_> {
+        let mut filter = NativeFilter::new(|data, index| {
+            ClassDataList::item_at(self, data, index).is_some_and(|class| accept(&class))
+        });
+        let (call_back, ctx) = filter.as_callback();
+        let result = unsafe {
+            let mut out_buf: *mut c_void = std::ptr::null_mut();
+            let mut out_len: usize = 0;
+            let mut out_indexes: *mut u32 = std::ptr::null_mut();
+            let mut out_index_len: usize = 0;
+            dexkit_sys::dexkit_find_class_filtered(
+                self.dexkit_handle,
+                buffer.as_mut_ptr() as *mut c_void,
+                call_back,
+                ctx,
+                limit,
+                &mut out_buf,
+                &mut out_len,
+                &mut out_indexes,
+                &mut out_index_len,
+            );
+
+            let (data, indexes) = if !out_buf.is_null() && !out_indexes.is_null() {
+                (
+                    std::slice::from_raw_parts(out_buf as *const u8, out_len),
+                    std::slice::from_raw_parts(out_indexes, out_index_len),
+                )
+            } else {
+                (&[][..], &[][..])
+            };
+
+            let result = ClassDataList::from_data_at(self, data, indexes);
+            dexkit_sys::dexkit_find_class_free(&mut out_buf, out_len); // release the memory allocated by layer C
+            dexkit_sys::dexkit_filtered_indexes_free(&mut out_indexes, out_index_len);
+            result
+        };
+        filter.resume_panic();
+        result
+    }
+
+    /// internal use, execute a serialized FindClass query
+    fn find_class_buffer(&self, mut buffer: Vec<u8>) -> ClassDataList<'_> {
         unsafe {
-            let mut buffer: Vec<u8> = find_class.into();
             let mut out_buf: *mut c_void = std::ptr::null_mut();
             let mut out_len: usize = 0;
             dexkit_sys::dexkit_find_class(
@@ -165,9 +440,147 @@ impl DexkitBridge {
 
     /// Find methods based on the provided FindMethod query.
     /// Returns a MethodDataList containing the results.
-    pub fn find_method(&self, find_method: FindMethod) -> MethodDataList<'_> {
+    /// Empty if the query holds a matcher `FindMethod::try_set_matcher` rejects,
+    /// or loads a resource the apk does not define, see `MethodMatcher::add_using_resource`.
+    /// A predicate set with `FindMethod::set_predicate` is called back by the native search.
+    pub fn find_method(&self, mut find_method: FindMethod) -> MethodDataList<'_> {
+        if find_method.check().is_err()
+            || !find_method.has_native_scope()
+            || find_method.resolve_resources(self).is_err()
+        {
+            return MethodDataList::new();
+        }
+        let find_first = find_method.is_find_first();
+        let predicate = find_method.take_predicate();
+        let result: Vec<_> = match find_method.take_logical_matcher() {
+            Some(logical) => {
+                let mut scope = None;
+                logical.evaluate(
+                    &mut |matcher| {
+                        self.find_method(find_method.fork(Some(matcher)))
+                            .iter()
+                            .map(|data| (data.get_mine_encode_id(), data.clone()))
+                            .collect()
+                    },
+                    &mut |_| {
+                        let scope = scope.get_or_insert_with(|| {
+                            self.find_method(find_method.fork(None))
+                                .iter()
+                                .map(|data| (data.get_mine_encode_id(), data.clone()))
+                                .collect::<Vec<_>>()
+                        });
+                        scope.clone()
+                    },
+                )
+            }
+            None if predicate.is_none() && !find_method.has_post_match() => {
+                return self.find_method_buffer(find_method.into());
+            }
+            None => {
+                let cache = PostMatchCache::default();
+                let limit = if find_first { 1 } else { usize::MAX };
+                return self.find_method_filtered(find_method.to_buffer(), limit, &|data| {
+                    find_method.post_match(data, &cache)
+                        && predicate.as_ref().is_none_or(|predicate| predicate(data))
+                });
+            }
+        };
+        result
+            .into_iter()
+            .filter(|data| predicate.as_ref().is_none_or(|predicate| predicate(data)))
+            .take(if find_first { 1 } else { usize::MAX })
+            .collect()
+    }
+
+    /// Lazily find methods based on the provided FindMethod query, see `DataStream`.
+    /// The search is split into chunks of classes, one native query per chunk.
+    /// Queries with a logical matcher are executed at once, the stream holds all their results.
+    pub fn find_method_stream<'a>(
+        &'a self,
+        mut find_method: FindMethod<'a>,
+    ) -> MethodDataStream<'a> {
+        if find_method.check().is_err()
+            || !find_method.has_native_scope()
+            || find_method.resolve_resources(self).is_err()
+        {
+            return DataStream::from_vec(Vec::new());
+        }
+        let find_first = find_method.is_find_first();
+        let stream = if find_method.has_logical_matcher() {
+            DataStream::from_vec(self.find_method(find_method).into())
+        } else {
+            let predicate = find_method.take_predicate();
+            let mut chunks = if find_method.has_item_scope() {
+                ClassChunks::whole(self)
+            } else {
+                ClassChunks::new(self, find_method.take_search_classes())
+            };
+            let cache = PostMatchCache::default();
+            DataStream::new(Box::new(move |chunk_size| {
+                if let Some(classes) = chunks.next(chunk_size)? {
+                    find_method.set_chunk_classes(classes);
+                }
+                let result =
+                    self.find_method_filtered(find_method.to_buffer(), usize::MAX, &|data| {
+                        find_method.post_match(data, &cache)
+                            && predicate.as_ref().is_none_or(|predicate| predicate(data))
+                    });
+                Some(result.into())
+            }))
+        };
+        if find_first { stream.limit(1) } else { stream }
+    }
+
+    /// internal use, execute a serialized FindMethod query, `accept` is called back by the native
+    /// search for each candidate and at most `limit` accepted candidates are decoded
+    fn find_method_filtered(
+        &self,
+        mut buffer: Vec<u8>,
+        limit: usize,
+        accept: &dyn Fn(&MethodData) -> bool,
+    ) -> MethodDataList<'_> {
+        let mut filter = NativeFilter::new(|data, index| {
+            MethodDataList::item_at(self, data, index).is_some_and(|method| accept(&method))
+        });
+        let (call_back, ctx) = filter.as_callback();
+        let result = unsafe {
+            let mut out_buf: *mut c_void = std::ptr::null_mut();
+            let mut out_len: usize = 0;
+            let mut out_indexes: *mut u32 = std::ptr::null_mut();
+            let mut out_index_len: usize = 0;
+            dexkit_sys::dexkit_find_method_filtered(
+                self.dexkit_handle,
+                buffer.as_mut_ptr() as *mut c_void,
+                call_back,
+                ctx,
+                limit,
+                &mut out_buf,
+                &mut out_len,
+                &mut out_indexes,
+                &mut out_index_len,
+            );
+
+            let (data, indexes) = if !out_buf.is_null() && !out_indexes.is_null() {
+                (
+                    std::slice::from_raw_parts(out_buf as *const u8, out_len),
+                    std::slice::from_raw_parts(out_indexes, out_index_len),
+                )
+            } else {
+                (&[][..], &[][..])
+            };
+
+            let result = MethodDataList::from_data_at(self, data, indexes);
+            dexkit_sys::dexkit_find_method_free(&mut out_buf, out_len); // release the memory allocated by layer C
+            dexkit_sys::dexkit_filtered_indexes_free(&mut out_indexes, out_index_len);
+            result
+        };
+        filter.resume_panic();
+        result
+    }
+
+    /// internal use, execute a serialized FindMethod query
+    fn find_method_buffer(&self, mut buffer: Vec<u8>) -> MethodDataList<'_> {
         unsafe {
-            let mut buffer: Vec<u8> = find_method.into();
             let mut out_buf: *mut c_void = std::ptr::null_mut();
             let mut out_len: usize = 0;
             dexkit_sys::dexkit_find_method(
@@ -191,9 +604,86 @@ impl DexkitBridge {
 
     /// Find fields based on the provided FindField query.
     /// Returns a FieldDataList containing the results.
-    pub fn find_field(&self, find_field: FindField) -> FieldDataList<'_> {
+    /// Empty if the query holds a matcher `FindField::try_set_matcher` rejects.
+    pub fn find_field(&self, mut find_field: FindField) -> FieldDataList<'_> {
+        if find_field.check().is_err() || !find_field.has_native_scope() {
+            return FieldDataList::new();
+        }
+        if let Some(logical) = find_field.take_logical_matcher() {
+            let find_first = find_field.is_find_first();
+            let mut scope = None;
+            let mut result = logical.evaluate(
+                &mut |matcher| {
+                    self.find_field(find_field.fork(Some(matcher)))
+                        .iter()
+                        .map(|data| (data.get_mine_encode_id(), data.clone()))
+                        .collect()
+                },
+                &mut |_| {
+                    let scope = scope.get_or_insert_with(|| {
+                        self.find_field(find_field.fork(None))
+                            .iter()
+                            .map(|data| (data.get_mine_encode_id(), data.clone()))
+                            .collect::<Vec<_>>()
+                    });
+                    scope.clone()
+                },
+            );
+            if find_first {
+                result.truncate(1);
+            }
+            return result.into_iter().collect();
+        }
+        if !find_field.has_post_match() {
+            return self.find_field_buffer(find_field.into());
+        }
+
+        let find_first = find_field.is_find_first();
+        let cache = PostMatchCache::default();
+        let result = self.find_field_buffer(find_field.to_buffer());
+        result
+            .into_iter()
+            .filter(|data| find_field.post_match(data, &cache))
+            .take(if find_first { 1 } else { usize::MAX })
+            .collect()
+    }
+
+    /// Lazily find fields based on the provided FindField query, see `DataStream`.
+    /// The search is split into chunks of classes, one native query per chunk.
+    /// Queries with a logical matcher are executed at once, the stream holds all their results.
+    pub fn find_field_stream<'a>(&'a self, mut find_field: FindField<'a>) -> FieldDataStream<'a> {
+        if find_field.check().is_err() || !find_field.has_native_scope() {
+            return DataStream::from_vec(Vec::new());
+        }
+        let find_first = find_field.is_find_first();
+        let stream = if find_field.has_logical_matcher() {
+            DataStream::from_vec(self.find_field(find_field).into())
+        } else {
+            let mut chunks = if find_field.has_item_scope() {
+                ClassChunks::whole(self)
+            } else {
+                ClassChunks::new(self, find_field.take_search_classes())
+            };
+            let cache = PostMatchCache::default();
+            DataStream::new(Box::new(move |chunk_size| {
+                if let Some(classes) = chunks.next(chunk_size)? {
+                    find_field.set_chunk_classes(classes);
+                }
+                let result = self.find_field_buffer(find_field.to_buffer());
+                Some(
+                    result
+                        .into_iter()
+                        .filter(|data| find_field.post_match(data, &cache))
+                        .collect(),
+                )
+            }))
+        };
+        if find_first { stream.limit(1) } else { stream }
+    }
+
+    /// internal use, execute a serialized FindField query
+    fn find_field_buffer(&self, mut buffer: Vec<u8>) -> FieldDataList<'_> {
         unsafe {
-            let mut buffer: Vec<u8> = find_field.into();
             let mut out_buf: *mut c_void = std::ptr::null_mut();
             let mut out_len: usize = 0;
             dexkit_sys::dexkit_find_field(
@@ -215,6 +705,69 @@ impl DexkitBridge {
         }
     }
 
+    /// Find classes based on a logical composition of FindClass queries.
+    /// Every query keeps its own scope, `not` is evaluated against all classes.
+    pub fn find_class_logical(&self, logical: Logical<FindClass>) -> ClassDataList<'_> {
+        let result = logical.evaluate(
+            &mut |query| {
+                self.find_class(query)
+                    .iter()
+                    .map(|data| (data.get_mine_encode_id(), data.clone()))
+                    .collect()
+            },
+            &mut |query| {
+                let scope = query.map_or_else(FindClass::create, |query| query.fork(None));
+                self.find_class(scope)
+                    .iter()
+                    .map(|data| (data.get_mine_encode_id(), data.clone()))
+                    .collect()
+            },
+        );
+        result.into_iter().collect()
+    }
+
+    /// Find methods based on a logical composition of FindMethod queries.
+    /// Every query keeps its own scope, `not` is evaluated against all methods.
+    pub fn find_method_logical(&self, logical: Logical<FindMethod>) -> MethodDataList<'_> {
+        let result = logical.evaluate(
+            &mut |query| {
+                self.find_method(query)
+                    .iter()
+                    .map(|data| (data.get_mine_encode_id(), data.clone()))
+                    .collect()
+            },
+            &mut |query| {
+                let scope = query.map_or_else(FindMethod::create, |query| query.fork(None));
+                self.find_method(scope)
+                    .iter()
+                    .map(|data| (data.get_mine_encode_id(), data.clone()))
+                    .collect()
+            },
+        );
+        result.into_iter().collect()
+    }
+
+    /// Find fields based on a logical composition of FindField queries.
+    /// Every query keeps its own scope, `not` is evaluated against all fields.
+    pub fn find_field_logical(&self, logical: Logical<FindField>) -> FieldDataList<'_> {
+        let result = logical.evaluate(
+            &mut |query| {
+                self.find_field(query)
+                    .iter()
+                    .map(|data| (data.get_mine_encode_id(), data.clone()))
+                    .collect()
+            },
+            &mut |query| {
+                let scope = query.map_or_else(FindField::create, |query| query.fork(None));
+                self.find_field(scope)
+                    .iter()
+                    .map(|data| (data.get_mine_encode_id(), data.clone()))
+                    .collect()
+            },
+        );
+        result.into_iter().collect()
+    }
+
     /// Get class data by its descriptor or simple name.
     /// The identifier can be a full descriptor (e.g., "Lcom/example/MyClass;")
     pub fn get_class_data<T>(&self, identifier: T) -> Option<ClassData<'_>>
@@ -251,10 +804,40 @@ impl DexkitBridge {
 
             let result = ClassData::with_meta_raw(self, data);
             dexkit_sys::dexkit_get_class_data_free(&mut out_buf, out_len);
-            result
+            result.or_else(|| self.get_library_class(descriptor))
         }
     }
 
+    /// Get a type by its descriptor or name, e.g. "Landroid/os/Bundle;", "android.os.Bundle"
+    /// or "int[]". Unlike `get_class_data`, types not defined in the apk or an attached
+    /// library are returned as `TypeRef::External`. None if the identifier is not a type.
+    pub fn get_type_ref<T>(&self, identifier: T) -> Option<TypeRef<'_>>
+    where
+        T: AsRef<str>,
+    {
+        let identifier = identifier.as_ref();
+        let descriptor = match DexSignature::get_type_name(identifier) {
+            Some(_) => identifier.to_string(),
+            None => DexSignature::get_type_signature(identifier)?,
+        };
+        Some(TypeRef::from_descriptor(self, descriptor))
+    }
+
+    /// Map each frame of a Java / Android stack trace to its class and method candidates,
+    /// lines that are not frames are skipped, see `StackFrame::parse`.
+    /// Overloads are told apart with the line table when the frame has a line number.
+    pub fn resolve_stack_trace(&self, trace: &str) -> Vec<ResolvedFrame<'_>> {
+        StackFrame::parse_all(trace)
+            .into_iter()
+            .map(|frame| ResolvedFrame::resolve(self, frame))
+            .collect()
+    }
+
+    /// Map a single stack trace frame, see `resolve_stack_trace`.
+    pub fn resolve_stack_frame(&self, frame: &StackFrame) -> ResolvedFrame<'_> {
+        ResolvedFrame::resolve(self, frame.clone())
+    }
+
     /// Get method data by its descriptor.
     pub fn get_method_data<T>(&self, descriptor: T) -> Option<MethodData<'_>>
     where
@@ -319,8 +902,81 @@ impl DexkitBridge {
     }
 
     /// ---> Internal use only --->
-    /// Get classes by their encoded IDs.
+    /// Get the dex files of the apk parsed on the Rust side, read on first use.
+    pub(crate) fn raw_dex_files(&self) -> Result<&[DexFile], Error> {
+        if let Some(dex_files) = self.dex_files.get() {
+            return Ok(dex_files);
+        }
+        let dex_files = DexFile::from_apk(&self.apk_path)?;
+        if dex_files.len() != self.get_dex_num().max(0) as usize {
+            return Err(Error::BridgeOperationError(format!(
+                "dex count mismatch, native: {}, parsed: {}",
+                self.get_dex_num(),
+                dex_files.len()
+            )));
+        }
+        Ok(self.dex_files.get_or_init(|| dex_files))
+    }
+
+    /// Get the parsed dex file with the given dex ID.
+    pub(crate) fn raw_dex_file(&self, dex_id: u32) -> Result<&DexFile, Error> {
+        self.raw_dex_files()?
+            .get(dex_id as usize)
+            .ok_or_else(|| Error::BridgeOperationError(format!("dex {} not found", dex_id)))
+    }
+
+    /// Is `dex_id` one of the attached library dex files.
+    pub(crate) fn is_library_dex(&self, dex_id: u32) -> bool {
+        self.library.dex_file(dex_id).is_some()
+    }
+
+    /// Get the parsed dex file with the given dex ID, from the apk or the library.
+    pub(crate) fn any_dex_file(&self, dex_id: u32) -> Result<&DexFile, Error> {
+        match self.library.dex_file(dex_id) {
+            Some(dex) => Ok(dex),
+            None => self.raw_dex_file(dex_id),
+        }
+    }
+
+    /// Get the library class with the given descriptor.
+    pub(crate) fn get_library_class(&self, descriptor: &str) -> Option<ClassData<'_>> {
+        let (dex, class_def) = self.library.find_class(descriptor)?;
+        ClassData::from_dex_file(self, dex, &class_def).ok()
+    }
+
+    /// Get every library class, see `FindClass::set_include_library`.
+    pub(crate) fn get_library_classes(&self) -> Vec<ClassData<'_>> {
+        self.library
+            .classes()
+            .into_iter()
+            .filter_map(|(dex, class_def)| ClassData::from_dex_file(self, dex, &class_def).ok())
+            .collect()
+    }
+
+    /// Resolve a type index of a loaded dex against the library classes.
+    pub(crate) fn get_library_type(&self, dex_id: u32, type_idx: u32) -> Option<ClassData<'_>> {
+        let descriptor = self
+            .any_dex_file(dex_id)
+            .and_then(|dex| dex.type_descriptor(type_idx))
+            .ok()?;
+        self.get_library_class(&descriptor)
+    }
+
+    /// internal use, drop the encoded ids of library dex files, DexKit never loaded them
+    fn native_ids(&self, encode_ids: &[i64]) -> Vec<i64> {
+        encode_ids
+            .iter()
+            .copied()
+            .filter(|&id| !self.is_library_dex((id >> 32) as u32))
+            .collect()
+    }
+
+    /// Get classes by their encoded IDs, library classes are left out.
     pub(crate) fn get_type_by_ids(&self, encode_id_array: &[i64]) -> ClassDataList<'_> {
+        let encode_id_array = self.native_ids(encode_id_array);
+        if encode_id_array.is_empty() {
+            return ClassDataList::new();
+        }
         unsafe {
             let mut out_buf: *mut c_void = std::ptr::null_mut();
             let mut out_len: usize = 0;
@@ -346,6 +1002,10 @@ impl DexkitBridge {
 
     /// Get methods by their encoded IDs.
     pub(crate) fn get_method_by_ids(&self, encode_id_array: &[i64]) -> MethodDataList<'_> {
+        let encode_id_array = self.native_ids(encode_id_array);
+        if encode_id_array.is_empty() {
+            return MethodDataList::new();
+        }
         unsafe {
             let mut out_buf: *mut c_void = std::ptr::null_mut();
             let mut out_len: usize = 0;
@@ -371,6 +1031,10 @@ impl DexkitBridge {
 
     /// Get fields by their encoded IDs.
     pub(crate) fn get_field_by_ids(&self, encode_id_array: &[i64]) -> FieldDataList<'_> {
+        let encode_id_array = self.native_ids(encode_id_array);
+        if encode_id_array.is_empty() {
+            return FieldDataList::new();
+        }
         unsafe {
             let mut out_buf: *mut c_void = std::ptr::null_mut();
             let mut out_len: usize = 0;
@@ -684,6 +1348,29 @@ impl DexkitBridge {
         }
     }
 
+    /// Get the number literals loaded in this method by method ID.
+    /// The core has no getter for them, the method body is decoded on the Rust side.
+    pub(crate) fn get_method_using_numbers(
+        &self,
+        method_id: i64,
+    ) -> Result<Vec<UsingNumber>, Error> {
+        let dex = self.raw_dex_file((method_id >> 32) as u32)?;
+        let Some(code) = dex.method_code(method_id as u32)? else {
+            return Ok(Vec::new());
+        };
+        let mut numbers: Vec<UsingNumber> = Vec::new();
+        for number in code
+            .instructions()
+            .flat_map(|instruction| instruction.literals())
+            .filter_map(UsingNumber::from_encoded_value)
+        {
+            if !numbers.contains(&number) {
+                numbers.push(number);
+            }
+        }
+        Ok(numbers)
+    }
+
     /// Get all fields used in this method by method ID
     pub(crate) fn get_method_using_fields(
         &self,
@@ -712,8 +1399,81 @@ impl DexkitBridge {
     }
 }
 
+/// internal use, a Rust filter called back by the native filtered searches.
+/// A panic is caught in the call back, the remaining candidates are rejected
+/// and the panic resumes once the native search returned, see `resume_panic`
+struct NativeFilter<F: FnMut(&[u8], u32) -> bool> {
+    accept: F,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<F: FnMut(&[u8], u32) -> bool> NativeFilter<F> {
+    fn new(accept: F) -> Self {
+        Self {
+            accept,
+            panic: None,
+        }
+    }
+
+    /// the call back and its `ctx`, valid while `self` is not moved
+    fn as_callback(&mut self) -> (dexkit_sys::DexkitFilterFn, *mut c_void) {
+        (Self::call_back, self as *mut Self as *mut c_void)
+    }
+
+    unsafe extern "C" fn call_back(
+        ctx: *mut c_void,
+        buf: *const c_void,
+        len: usize,
+        index: u32,
+    ) -> bool {
+        let filter = unsafe { &mut *(ctx as *mut Self) };
+        if filter.panic.is_some() {
+            return false;
+        }
+        let data = unsafe { std::slice::from_raw_parts(buf as *const u8, len) };
+        let accept = &mut filter.accept;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| accept(data, index))) {
+            Ok(accepted) => accepted,
+            Err(panic) => {
+                filter.panic = Some(panic);
+                false
+            }
+        }
+    }
+
+    fn resume_panic(self) {
+        if let Some(panic) = self.panic {
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
+
 impl Drop for DexkitBridge {
     fn drop(&mut self) {
         self.close();
     }
 }
+
+#[cfg(test)]
+impl DexkitBridge {
+    /// internal use, a bridge without a native DexKit instance for tests of the Rust side,
+    /// `dex_files` are the apk dex files
+    pub(crate) fn for_test(dex_files: Vec<DexFile>) -> Self {
+        DexkitBridge {
+            dexkit_handle: std::ptr::null_mut(),
+            apk_path: String::new(),
+            dex_files: OnceCell::from(dex_files),
+            resources: OnceCell::new(),
+            manifest: OnceCell::new(),
+            library: DexLibrary::default(),
+        }
+    }
+
+    /// internal use, attach parsed library dex files, see `add_library_path`
+    pub(crate) fn with_test_library(mut self, dex_files: Vec<DexFile>) -> Self {
+        for dex in dex_files {
+            self.library.insert(dex).unwrap();
+        }
+        self
+    }
+}
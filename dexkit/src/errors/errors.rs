@@ -1,3 +1,4 @@
+use crate::query::enums::AnnotationEncodeValueType;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -10,4 +11,64 @@ pub enum Error {
     MUtf8DecodeError(String),
     #[error("Opcode Error: {0}")]
     OpcodeError(String),
+    #[error("Regex Error: {0}")]
+    RegexError(String),
+    #[error("Apk Read Error: {0}")]
+    ApkReadError(String),
+    #[error("Dex Parse Error: {0}")]
+    DexParseError(String),
+    #[error("Resource Error: {0}")]
+    ResourceError(String),
+    #[error("Dex Write Error: {0}")]
+    DexWriteError(String),
+    #[error("Matcher Error: {0}")]
+    MatcherError(String),
+}
+
+/// The shape of an annotation does not match the Rust type it is read into,
+/// see `result::FromDexAnnotation`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FromAnnotationError {
+    #[error("expected annotation {expected}, found {found}")]
+    WrongAnnotation { expected: String, found: String },
+    #[error("missing element `{0}`")]
+    MissingElement(String),
+    #[error("expected {expected} value, found {found:?}")]
+    WrongValueType {
+        expected: &'static str,
+        found: AnnotationEncodeValueType,
+    },
+    #[error("unknown enum constant `{0}`")]
+    UnknownEnumConstant(String),
+    #[error("`{element}`: {source}")]
+    InElement {
+        element: String,
+        source: Box<FromAnnotationError>,
+    },
+}
+
+impl FromAnnotationError {
+    /// internal use, attach the element name or array index the error happened in
+    pub(crate) fn in_element<S: Into<String>>(self, element: S) -> Self {
+        FromAnnotationError::InElement {
+            element: element.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+/// A target of `#[derive(DexTargets)]` did not resolve to the expected number of matches,
+/// see `query::DexTargets`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ResolveError {
+    #[error("target `{0}` not found")]
+    NotFound(String),
+    #[error("target `{target}` is ambiguous, {} matches: {}", .candidates.len(), .candidates.join(", "))]
+    NotUnique {
+        target: String,
+        /// descriptors of the matches
+        candidates: Vec<String>,
+    },
+    #[error("target `{target}` has an invalid query: {message}")]
+    InvalidQuery { target: String, message: String },
 }
@@ -18,6 +18,10 @@ pub mod query {
         pub use i_annotation_encode_value::*;
         mod i_number_encode_value;
         pub use i_number_encode_value::*;
+        mod i_post_match;
+        pub use i_post_match::*;
+        mod i_static_value;
+        pub use i_static_value::*;
         mod i_query;
         pub use i_query::*;
     }
@@ -106,6 +110,10 @@ pub mod query {
     pub use find_field::*;
     mod find_method;
     pub use find_method::*;
+    mod logical;
+    pub use logical::*;
+    mod dex_targets;
+    pub use dex_targets::*;
 }
 
 #[allow(unused)]
@@ -117,6 +125,8 @@ pub mod result {
 
     mod data_collections;
     pub use data_collections::*;
+    mod data_stream;
+    pub use data_stream::*;
 
     mod class_data;
     pub use class_data::*;
@@ -134,8 +144,66 @@ pub mod result {
     pub use annotation_encode_value::*;
     mod using_field_data;
     pub use using_field_data::*;
+    mod using_number;
+    pub use using_number::*;
     mod field_using_type;
     pub use field_using_type::*;
+    mod string_xref_index;
+    pub use string_xref_index::*;
+    mod type_ref;
+    pub use type_ref::*;
+    mod resolved_frame;
+    pub use resolved_frame::*;
+    mod enum_constant;
+    pub use enum_constant::*;
+    mod annotation_class_info;
+    pub use annotation_class_info::*;
+    mod from_dex_annotation;
+    pub use from_dex_annotation::*;
+}
+
+#[allow(unused)]
+pub mod apk {
+    mod apk_archive;
+    pub(crate) use apk_archive::*;
+    mod res_chunk;
+    pub use res_chunk::*;
+    mod resource_table;
+    pub use resource_table::*;
+    mod binary_xml;
+    pub(crate) use binary_xml::*;
+    mod android_manifest;
+    pub use android_manifest::*;
+    mod apk_signing;
+    pub use apk_signing::*;
+    mod apk_info;
+    pub use apk_info::*;
+}
+
+#[allow(unused)]
+pub mod dex {
+    mod dex_file;
+    pub use dex_file::*;
+    mod debug_info;
+    pub use debug_info::*;
+    mod try_block;
+    pub use try_block::*;
+    mod enum_constants;
+    pub use enum_constants::*;
+    mod dex_file_info;
+    pub use dex_file_info::*;
+    mod dex_writer;
+    pub(crate) use dex_writer::*;
+    mod dex_export;
+    pub use dex_export::*;
+    mod dex_library;
+    pub(crate) use dex_library::*;
+    mod dex_reader;
+    pub(crate) use dex_reader::*;
+    mod encoded_value;
+    pub use encoded_value::*;
+    mod instruction;
+    pub use instruction::*;
 }
 
 pub mod wrap {
@@ -153,6 +221,10 @@ pub mod uitls {
     pub use modifier::*;
     mod dex_signature;
     pub use dex_signature::*;
+    mod generic_signature;
+    pub use generic_signature::*;
+    mod stack_trace;
+    pub use stack_trace::*;
     mod mutf8;
     pub use mutf8::*;
     mod string_unicode_encode_decode;
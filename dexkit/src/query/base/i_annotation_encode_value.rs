@@ -2,4 +2,10 @@ use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
 
 pub trait IAnnotationEncodeValue {
     fn inner_build_annotation_union(&self, fbb: &mut FlatBufferBuilder) -> WIPOffset<UnionWIPOffset>;
+
+    /// internal use, whether the value holds a condition checked after the native search,
+    /// annotation values are only evaluated natively so such a value is rejected
+    fn has_post_match_value(&self) -> bool {
+        false
+    }
 }
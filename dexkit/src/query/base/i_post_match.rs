@@ -0,0 +1,83 @@
+use crate::errors::Error;
+use crate::query::matchers::ClassMatcher;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// Conditions the native side can not evaluate (e.g. `StringMatchType::Regex`),
+/// they are left out of the native query and checked on the results.
+pub(crate) trait IPostMatch<D: ?Sized> {
+    fn has_post_match(&self) -> bool;
+
+    fn post_match(&self, data: &D, cache: &PostMatchCache) -> bool;
+
+    /// Err if a regex does not compile, or a post match condition is nested where the
+    /// results can not be checked in Rust, see `reject_post_match`.
+    fn check_post_match(&self) -> Result<(), Error>;
+}
+
+/// internal use, list matchers (methods, fields, interfaces, annotations, using fields),
+/// annotation elements and batch string groups are only evaluated natively.
+/// The native side can not tell which item satisfied which condition, so a condition
+/// checked after the native search can not be combined with them and is rejected.
+pub(crate) fn reject_post_match(position: &str, has_post_match: bool) -> Result<(), Error> {
+    if has_post_match {
+        return Err(Error::MatcherError(format!(
+            "{} can not hold conditions checked after the native search, e.g. StringMatchType::Regex",
+            position
+        )));
+    }
+    Ok(())
+}
+
+/// internal use, state of the post match of one query, created by each bridge search.
+/// Matchers are not mutated by a search, so nothing cached here outlives the query.
+#[derive(Default)]
+pub(crate) struct PostMatchCache {
+    /// ids of the classes matching an outer class matcher, keyed by the matcher address,
+    /// the query owning the matchers outlives the cache.
+    outer_class_ids: RefCell<HashMap<usize, HashSet<i64>>>,
+}
+
+impl PostMatchCache {
+    /// internal use, whether `id` matches the outer class `matcher`,
+    /// the matching classes are searched by `search` once per query.
+    pub(crate) fn is_outer_class(
+        &self,
+        matcher: &ClassMatcher,
+        id: i64,
+        search: impl FnOnce() -> HashSet<i64>,
+    ) -> bool {
+        let key = matcher as *const ClassMatcher as usize;
+        if let Some(ids) = self.outer_class_ids.borrow().get(&key) {
+            return ids.contains(&id);
+        }
+        // the search post matches nested outer class matchers through this cache,
+        // so no borrow is held while it runs
+        let ids = search();
+        let contains = ids.contains(&id);
+        self.outer_class_ids.borrow_mut().insert(key, ids);
+        contains
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outer_class_searched_once_per_cache() {
+        let matcher = ClassMatcher::create();
+        let mut searches = 0;
+        let mut search = || {
+            searches += 1;
+            HashSet::from([1i64])
+        };
+        let cache = PostMatchCache::default();
+        assert!(cache.is_outer_class(&matcher, 1, &mut search));
+        assert!(!cache.is_outer_class(&matcher, 2, &mut search));
+        // a new query searches again
+        let cache = PostMatchCache::default();
+        assert!(cache.is_outer_class(&matcher, 1, &mut search));
+        assert_eq!(searches, 2);
+    }
+}
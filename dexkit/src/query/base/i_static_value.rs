@@ -0,0 +1,84 @@
+use crate::query::matchers::{
+    EncodeValueBoolean, EncodeValueByte, EncodeValueChar, EncodeValueDouble, EncodeValueFloat,
+    EncodeValueInt, EncodeValueLong, EncodeValueNull, EncodeValueShort, EncodeValueString,
+};
+use crate::result::EncodeValueData;
+
+/// A value compared with the static value of a field, see `FieldMatcher::set_static_value`.
+/// Integral values match the other integral types holding the same number,
+/// e.g. `EncodeValueInt(1)` matches a `static final byte` of 1.
+pub trait IStaticValue {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool;
+}
+
+/// internal use, the number of an integral value
+fn integral(value: &EncodeValueData) -> Option<i64> {
+    match value {
+        EncodeValueData::Byte(v) => Some(*v as i64),
+        EncodeValueData::Short(v) => Some(*v as i64),
+        EncodeValueData::Char(v) => Some(*v as i64),
+        EncodeValueData::Int(v) => Some(*v as i64),
+        EncodeValueData::Long(v) => Some(*v),
+        _ => None,
+    }
+}
+
+impl IStaticValue for EncodeValueByte {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        integral(value) == Some(self.0 as i64)
+    }
+}
+
+impl IStaticValue for EncodeValueShort {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        integral(value) == Some(self.0 as i64)
+    }
+}
+
+impl IStaticValue for EncodeValueChar {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        integral(value) == Some(self.0 as i64)
+    }
+}
+
+impl IStaticValue for EncodeValueInt {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        integral(value) == Some(self.0 as i64)
+    }
+}
+
+impl IStaticValue for EncodeValueLong {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        integral(value) == Some(self.0)
+    }
+}
+
+impl IStaticValue for EncodeValueFloat {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        matches!(value, EncodeValueData::Float(v) if v.to_bits() == self.0.to_bits())
+    }
+}
+
+impl IStaticValue for EncodeValueDouble {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        matches!(value, EncodeValueData::Double(v) if v.to_bits() == self.0.to_bits())
+    }
+}
+
+impl IStaticValue for EncodeValueString {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        matches!(value, EncodeValueData::String(v) if *v == self.0)
+    }
+}
+
+impl IStaticValue for EncodeValueNull {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        matches!(value, EncodeValueData::Null)
+    }
+}
+
+impl IStaticValue for EncodeValueBoolean {
+    fn match_static_value(&self, value: &EncodeValueData) -> bool {
+        matches!(value, EncodeValueData::Bool(v) if *v == self.0)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     BatchFindMethodUsingStrings as FBBatchFindMethodUsingStrings,
     BatchFindMethodUsingStringsArgs as FBBatchFindMethodUsingStringsArgs,
@@ -59,6 +60,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBBatchFindMethodUsingStrings<'a>>>
         let in_classes = self.search_classes.as_ref().map(|classes| {
             let ids = classes
                 .iter()
+                .filter(|class| !class.is_library())
                 .map(|class| class.get_mine_encode_id())
                 .collect::<Vec<i64>>();
             fbb.create_vector(&ids)
@@ -160,4 +162,21 @@ impl<'a> BatchFindMethodUsingStrings<'a> {
         self.search_groups.get_or_insert_with(Vec::new).push(group);
         self
     }
+
+    /// internal use, Err if a group holds a matcher `StringMatchersGroup::try_add_string_matcher`
+    /// rejects, the search then finds nothing
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        self.search_groups
+            .iter()
+            .flatten()
+            .try_for_each(|group| group.check())
+    }
+
+    /// internal use, false if the search classes are all library classes,
+    /// see `FindClass::has_native_scope`
+    pub(crate) fn has_native_scope(&self) -> bool {
+        self.search_classes
+            .as_ref()
+            .is_none_or(|classes| classes.iter().any(|class| !class.is_library()))
+    }
 }
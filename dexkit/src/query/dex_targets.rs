@@ -0,0 +1,99 @@
+use crate::errors::{Error, ResolveError};
+use crate::result::{ClassData, FieldData, MethodData};
+
+/// Resolve a struct of hook targets with one query per field, generates
+/// `resolve(&DexkitBridge) -> Result<Self, ResolveError>`, requires the `derive` feature:
+///
+/// ```ignore
+/// #[derive(DexTargets)]
+/// struct LoginTargets<'a> {
+///     #[class(using_string = "LoginManager init", implements = "java.io.Closeable")]
+///     login_manager: ClassData<'a>,
+///     #[method(using_string = "login", returns = "void", in_class = "self.login_manager")]
+///     login: MethodData<'a>,
+///     #[method(params = "java.lang.String, int", in_class = "self.login_manager")]
+///     retry: Option<MethodData<'a>>,
+///     #[field(type = "self.login_manager")]
+///     instances: Vec<FieldData<'a>>,
+/// }
+///
+/// let targets = LoginTargets::resolve(&bridge)?;
+/// ```
+///
+/// Each field takes one of `#[class(...)]`, `#[method(...)]` or `#[field(...)]`:
+/// - class: `name`, `using_string` (repeatable), `super_class`, `implements` (repeatable), `source`
+/// - method: `name`, `using_string` (repeatable), `returns`, `params`, `in_class`
+/// - field: `name`, `type`, `in_class`
+///
+/// Names and `source` are exact, `using_string` matches a part of the string. Type conditions
+/// accept a class name or `"self.<field>"`, the name of a class target resolved before, so the
+/// queries run in dependency order. A `T` field requires exactly one match, `Option<T>` at most one and
+/// `Vec<T>` takes all of them.
+#[cfg(feature = "derive")]
+pub use dexkit_derive::DexTargets;
+
+/// internal use, helpers of the derived `resolve`
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    pub trait Target {
+        fn target_descriptor(&self) -> String;
+    }
+
+    impl Target for ClassData<'_> {
+        fn target_descriptor(&self) -> String {
+            self.descriptor()
+        }
+    }
+
+    impl Target for MethodData<'_> {
+        fn target_descriptor(&self) -> String {
+            self.descriptor()
+        }
+    }
+
+    impl Target for FieldData<'_> {
+        fn target_descriptor(&self) -> String {
+            self.descriptor()
+        }
+    }
+
+    /// the query of a target, Err if its matcher was rejected
+    pub fn query<Q>(target: &str, query: Result<Q, Error>) -> Result<Q, ResolveError> {
+        query.map_err(|e| ResolveError::InvalidQuery {
+            target: target.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// the single match of a required target
+    pub fn unique<T: Target>(target: &str, found: Vec<T>) -> Result<T, ResolveError> {
+        optional(target, found)?.ok_or_else(|| ResolveError::NotFound(target.to_string()))
+    }
+
+    /// the match of an optional target, None without match
+    pub fn optional<T: Target>(target: &str, mut found: Vec<T>) -> Result<Option<T>, ResolveError> {
+        match found.len() {
+            0 | 1 => Ok(found.pop()),
+            _ => Err(ResolveError::NotUnique {
+                target: target.to_string(),
+                candidates: found.iter().map(Target::target_descriptor).collect(),
+            }),
+        }
+    }
+
+    /// every match of a list target
+    pub fn all<T: Target>(_target: &str, found: Vec<T>) -> Result<Vec<T>, ResolveError> {
+        Ok(found)
+    }
+
+    /// the name of a class target, for `"self.<field>"` type conditions of `target`,
+    /// Err if the name can not be read
+    pub fn class_name(target: &str, class: &ClassData) -> Result<String, ResolveError> {
+        class.name().ok_or_else(|| ResolveError::InvalidQuery {
+            target: target.to_string(),
+            message: format!("no class name for {}", class.descriptor()),
+        })
+    }
+}
@@ -7,6 +7,18 @@ pub enum RetentionPolicyType {
     Runtime,
 }
 
+impl RetentionPolicyType {
+    /// internal use, from the `java.lang.annotation.RetentionPolicy` constant name
+    pub(crate) fn from_java_name(name: &str) -> Option<Self> {
+        match name {
+            "SOURCE" => Some(Self::Source),
+            "CLASS" => Some(Self::Class),
+            "RUNTIME" => Some(Self::Runtime),
+            _ => None,
+        }
+    }
+}
+
 impl From<RetentionPolicyType> for FBRetentionPolicyType {
     fn from(value: RetentionPolicyType) -> Self {
         match value {
@@ -7,6 +7,17 @@ pub enum StringMatchType {
     EndWith,
     SimilarRegex,
     Equals,
+    /// full regular expression, evaluated in Rust after the native search.
+    /// Not allowed inside list matchers (methods, fields, interfaces, annotations, using fields),
+    /// annotation elements and batch string groups: a query holding one finds nothing,
+    /// `FindClass::try_set_matcher` and the other `try_*` setters report it.
+    ///
+    /// Annotation strings (element values and using strings) are left out for this reason:
+    /// DexKit matches an annotation matcher against all annotations of an item at once and
+    /// only reports whether the list matched, so a regex checked afterwards could be satisfied
+    /// by another annotation than the one the native conditions matched. Match them with
+    /// `SimilarRegex`, or check `ClassData::annotations` of the results.
+    Regex,
 }
 
 impl Default for StringMatchType {
@@ -23,6 +34,8 @@ impl From<StringMatchType> for FBStringMatchType {
             StringMatchType::EndWith => Self::EndWith,
             StringMatchType::SimilarRegex => Self::SimilarRegex,
             StringMatchType::Equals => Self::Equal,
+            // `Regex` matchers are left out of the native query, never sent
+            StringMatchType::Regex => Self::SimilarRegex,
         }
     }
 }
@@ -15,6 +15,25 @@ pub enum TargetElementType {
     //TODO add MODULE
 }
 
+impl TargetElementType {
+    /// internal use, from the `java.lang.annotation.ElementType` constant name
+    pub(crate) fn from_java_name(name: &str) -> Option<Self> {
+        match name {
+            "TYPE" => Some(Self::Type),
+            "FIELD" => Some(Self::Field),
+            "METHOD" => Some(Self::Method),
+            "PARAMETER" => Some(Self::Parameter),
+            "CONSTRUCTOR" => Some(Self::Constructor),
+            "LOCAL_VARIABLE" => Some(Self::LocalVariable),
+            "ANNOTATION_TYPE" => Some(Self::AnnotationType),
+            "PACKAGE" => Some(Self::Package),
+            "TYPE_PARAMETER" => Some(Self::TypeParameter),
+            "TYPE_USE" => Some(Self::TypeUse),
+            _ => None,
+        }
+    }
+}
+
 impl From<TargetElementType> for FBTargetElementType {
     fn from(value: TargetElementType) -> Self {
         match value {
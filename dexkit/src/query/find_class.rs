@@ -1,7 +1,9 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     FindClass as FBFindClass, FindClassArgs as FBFindClassArgs,
 };
-use crate::query::base::BaseQuery;
+use crate::query::Logical;
+use crate::query::base::{BaseQuery, IPostMatch, PostMatchCache};
 use crate::query::matchers::ClassMatcher;
 use crate::result::ClassData;
 use crate::result::base::BaseData;
@@ -12,8 +14,11 @@ pub struct FindClass<'a> {
     exclude_packages: Option<Vec<String>>,
     ignore_packages_case: bool,
     search_classes: Option<Vec<ClassData<'a>>>,
+    include_library: bool,
     find_first: bool,
     matcher: Option<ClassMatcher>,
+    logical_matcher: Option<Logical<ClassMatcher>>,
+    predicate: Option<Box<dyn Fn(&ClassData) -> bool + 'a>>,
 }
 
 impl<'a> Default for FindClass<'a> {
@@ -23,18 +28,18 @@ impl<'a> Default for FindClass<'a> {
             exclude_packages: None,
             ignore_packages_case: false,
             search_classes: None,
+            include_library: false,
             find_first: false,
             matcher: None,
+            logical_matcher: None,
+            predicate: None,
         }
     }
 }
 
 impl<'a> From<FindClass<'a>> for Vec<u8> {
     fn from(value: FindClass) -> Self {
-        let mut fbb = FlatBufferBuilder::with_capacity(1024);
-        let root = value.inner_build(&mut fbb);
-        fbb.finish(root, None);
-        fbb.finished_data().to_vec()
+        value.to_buffer()
     }
 }
 
@@ -51,6 +56,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBFindClass<'a>>> for FindClass<'a> {
         let in_classes = self.search_classes.as_ref().map(|classes| {
             let ids = classes
                 .iter()
+                .filter(|class| !class.is_library())
                 .map(|class| class.get_mine_encode_id())
                 .collect::<Vec<i64>>();
             fbb.create_vector(&ids)
@@ -64,7 +70,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBFindClass<'a>>> for FindClass<'a> {
                 exclude_packages,
                 ignore_packages_case: self.ignore_packages_case,
                 in_classes,
-                find_first: self.find_first,
+                find_first: self.find_first && !self.has_post_match(),
                 matcher,
             },
         )
@@ -97,16 +103,57 @@ impl<'a> FindClass<'a> {
         self
     }
 
+    /// Also return classes of the libraries attached with `DexkitBridge::add_library_path`,
+    /// after the classes of the apk. They are not loaded into DexKit, so only the packages,
+    /// the search classes and the predicate are checked for them, a query with a matcher
+    /// does not return them.
+    pub fn set_include_library(mut self, include: bool) -> Self {
+        self.include_library = include;
+        self
+    }
+
     pub fn set_find_first(mut self, find_first: bool) -> Self {
         self.find_first = find_first;
         self
     }
 
+    /// A matcher `try_set_matcher` rejects makes the query match nothing.
     pub fn set_matcher(mut self, matcher: ClassMatcher) -> Self {
         self.matcher = Some(matcher);
         self
     }
 
+    /// `set_matcher`, Err if a `StringMatchType::Regex` pattern does not compile, or a condition
+    /// checked after the native search is nested where it can not be checked, see `StringMatchType::Regex`.
+    pub fn try_set_matcher(self, matcher: ClassMatcher) -> Result<Self, Error> {
+        matcher.check_post_match()?;
+        Ok(self.set_matcher(matcher))
+    }
+
+    /// Logical composition of matchers, executed as several native queries.
+    /// If `set_matcher` is also used, both must match.
+    pub fn set_logical_matcher(mut self, matcher: Logical<ClassMatcher>) -> Self {
+        self.logical_matcher = Some(matcher);
+        self
+    }
+
+    /// `set_logical_matcher`, Err if one of the matchers is rejected as by `try_set_matcher`.
+    pub fn try_set_logical_matcher(self, matcher: Logical<ClassMatcher>) -> Result<Self, Error> {
+        matcher.try_for_each_item(&mut |m| m.check_post_match())?;
+        Ok(self.set_logical_matcher(matcher))
+    }
+
+    /// Custom condition checked in Rust after the built-in conditions pass.
+    /// The native search calls it back for each candidate and only the accepted candidates
+    /// are returned. With a logical matcher it filters the combined results instead.
+    pub fn set_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&ClassData) -> bool + 'a,
+    {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
     // extend search_packages
     pub fn add_search_package<S: Into<String>>(mut self, package: S) -> Self {
         self.search_packages
@@ -128,4 +175,174 @@ impl<'a> FindClass<'a> {
         self.search_classes.get_or_insert_with(Vec::new).push(class);
         self
     }
+
+    // logical, see `Logical`
+    pub fn any_of<I, L>(queries: I) -> Logical<FindClass<'a>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FindClass<'a>>>,
+    {
+        Logical::any_of(queries)
+    }
+
+    pub fn all_of<I, L>(queries: I) -> Logical<FindClass<'a>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FindClass<'a>>>,
+    {
+        Logical::all_of(queries)
+    }
+
+    pub fn not<L: Into<Logical<FindClass<'a>>>>(query: L) -> Logical<FindClass<'a>> {
+        Logical::not(query)
+    }
+
+    /// internal use, whether only the first result is wanted
+    pub(crate) fn is_find_first(&self) -> bool {
+        self.find_first
+    }
+
+    /// internal use, take the logical matcher out of the query, the plain matcher is merged into it.
+    /// Returns None and leaves the query untouched if no logical matcher is set.
+    pub(crate) fn take_logical_matcher(&mut self) -> Option<Logical<ClassMatcher>> {
+        let logical = self.logical_matcher.take()?;
+        Some(match self.matcher.take() {
+            Some(matcher) => logical.and(matcher),
+            None => logical,
+        })
+    }
+
+    /// internal use, copy the query scope with another matcher, `find_first` is not copied
+    pub(crate) fn fork(&self, matcher: Option<ClassMatcher>) -> Self {
+        Self {
+            search_packages: self.search_packages.clone(),
+            exclude_packages: self.exclude_packages.clone(),
+            ignore_packages_case: self.ignore_packages_case,
+            search_classes: self.search_classes.clone(),
+            include_library: false,
+            find_first: false,
+            matcher,
+            logical_matcher: None,
+            predicate: None,
+        }
+    }
+
+    /// internal use, take the predicate out of the query.
+    /// The native `find_first` is disabled, the first native result may be rejected by the predicate.
+    pub(crate) fn take_predicate(&mut self) -> Option<Box<dyn Fn(&ClassData) -> bool + 'a>> {
+        let predicate = self.predicate.take();
+        if predicate.is_some() {
+            self.find_first = false;
+        }
+        predicate
+    }
+
+    /// internal use, whether the matcher has conditions checked after the native search,
+    /// the native `find_first` is disabled then.
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.matcher.as_ref().is_some_and(|m| m.has_post_match())
+    }
+
+    /// internal use, Err for a matcher `try_set_matcher` or `try_set_logical_matcher` rejects,
+    /// the query then matches nothing
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if let Some(matcher) = &self.matcher {
+            matcher.check_post_match()?;
+        }
+        match &self.logical_matcher {
+            Some(logical) => logical.try_for_each_item(&mut |m| m.check_post_match()),
+            None => Ok(()),
+        }
+    }
+
+    /// internal use, check the conditions the native search left out
+    pub(crate) fn post_match(&self, data: &ClassData, cache: &PostMatchCache) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|m| m.post_match(data, cache))
+    }
+
+    /// internal use, whether a logical matcher is set
+    pub(crate) fn has_logical_matcher(&self) -> bool {
+        self.logical_matcher.is_some()
+    }
+
+    /// internal use, whether library classes are included, see `set_include_library`
+    pub(crate) fn includes_library(&self) -> bool {
+        self.include_library
+    }
+
+    /// internal use, stop including library classes, the bridge matches them apart
+    pub(crate) fn take_include_library(&mut self) -> bool {
+        std::mem::take(&mut self.include_library)
+    }
+
+    /// internal use, whether a library class is returned by the query, see `set_include_library`
+    pub(crate) fn library_match(&self, class: &ClassData) -> bool {
+        let name = class.name().unwrap_or_default();
+        let in_package = |packages: &[String]| {
+            packages.iter().any(|package| {
+                let prefix = format!("{}.", package.replace('/', "."));
+                match self.ignore_packages_case {
+                    true => name.to_lowercase().starts_with(&prefix.to_lowercase()),
+                    false => name.starts_with(&prefix),
+                }
+            })
+        };
+        class.is_library()
+            && self.matcher.is_none()
+            && self.logical_matcher.is_none()
+            && self.search_packages.as_deref().is_none_or(in_package)
+            && !self.exclude_packages.as_deref().is_some_and(in_package)
+            && self
+                .search_classes
+                .as_ref()
+                .is_none_or(|classes| classes.contains(class))
+            && self
+                .predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(class))
+    }
+
+    /// internal use, false if the search classes are all library classes, DexKit would
+    /// search every class for an empty scope
+    pub(crate) fn has_native_scope(&self) -> bool {
+        self.search_classes
+            .as_ref()
+            .is_none_or(|classes| classes.iter().any(|class| !class.is_library()))
+    }
+
+    /// internal use, serialize the query without consuming it
+    pub(crate) fn to_buffer(&self) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::with_capacity(1024);
+        let root = self.inner_build(&mut fbb);
+        fbb.finish(root, None);
+        fbb.finished_data().to_vec()
+    }
+
+    /// internal use, serialize a query over all classes with a borrowed matcher,
+    /// used for nested matchers evaluated after the native search
+    pub(crate) fn matcher_buffer(matcher: &ClassMatcher) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::with_capacity(1024);
+        let matcher = Some(matcher.inner_build(&mut fbb));
+        let root = FBFindClass::create(
+            &mut fbb,
+            &FBFindClassArgs {
+                matcher,
+                ..Default::default()
+            },
+        );
+        fbb.finish(root, None);
+        fbb.finished_data().to_vec()
+    }
+
+    /// internal use, take the classes the query is restricted to
+    pub(crate) fn take_search_classes(&mut self) -> Option<Vec<ClassData<'a>>> {
+        self.search_classes.take()
+    }
+
+    /// internal use, restrict the query to a chunk of classes, see `DataStream`
+    pub(crate) fn set_chunk_classes(&mut self, classes: Vec<ClassData<'a>>) {
+        self.search_classes = Some(classes);
+    }
 }
@@ -1,7 +1,9 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     FindField as FBFindField, FindFieldArgs as FBFindFieldArgs,
 };
-use crate::query::base::BaseQuery;
+use crate::query::Logical;
+use crate::query::base::{BaseQuery, IPostMatch, PostMatchCache};
 use crate::query::matchers::FieldMatcher;
 use crate::result::base::BaseData;
 use crate::result::{ClassData, FieldData};
@@ -15,6 +17,7 @@ pub struct FindField<'a> {
     search_fields: Option<Vec<FieldData<'a>>>,
     find_first: bool,
     matcher: Option<FieldMatcher>,
+    logical_matcher: Option<Logical<FieldMatcher>>,
 }
 
 impl<'a> Default for FindField<'a> {
@@ -27,16 +30,14 @@ impl<'a> Default for FindField<'a> {
             search_fields: None,
             find_first: false,
             matcher: None,
+            logical_matcher: None,
         }
     }
 }
 
 impl<'a> From<FindField<'a>> for Vec<u8> {
     fn from(value: FindField) -> Self {
-        let mut fbb = FlatBufferBuilder::with_capacity(1024);
-        let root = value.inner_build(&mut fbb);
-        fbb.finish(root, None);
-        fbb.finished_data().to_vec()
+        value.to_buffer()
     }
 }
 
@@ -56,6 +57,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBFindField<'a>>> for FindField<'a> {
         let in_classes = self.search_classes.as_ref().map(|classes| {
             let ids = classes
                 .iter()
+                .filter(|class| !class.is_library())
                 .map(|class| class.get_mine_encode_id())
                 .collect::<Vec<i64>>();
             fbb.create_vector(&ids)
@@ -78,7 +80,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBFindField<'a>>> for FindField<'a> {
                 ignore_packages_case: self.ignore_packages_case,
                 in_classes,
                 in_fields,
-                find_first: self.find_first,
+                find_first: self.find_first && !self.has_post_match(),
                 matcher,
             },
         );
@@ -122,11 +124,32 @@ impl<'a> FindField<'a> {
         self
     }
 
+    /// A matcher `try_set_matcher` rejects makes the query match nothing.
     pub fn set_matcher(mut self, matcher: FieldMatcher) -> Self {
         self.matcher = Some(matcher);
         self
     }
 
+    /// `set_matcher`, Err if a `StringMatchType::Regex` pattern does not compile, or a condition
+    /// checked after the native search is nested where it can not be checked, see `StringMatchType::Regex`.
+    pub fn try_set_matcher(self, matcher: FieldMatcher) -> Result<Self, Error> {
+        matcher.check_post_match()?;
+        Ok(self.set_matcher(matcher))
+    }
+
+    /// Logical composition of matchers, executed as several native queries.
+    /// If `set_matcher` is also used, both must match.
+    pub fn set_logical_matcher(mut self, matcher: Logical<FieldMatcher>) -> Self {
+        self.logical_matcher = Some(matcher);
+        self
+    }
+
+    /// `set_logical_matcher`, Err if one of the matchers is rejected as by `try_set_matcher`.
+    pub fn try_set_logical_matcher(self, matcher: Logical<FieldMatcher>) -> Result<Self, Error> {
+        matcher.try_for_each_item(&mut |m| m.check_post_match())?;
+        Ok(self.set_logical_matcher(matcher))
+    }
+
     // extend search_packages
     pub fn add_search_package<S: Into<String>>(mut self, package: S) -> Self {
         self.search_packages
@@ -154,4 +177,115 @@ impl<'a> FindField<'a> {
         self.search_fields.get_or_insert_with(Vec::new).push(field);
         self
     }
+
+    // logical, see `Logical`
+    pub fn any_of<I, L>(queries: I) -> Logical<FindField<'a>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FindField<'a>>>,
+    {
+        Logical::any_of(queries)
+    }
+
+    pub fn all_of<I, L>(queries: I) -> Logical<FindField<'a>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FindField<'a>>>,
+    {
+        Logical::all_of(queries)
+    }
+
+    pub fn not<L: Into<Logical<FindField<'a>>>>(query: L) -> Logical<FindField<'a>> {
+        Logical::not(query)
+    }
+
+    /// internal use, whether only the first result is wanted
+    pub(crate) fn is_find_first(&self) -> bool {
+        self.find_first
+    }
+
+    /// internal use, take the logical matcher out of the query, the plain matcher is merged into it.
+    /// Returns None and leaves the query untouched if no logical matcher is set.
+    pub(crate) fn take_logical_matcher(&mut self) -> Option<Logical<FieldMatcher>> {
+        let logical = self.logical_matcher.take()?;
+        Some(match self.matcher.take() {
+            Some(matcher) => logical.and(matcher),
+            None => logical,
+        })
+    }
+
+    /// internal use, copy the query scope with another matcher, `find_first` is not copied
+    pub(crate) fn fork(&self, matcher: Option<FieldMatcher>) -> Self {
+        Self {
+            search_packages: self.search_packages.clone(),
+            exclude_packages: self.exclude_packages.clone(),
+            ignore_packages_case: self.ignore_packages_case,
+            search_classes: self.search_classes.clone(),
+            search_fields: self.search_fields.clone(),
+            find_first: false,
+            matcher,
+            logical_matcher: None,
+        }
+    }
+
+    /// internal use, whether the matcher has conditions checked after the native search,
+    /// the native `find_first` is disabled then.
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.matcher.as_ref().is_some_and(|m| m.has_post_match())
+    }
+
+    /// internal use, Err for a matcher `try_set_matcher` or `try_set_logical_matcher` rejects,
+    /// the query then matches nothing
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if let Some(matcher) = &self.matcher {
+            matcher.check_post_match()?;
+        }
+        match &self.logical_matcher {
+            Some(logical) => logical.try_for_each_item(&mut |m| m.check_post_match()),
+            None => Ok(()),
+        }
+    }
+
+    /// internal use, false if the search classes are all library classes,
+    /// see `FindClass::has_native_scope`
+    pub(crate) fn has_native_scope(&self) -> bool {
+        self.search_classes
+            .as_ref()
+            .is_none_or(|classes| classes.iter().any(|class| !class.is_library()))
+    }
+
+    /// internal use, check the conditions the native search left out
+    pub(crate) fn post_match(&self, data: &FieldData, cache: &PostMatchCache) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|m| m.post_match(data, cache))
+    }
+
+    /// internal use, whether a logical matcher is set
+    pub(crate) fn has_logical_matcher(&self) -> bool {
+        self.logical_matcher.is_some()
+    }
+
+    /// internal use, serialize the query without consuming it
+    pub(crate) fn to_buffer(&self) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::with_capacity(1024);
+        let root = self.inner_build(&mut fbb);
+        fbb.finish(root, None);
+        fbb.finished_data().to_vec()
+    }
+
+    /// internal use, take the classes the query is restricted to
+    pub(crate) fn take_search_classes(&mut self) -> Option<Vec<ClassData<'a>>> {
+        self.search_classes.take()
+    }
+
+    /// internal use, restrict the query to a chunk of classes, see `DataStream`
+    pub(crate) fn set_chunk_classes(&mut self, classes: Vec<ClassData<'a>>) {
+        self.search_classes = Some(classes);
+    }
+
+    /// internal use, whether the query is restricted to single items
+    pub(crate) fn has_item_scope(&self) -> bool {
+        self.search_fields.is_some()
+    }
 }
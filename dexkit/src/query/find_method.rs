@@ -1,7 +1,10 @@
+use crate::DexkitBridge;
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     FindMethod as FBMethodFind, FindMethodArgs as FBMethodFindArgs,
 };
-use crate::query::base::BaseQuery;
+use crate::query::Logical;
+use crate::query::base::{BaseQuery, IPostMatch, PostMatchCache};
 use crate::query::matchers::MethodMatcher;
 use crate::result::base::BaseData;
 use crate::result::{ClassData, MethodData};
@@ -15,6 +18,8 @@ pub struct FindMethod<'a> {
     search_methods: Option<Vec<MethodData<'a>>>,
     find_first: bool,
     matcher: Option<MethodMatcher>,
+    logical_matcher: Option<Logical<MethodMatcher>>,
+    predicate: Option<Box<dyn Fn(&MethodData) -> bool + 'a>>,
 }
 
 impl<'a> Default for FindMethod<'a> {
@@ -27,16 +32,15 @@ impl<'a> Default for FindMethod<'a> {
             search_methods: None,
             find_first: false,
             matcher: None,
+            logical_matcher: None,
+            predicate: None,
         }
     }
 }
 
 impl<'a> From<FindMethod<'a>> for Vec<u8> {
     fn from(value: FindMethod) -> Self {
-        let mut fbb = FlatBufferBuilder::with_capacity(1024);
-        let root = value.inner_build(&mut fbb);
-        fbb.finish(root, None);
-        fbb.finished_data().to_vec()
+        value.to_buffer()
     }
 }
 
@@ -56,6 +60,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBMethodFind<'a>>> for FindMethod<'a> {
         let in_classes = self.search_classes.as_ref().map(|classes| {
             let ids = classes
                 .iter()
+                .filter(|class| !class.is_library())
                 .map(|class| class.get_mine_encode_id())
                 .collect::<Vec<i64>>();
             fbb.create_vector(&ids)
@@ -77,7 +82,7 @@ impl<'a> BaseQuery<'a, WIPOffset<FBMethodFind<'a>>> for FindMethod<'a> {
                 ignore_packages_case: self.ignore_packages_case,
                 in_classes,
                 in_methods,
-                find_first: self.find_first,
+                find_first: self.find_first && !self.has_post_match(),
                 matcher,
             },
         )
@@ -120,11 +125,43 @@ impl<'a> FindMethod<'a> {
         self
     }
 
+    /// A matcher `try_set_matcher` rejects makes the query match nothing.
     pub fn set_matcher(mut self, matcher: MethodMatcher) -> Self {
         self.matcher = Some(matcher);
         self
     }
 
+    /// `set_matcher`, Err if a `StringMatchType::Regex` pattern does not compile, or a condition
+    /// checked after the native search is nested where it can not be checked, see `StringMatchType::Regex`.
+    pub fn try_set_matcher(self, matcher: MethodMatcher) -> Result<Self, Error> {
+        matcher.check_post_match()?;
+        Ok(self.set_matcher(matcher))
+    }
+
+    /// Logical composition of matchers, executed as several native queries.
+    /// If `set_matcher` is also used, both must match.
+    pub fn set_logical_matcher(mut self, matcher: Logical<MethodMatcher>) -> Self {
+        self.logical_matcher = Some(matcher);
+        self
+    }
+
+    /// `set_logical_matcher`, Err if one of the matchers is rejected as by `try_set_matcher`.
+    pub fn try_set_logical_matcher(self, matcher: Logical<MethodMatcher>) -> Result<Self, Error> {
+        matcher.try_for_each_item(&mut |m| m.check_post_match())?;
+        Ok(self.set_logical_matcher(matcher))
+    }
+
+    /// Custom condition checked in Rust after the built-in conditions pass.
+    /// The native search calls it back for each candidate and only the accepted candidates
+    /// are returned. With a logical matcher it filters the combined results instead.
+    pub fn set_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&MethodData) -> bool + 'a,
+    {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
     // extend search_packages
     pub fn add_search_package<S: Into<String>>(mut self, package: S) -> Self {
         self.search_packages
@@ -152,4 +189,137 @@ impl<'a> FindMethod<'a> {
         self.search_methods.get_or_insert_with(Vec::new).push(field);
         self
     }
+
+    // logical, see `Logical`
+    pub fn any_of<I, L>(queries: I) -> Logical<FindMethod<'a>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FindMethod<'a>>>,
+    {
+        Logical::any_of(queries)
+    }
+
+    pub fn all_of<I, L>(queries: I) -> Logical<FindMethod<'a>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FindMethod<'a>>>,
+    {
+        Logical::all_of(queries)
+    }
+
+    pub fn not<L: Into<Logical<FindMethod<'a>>>>(query: L) -> Logical<FindMethod<'a>> {
+        Logical::not(query)
+    }
+
+    /// internal use, whether only the first result is wanted
+    pub(crate) fn is_find_first(&self) -> bool {
+        self.find_first
+    }
+
+    /// internal use, take the logical matcher out of the query, the plain matcher is merged into it.
+    /// Returns None and leaves the query untouched if no logical matcher is set.
+    pub(crate) fn take_logical_matcher(&mut self) -> Option<Logical<MethodMatcher>> {
+        let logical = self.logical_matcher.take()?;
+        Some(match self.matcher.take() {
+            Some(matcher) => logical.and(matcher),
+            None => logical,
+        })
+    }
+
+    /// internal use, copy the query scope with another matcher, `find_first` is not copied
+    pub(crate) fn fork(&self, matcher: Option<MethodMatcher>) -> Self {
+        Self {
+            search_packages: self.search_packages.clone(),
+            exclude_packages: self.exclude_packages.clone(),
+            ignore_packages_case: self.ignore_packages_case,
+            search_classes: self.search_classes.clone(),
+            search_methods: self.search_methods.clone(),
+            find_first: false,
+            matcher,
+            logical_matcher: None,
+            predicate: None,
+        }
+    }
+
+    /// internal use, take the predicate out of the query.
+    /// The native `find_first` is disabled, the first native result may be rejected by the predicate.
+    pub(crate) fn take_predicate(&mut self) -> Option<Box<dyn Fn(&MethodData) -> bool + 'a>> {
+        let predicate = self.predicate.take();
+        if predicate.is_some() {
+            self.find_first = false;
+        }
+        predicate
+    }
+
+    /// internal use, whether the matcher has conditions checked after the native search,
+    /// the native `find_first` is disabled then.
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.matcher.as_ref().is_some_and(|m| m.has_post_match())
+    }
+
+    /// internal use, Err for a matcher `try_set_matcher` or `try_set_logical_matcher` rejects,
+    /// the query then matches nothing
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if let Some(matcher) = &self.matcher {
+            matcher.check_post_match()?;
+        }
+        match &self.logical_matcher {
+            Some(logical) => logical.try_for_each_item(&mut |m| m.check_post_match()),
+            None => Ok(()),
+        }
+    }
+
+    /// internal use, false if the search classes are all library classes,
+    /// see `FindClass::has_native_scope`
+    pub(crate) fn has_native_scope(&self) -> bool {
+        self.search_classes
+            .as_ref()
+            .is_none_or(|classes| classes.iter().any(|class| !class.is_library()))
+    }
+
+    /// internal use, look up the resources the matcher loads, Err if the apk does not define one.
+    /// The items of a logical matcher are resolved when their query is forked.
+    pub(crate) fn resolve_resources(&mut self, bridge: &DexkitBridge) -> Result<(), Error> {
+        match &mut self.matcher {
+            Some(matcher) if matcher.has_using_resources() => {
+                matcher.resolve_resources(bridge.resources()?)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// internal use, check the conditions the native search left out
+    pub(crate) fn post_match(&self, data: &MethodData, cache: &PostMatchCache) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|m| m.post_match(data, cache))
+    }
+
+    /// internal use, whether a logical matcher is set
+    pub(crate) fn has_logical_matcher(&self) -> bool {
+        self.logical_matcher.is_some()
+    }
+
+    /// internal use, serialize the query without consuming it
+    pub(crate) fn to_buffer(&self) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::with_capacity(1024);
+        let root = self.inner_build(&mut fbb);
+        fbb.finish(root, None);
+        fbb.finished_data().to_vec()
+    }
+
+    /// internal use, take the classes the query is restricted to
+    pub(crate) fn take_search_classes(&mut self) -> Option<Vec<ClassData<'a>>> {
+        self.search_classes.take()
+    }
+
+    /// internal use, restrict the query to a chunk of classes, see `DataStream`
+    pub(crate) fn set_chunk_classes(&mut self, classes: Vec<ClassData<'a>>) {
+        self.search_classes = Some(classes);
+    }
+
+    /// internal use, whether the query is restricted to single items
+    pub(crate) fn has_item_scope(&self) -> bool {
+        self.search_methods.is_some()
+    }
 }
@@ -0,0 +1,392 @@
+use crate::query::matchers::{ClassMatcher, FieldMatcher, MethodMatcher};
+use crate::query::{FindClass, FindField, FindMethod};
+use std::collections::{HashMap, HashSet};
+
+/// Logical composition of matchers or whole queries.
+///
+/// DexKit only ANDs the conditions of a single matcher, so a composition is executed
+/// as several native queries whose result sets are merged by encoded id.
+/// `Not` is evaluated against the scope of what it negates: the enclosing query for matchers
+/// (packages, search classes ...), the negated query's own scope for queries.
+pub enum Logical<T> {
+    Item(T),
+    AllOf(Vec<Logical<T>>),
+    AnyOf(Vec<Logical<T>>),
+    Not(Box<Logical<T>>),
+}
+
+impl<T> Logical<T> {
+    /// Wrap a single matcher or query.
+    pub fn item(value: T) -> Self {
+        Logical::Item(value)
+    }
+
+    /// Matches if every item matches.
+    pub fn all_of<I, L>(items: I) -> Self
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<T>>,
+    {
+        Logical::AllOf(items.into_iter().map(Into::into).collect())
+    }
+
+    /// Matches if at least one item matches.
+    pub fn any_of<I, L>(items: I) -> Self
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<T>>,
+    {
+        Logical::AnyOf(items.into_iter().map(Into::into).collect())
+    }
+
+    /// Matches if the item does not match.
+    pub fn not<L: Into<Logical<T>>>(item: L) -> Self {
+        Logical::Not(Box::new(item.into()))
+    }
+
+    /// Combine with another item, both must match.
+    pub fn and<L: Into<Logical<T>>>(self, other: L) -> Self {
+        match self {
+            Logical::AllOf(mut items) => {
+                items.push(other.into());
+                Logical::AllOf(items)
+            }
+            this => Logical::AllOf(vec![this, other.into()]),
+        }
+    }
+
+    /// Combine with another item, either may match.
+    pub fn or<L: Into<Logical<T>>>(self, other: L) -> Self {
+        match self {
+            Logical::AnyOf(mut items) => {
+                items.push(other.into());
+                Logical::AnyOf(items)
+            }
+            this => Logical::AnyOf(vec![this, other.into()]),
+        }
+    }
+
+    /// internal use, call `f` on every item, stops at the first error
+    pub(crate) fn try_for_each_item<E>(
+        &self,
+        f: &mut impl FnMut(&T) -> Result<(), E>,
+    ) -> Result<(), E> {
+        match self {
+            Logical::Item(item) => f(item),
+            Logical::AllOf(items) | Logical::AnyOf(items) => {
+                items.iter().try_for_each(|item| item.try_for_each_item(f))
+            }
+            Logical::Not(item) => item.try_for_each_item(f),
+        }
+    }
+
+    /// internal use, evaluate the expression.
+    /// `run(item)` executes one native query, `scope(Some(item))` returns everything the item
+    /// searches in (used by `Not`), `scope(None)` the default scope of an empty `AllOf`.
+    /// Results keep the order in which they were first found.
+    pub(crate) fn evaluate<D: Clone>(
+        self,
+        run: &mut RunFn<'_, T, D>,
+        scope: &mut ScopeFn<'_, T, D>,
+    ) -> Vec<D> {
+        let mut evaluator = Evaluator { run, scope };
+        let set = evaluator.eval(self);
+        set.into_values()
+    }
+}
+
+macro_rules! impl_logical_from {
+    ($($ty:ident),*) => {
+        $(
+            impl From<$ty> for Logical<$ty> {
+                fn from(value: $ty) -> Self {
+                    Logical::Item(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_logical_from!(ClassMatcher, MethodMatcher, FieldMatcher);
+
+impl<'a> From<FindClass<'a>> for Logical<FindClass<'a>> {
+    fn from(value: FindClass<'a>) -> Self {
+        Logical::Item(value)
+    }
+}
+
+impl<'a> From<FindMethod<'a>> for Logical<FindMethod<'a>> {
+    fn from(value: FindMethod<'a>) -> Self {
+        Logical::Item(value)
+    }
+}
+
+impl<'a> From<FindField<'a>> for Logical<FindField<'a>> {
+    fn from(value: FindField<'a>) -> Self {
+        Logical::Item(value)
+    }
+}
+
+/// Executes one item, returns `(encoded id, data)` pairs.
+type RunFn<'r, T, D> = dyn FnMut(T) -> Vec<(i64, D)> + 'r;
+/// Returns the scope of one item, or the default scope for `None`.
+type ScopeFn<'r, T, D> = dyn FnMut(Option<&T>) -> Vec<(i64, D)> + 'r;
+
+/// Ordered result set keyed by encoded id.
+#[derive(Clone)]
+struct IdSet<D> {
+    order: Vec<i64>,
+    items: HashMap<i64, D>,
+}
+
+impl<D: Clone> IdSet<D> {
+    fn from_pairs(pairs: Vec<(i64, D)>) -> Self {
+        let mut set = IdSet {
+            order: Vec::with_capacity(pairs.len()),
+            items: HashMap::with_capacity(pairs.len()),
+        };
+        for (id, data) in pairs {
+            set.insert(id, data);
+        }
+        set
+    }
+
+    fn insert(&mut self, id: i64, data: D) {
+        if !self.items.contains_key(&id) {
+            self.order.push(id);
+            self.items.insert(id, data);
+        }
+    }
+
+    fn union(mut self, other: IdSet<D>) -> Self {
+        let IdSet { order, mut items } = other;
+        for id in order {
+            if let Some(data) = items.remove(&id) {
+                self.insert(id, data);
+            }
+        }
+        self
+    }
+
+    fn intersect(mut self, other: &IdSet<D>) -> Self {
+        self.order.retain(|id| other.items.contains_key(id));
+        let keep: HashSet<i64> = self.order.iter().copied().collect();
+        self.items.retain(|id, _| keep.contains(id));
+        self
+    }
+
+    fn difference(mut self, other: &IdSet<D>) -> Self {
+        self.order.retain(|id| !other.items.contains_key(id));
+        self.items.retain(|id, _| !other.items.contains_key(id));
+        self
+    }
+
+    fn into_values(self) -> Vec<D> {
+        let IdSet { order, mut items } = self;
+        order
+            .into_iter()
+            .filter_map(|id| items.remove(&id))
+            .collect()
+    }
+}
+
+struct Evaluator<'r, T, D> {
+    run: &'r mut RunFn<'r, T, D>,
+    scope: &'r mut ScopeFn<'r, T, D>,
+}
+
+impl<'r, T, D: Clone> Evaluator<'r, T, D> {
+    /// Everything `expr` searches in: the union of the scopes of `AnyOf`,
+    /// the intersection of the scopes of `AllOf`.
+    fn scope_of(&mut self, expr: &Logical<T>) -> IdSet<D> {
+        match expr {
+            Logical::Item(item) => IdSet::from_pairs((self.scope)(Some(item))),
+            Logical::AnyOf(items) => {
+                let mut result = IdSet::from_pairs(Vec::new());
+                for item in items {
+                    let set = self.scope_of(item);
+                    result = result.union(set);
+                }
+                result
+            }
+            Logical::AllOf(items) => self.scope_of_all(items.iter()),
+            Logical::Not(inner) => self.scope_of(inner),
+        }
+    }
+
+    fn scope_of_all<'e, I>(&mut self, items: I) -> IdSet<D>
+    where
+        I: Iterator<Item = &'e Logical<T>>,
+        T: 'e,
+    {
+        let mut result: Option<IdSet<D>> = None;
+        for item in items {
+            let set = self.scope_of(item);
+            result = Some(match result {
+                None => set,
+                Some(acc) => acc.intersect(&set),
+            });
+        }
+        result.unwrap_or_else(|| IdSet::from_pairs((self.scope)(None)))
+    }
+
+    fn eval(&mut self, expr: Logical<T>) -> IdSet<D> {
+        match expr {
+            Logical::Item(item) => IdSet::from_pairs((self.run)(item)),
+            Logical::AnyOf(items) => {
+                let mut result = IdSet::from_pairs(Vec::new());
+                for item in items {
+                    let set = self.eval(item);
+                    result = result.union(set);
+                }
+                result
+            }
+            Logical::AllOf(items) => {
+                // evaluate positive items first, negations are subtracted afterwards,
+                // so `all_of([a, not(b)])` does not need the scope of `b`.
+                let mut positives = Vec::new();
+                let mut negatives = Vec::new();
+                for item in items {
+                    match item {
+                        Logical::Not(inner) => negatives.push(*inner),
+                        other => positives.push(other),
+                    }
+                }
+
+                let mut result: Option<IdSet<D>> = None;
+                for item in positives {
+                    let set = self.eval(item);
+                    result = Some(match result {
+                        None => set,
+                        Some(acc) => acc.intersect(&set),
+                    });
+                    if result.as_ref().is_some_and(|r| r.order.is_empty()) {
+                        return result.unwrap();
+                    }
+                }
+                let mut result = match result {
+                    Some(result) => result,
+                    None => self.scope_of_all(negatives.iter()),
+                };
+                for item in negatives {
+                    if result.order.is_empty() {
+                        break;
+                    }
+                    let set = self.eval(item);
+                    result = result.difference(&set);
+                }
+                result
+            }
+            Logical::Not(inner) => {
+                let scope = self.scope_of(&inner);
+                let set = self.eval(*inner);
+                scope.difference(&set)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake query: the ids it searches in and the ids it matches.
+    struct Query {
+        scope: Vec<i64>,
+        matches: Vec<i64>,
+    }
+
+    fn query(matches: &[i64]) -> Logical<Query> {
+        scoped(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9], matches)
+    }
+
+    fn scoped(scope: &[i64], matches: &[i64]) -> Logical<Query> {
+        Logical::item(Query {
+            scope: scope.to_vec(),
+            matches: matches.to_vec(),
+        })
+    }
+
+    fn evaluate(expr: Logical<Query>) -> Vec<i64> {
+        fn pairs(ids: &[i64]) -> Vec<(i64, i64)> {
+            ids.iter().map(|&id| (id, id)).collect()
+        }
+        expr.evaluate(
+            &mut |query| pairs(&query.matches),
+            &mut |query| match query {
+                Some(query) => pairs(&query.scope),
+                None => pairs(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            },
+        )
+    }
+
+    #[test]
+    fn test_any_of() {
+        let expr = Logical::any_of([query(&[3, 1]), query(&[1, 5]), query(&[])]);
+        assert_eq!(evaluate(expr), vec![3, 1, 5]);
+        assert!(evaluate(Logical::any_of(Vec::<Logical<Query>>::new())).is_empty());
+    }
+
+    #[test]
+    fn test_all_of() {
+        let expr = Logical::all_of([query(&[1, 2, 3, 4]), query(&[4, 3, 9]), query(&[3, 4, 5])]);
+        assert_eq!(evaluate(expr), vec![3, 4]);
+        assert!(evaluate(Logical::all_of([query(&[1]), query(&[2])])).is_empty());
+        assert_eq!(evaluate(query(&[1]).and(query(&[1, 2]))), vec![1]);
+    }
+
+    #[test]
+    fn test_not() {
+        assert_eq!(
+            evaluate(Logical::not(query(&[0, 2, 4, 6, 8]))),
+            vec![1, 3, 5, 7, 9]
+        );
+        assert_eq!(
+            evaluate(Logical::all_of([
+                query(&[1, 2, 3]),
+                Logical::not(query(&[2]))
+            ])),
+            vec![1, 3]
+        );
+        assert_eq!(
+            evaluate(Logical::all_of(Vec::<Logical<Query>>::new())).len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_not_scope() {
+        // `not` only yields ids the negated query searches in
+        let expr = Logical::not(scoped(&[1, 2, 3], &[2]));
+        assert_eq!(evaluate(expr), vec![1, 3]);
+
+        let expr = Logical::all_of([
+            Logical::not(scoped(&[1, 2, 3, 4], &[2])),
+            Logical::not(scoped(&[3, 4, 5], &[])),
+        ]);
+        assert_eq!(evaluate(expr), vec![3, 4]);
+
+        let expr = Logical::not(Logical::any_of([scoped(&[1, 2], &[1]), scoped(&[5], &[])]));
+        assert_eq!(evaluate(expr), vec![2, 5]);
+    }
+
+    #[test]
+    fn test_nested() {
+        // (a or b) and not (c or d)
+        let expr = query(&[1, 2, 3])
+            .or(query(&[4, 5]))
+            .and(Logical::not(query(&[2]).or(query(&[5]))));
+        assert_eq!(evaluate(expr), vec![1, 3, 4]);
+
+        // not (not a) == a
+        let expr = Logical::not(Logical::not(query(&[7, 3])));
+        assert_eq!(evaluate(expr), vec![3, 7]);
+
+        // any_of(all_of(a, b), not c)
+        let expr = Logical::any_of([
+            Logical::all_of([query(&[1, 2]), query(&[2, 3])]),
+            Logical::not(query(&[0, 1, 2, 3, 4, 5, 6, 7])),
+        ]);
+        assert_eq!(evaluate(expr), vec![2, 8, 9]);
+    }
+}
@@ -55,6 +55,15 @@ impl<'a> BaseQuery<'a, WIPOffset<FBAnnotationElementMatcher<'a>>> for Annotation
 }
 
 impl AnnotationElementMatcher {
+    /// internal use, whether the name or value needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        StringMatcher::has_post_match(&self.name_matcher)
+            || self
+                .value_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+    }
+
     pub fn create() -> Self {
         Self::default()
     }
@@ -101,6 +110,7 @@ impl AnnotationElementMatcher {
         self
     }
 
+    /// `StringMatchType::Regex` is not supported for annotation strings, see its doc
     pub fn string_value(mut self, value: StringMatcher) -> Self {
         self.value_matcher = Some(AnnotationEncodeValueMatcher::create_string(value));
         self
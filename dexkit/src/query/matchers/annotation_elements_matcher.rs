@@ -48,6 +48,14 @@ impl<'a> BaseQuery<'a, WIPOffset<FBAnnotationElementsMatcher<'a>>> for Annotatio
 }
 
 impl AnnotationElementsMatcher {
+    /// internal use, whether one of the elements needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.elements_matcher
+            .iter()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
+
     pub fn create() -> Self {
         Self::default()
     }
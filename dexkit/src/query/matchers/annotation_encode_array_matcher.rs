@@ -36,6 +36,13 @@ impl IAnnotationEncodeValue for AnnotationEncodeArrayMatcher {
     ) -> WIPOffset<UnionWIPOffset> {
         self.inner_build(fbb).as_union_value()
     }
+
+    fn has_post_match_value(&self) -> bool {
+        self.encode_values_matcher
+            .iter()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
 }
 
 impl<'a> BaseQuery<'a, WIPOffset<FBAnnotationEncodeArrayMatcher<'a>>>
@@ -4,6 +4,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
 };
 use crate::query::base::BaseQuery;
 use crate::query::base::IAnnotationEncodeValue;
+use crate::query::base::IPostMatch;
 use crate::query::enums::{RetentionPolicyType, StringMatchType, TargetElementType};
 use crate::query::matchers::ClassMatcher;
 use crate::query::matchers::base::StringMatcher;
@@ -38,6 +39,10 @@ impl IAnnotationEncodeValue for AnnotationMatcher {
     ) -> WIPOffset<flatbuffers::UnionWIPOffset> {
         self.inner_build(fbb).as_union_value()
     }
+
+    fn has_post_match_value(&self) -> bool {
+        self.has_post_match()
+    }
 }
 
 impl<'a> BaseQuery<'a, WIPOffset<FBAnnotationMatcher<'a>>> for AnnotationMatcher {
@@ -71,6 +76,18 @@ impl<'a> BaseQuery<'a, WIPOffset<FBAnnotationMatcher<'a>>> for AnnotationMatcher
 }
 
 impl AnnotationMatcher {
+    /// internal use, whether a nested matcher needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.type_matcher
+            .as_ref()
+            .is_some_and(|m| m.has_post_match())
+            || StringMatcher::has_post_match_any(&self.using_strings_matcher)
+            || self
+                .elements_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+    }
+
     pub(crate) fn create() -> Self {
         Self::default()
     }
@@ -86,6 +103,7 @@ impl AnnotationMatcher {
         self
     }
 
+    /// `StringMatchType::Regex` is not supported for annotation strings, see its doc
     pub fn set_using_strings_matcher(mut self, matcher: Vec<StringMatcher>) -> Self {
         self.using_strings_matcher = Some(matcher);
         self
@@ -48,6 +48,14 @@ impl<'a> BaseQuery<'a, WIPOffset<FBAnnotationsMatcher<'a>>> for AnnotationsMatch
 }
 
 impl AnnotationsMatcher {
+    /// internal use, whether one of the items needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.annotations_matcher
+            .iter()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
+
     pub fn create() -> Self {
         Self::default()
     }
@@ -46,6 +46,13 @@ impl From<&AnnotationEncodeValueMatcher> for FBAnnotationEncodeValueMatcher {
 }
 
 impl AnnotationEncodeValueMatcher {
+    /// internal use, whether the value needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.value
+            .as_ref()
+            .is_some_and(|v| v.has_post_match_value())
+    }
+
     // create
     pub fn create() -> Self {
         Self::default()
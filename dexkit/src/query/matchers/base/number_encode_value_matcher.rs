@@ -1,6 +1,10 @@
 use crate::gen_flatbuffers::dexkit::schema::Number as FBNumber;
 use crate::query::base::{BaseQuery, INumberEncodeValue};
 use crate::query::enums::NumberEncodeValueType;
+use crate::query::matchers::{
+    EncodeValueByte, EncodeValueDouble, EncodeValueFloat, EncodeValueInt, EncodeValueLong,
+    EncodeValueShort,
+};
 use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
 
 pub struct NumberEncodeValueMatcher {
@@ -17,6 +21,43 @@ impl Default for NumberEncodeValueMatcher {
     }
 }
 
+impl NumberEncodeValueMatcher {
+    pub fn create_byte(value: i8) -> Self {
+        Self::create_value(EncodeValueByte(value), NumberEncodeValueType::ByteValue)
+    }
+
+    pub fn create_short(value: i16) -> Self {
+        Self::create_value(EncodeValueShort(value), NumberEncodeValueType::ShortValue)
+    }
+
+    pub fn create_int(value: i32) -> Self {
+        Self::create_value(EncodeValueInt(value), NumberEncodeValueType::IntValue)
+    }
+
+    pub fn create_long(value: i64) -> Self {
+        Self::create_value(EncodeValueLong(value), NumberEncodeValueType::LongValue)
+    }
+
+    pub fn create_float(value: f32) -> Self {
+        Self::create_value(EncodeValueFloat(value), NumberEncodeValueType::FloatValue)
+    }
+
+    pub fn create_double(value: f64) -> Self {
+        Self::create_value(EncodeValueDouble(value), NumberEncodeValueType::DoubleValue)
+    }
+
+    /// internal use
+    fn create_value<V: INumberEncodeValue + 'static>(
+        value: V,
+        value_type: NumberEncodeValueType,
+    ) -> Self {
+        Self {
+            value: Some(Box::new(value)),
+            value_type: Some(value_type),
+        }
+    }
+}
+
 // marker..
 impl<'a> BaseQuery<'a, Option<WIPOffset<UnionWIPOffset>>> for NumberEncodeValueMatcher {
     fn inner_build(&self, fbb: &mut FlatBufferBuilder<'a>) -> Option<WIPOffset<UnionWIPOffset>> {
@@ -1,3 +1,4 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     StringMatchType as FBSchemaStringMatchType, StringMatcher as FBStringMatcher,
     StringMatcherArgs as FBStringMatcherArgs,
@@ -5,11 +6,15 @@ use crate::gen_flatbuffers::dexkit::schema::{
 use crate::query::base::{BaseQuery, IAnnotationEncodeValue};
 use crate::query::enums::StringMatchType;
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
+use regex::{Regex, RegexBuilder};
+use std::cell::OnceCell;
 
 pub struct StringMatcher {
     value: Option<String>,
     match_type: StringMatchType,
     ignore_case: bool,
+    // Lazy compiled `StringMatchType::Regex` pattern
+    regex: OnceCell<Option<Regex>>,
 }
 
 impl Default for StringMatcher {
@@ -18,6 +23,7 @@ impl Default for StringMatcher {
             value: None,
             match_type: StringMatchType::Contains,
             ignore_case: false,
+            regex: OnceCell::new(),
         }
     }
 }
@@ -29,6 +35,10 @@ impl IAnnotationEncodeValue for StringMatcher {
     ) -> WIPOffset<flatbuffers::UnionWIPOffset> {
         self.inner_build(fbb).as_union_value()
     }
+
+    fn has_post_match_value(&self) -> bool {
+        !self.is_native()
+    }
 }
 
 impl<'a> BaseQuery<'a, WIPOffset<FBStringMatcher<'a>>> for StringMatcher {
@@ -58,6 +68,7 @@ impl StringMatcher {
             value: Some(value.into()),
             match_type: StringMatchType::Contains,
             ignore_case: false,
+            regex: OnceCell::new(),
         }
     }
 
@@ -66,15 +77,31 @@ impl StringMatcher {
             value: Some(value.into()),
             match_type: StringMatchType::Equals,
             ignore_case: false,
+            regex: OnceCell::new(),
         }
     }
 
+    /// full regular expression, see `StringMatchType::Regex`
+    pub fn create_regex<S: Into<String>>(pattern: S) -> Result<Self, Error> {
+        let pattern = pattern.into();
+        Regex::new(&pattern).map_err(|e| Error::RegexError(e.to_string()))?;
+        Ok(Self {
+            value: Some(pattern),
+            match_type: StringMatchType::Regex,
+            ignore_case: false,
+            regex: OnceCell::new(),
+        })
+    }
+
     // base
     pub fn set_value<S: Into<String>>(mut self, value: S) -> Self {
         self.value = Some(value.into());
+        self.regex = OnceCell::new();
         self
     }
 
+    /// an invalid `StringMatchType::Regex` pattern is reported by `FindClass::try_set_matcher`
+    /// and the other `try_*` setters, with the plain setters the query finds nothing
     pub fn set_match_type(mut self, match_type: StringMatchType) -> Self {
         self.match_type = match_type;
         self
@@ -82,6 +109,88 @@ impl StringMatcher {
 
     pub fn set_ignore_case(mut self, ignore_case: bool) -> Self {
         self.ignore_case = ignore_case;
+        self.regex = OnceCell::new();
         self
     }
+
+    /// internal use, whether the native side can evaluate this matcher
+    pub(crate) fn is_native(&self) -> bool {
+        self.match_type != StringMatchType::Regex
+    }
+
+    fn build_regex(&self) -> Result<Regex, Error> {
+        RegexBuilder::new(self.value.as_deref().unwrap_or_default())
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|e| Error::RegexError(e.to_string()))
+    }
+
+    /// internal use, evaluate a `StringMatchType::Regex` matcher,
+    /// the pattern was validated by `check` before the search
+    pub(crate) fn post_match_str(&self, value: &str) -> bool {
+        let regex = self.regex.get_or_init(|| self.build_regex().ok());
+        regex.as_ref().is_some_and(|regex| regex.is_match(value))
+    }
+
+    /// internal use, Err if the `StringMatchType::Regex` pattern of an optional matcher does not compile
+    pub(crate) fn check(matcher: &Option<StringMatcher>) -> Result<(), Error> {
+        match matcher.as_ref().filter(|m| !m.is_native()) {
+            Some(matcher) => matcher.build_regex().map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// internal use, `check` every matcher of a list
+    pub(crate) fn check_all(matchers: &Option<Vec<StringMatcher>>) -> Result<(), Error> {
+        matchers
+            .iter()
+            .flatten()
+            .filter(|m| !m.is_native())
+            .try_for_each(|m| m.build_regex().map(|_| ()))
+    }
+
+    /// internal use, native part of an optional matcher
+    pub(crate) fn native(matcher: &Option<StringMatcher>) -> Option<&StringMatcher> {
+        matcher.as_ref().filter(|m| m.is_native())
+    }
+
+    /// internal use, whether an optional matcher needs post matching
+    pub(crate) fn has_post_match(matcher: &Option<StringMatcher>) -> bool {
+        matcher.as_ref().is_some_and(|m| !m.is_native())
+    }
+
+    /// internal use, post match an optional matcher, `value` is only loaded if needed
+    pub(crate) fn post_match(
+        matcher: &Option<StringMatcher>,
+        value: impl FnOnce() -> Option<String>,
+    ) -> bool {
+        match matcher.as_ref().filter(|m| !m.is_native()) {
+            Some(matcher) => value().is_some_and(|v| matcher.post_match_str(&v)),
+            None => true,
+        }
+    }
+
+    /// internal use, whether a matcher list needs post matching
+    pub(crate) fn has_post_match_any(matchers: &Option<Vec<StringMatcher>>) -> bool {
+        matchers
+            .as_ref()
+            .is_some_and(|ms| ms.iter().any(|m| !m.is_native()))
+    }
+
+    /// internal use, every regex matcher of the list must match one of the values,
+    /// `values` is only loaded if needed
+    pub(crate) fn post_match_any(
+        matchers: &Option<Vec<StringMatcher>>,
+        values: impl FnOnce() -> Vec<String>,
+    ) -> bool {
+        if !Self::has_post_match_any(matchers) {
+            return true;
+        }
+        let values = values();
+        matchers
+            .iter()
+            .flatten()
+            .filter(|m| !m.is_native())
+            .all(|m| values.iter().any(|v| m.post_match_str(v)))
+    }
 }
@@ -1,8 +1,11 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     ClassMatcher as FBClassMatcher, ClassMatcherArgs as FBClassMatcherArgs,
 };
+use crate::query::Logical;
 use crate::query::base::BaseQuery;
 use crate::query::base::IAnnotationEncodeValue;
+use crate::query::base::{IPostMatch, PostMatchCache, reject_post_match};
 use crate::query::enums::{MatchType, StringMatchType};
 use crate::query::matchers::AnnotationMatcher;
 use crate::query::matchers::AnnotationsMatcher;
@@ -13,6 +16,8 @@ use crate::query::matchers::MethodsMatcher;
 use crate::query::matchers::base::AccessFlagsMatcher;
 use crate::query::matchers::base::StringMatcher;
 use crate::query::matchers::interfaces_matcher::InterfacesMatcher;
+use crate::result::ClassData;
+use crate::result::base::BaseData;
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
 
 pub struct ClassMatcher {
@@ -25,6 +30,11 @@ pub struct ClassMatcher {
     fields_matcher: Option<FieldsMatcher>,
     methods_matcher: Option<MethodsMatcher>,
     using_strings_matcher: Option<Vec<StringMatcher>>,
+    // post match only, from the dalvik.annotation system annotations
+    outer_class_matcher: Option<Box<ClassMatcher>>,
+    anonymous: Option<bool>,
+    // post match only, recovered from the enum <clinit>
+    enum_constant_names: Option<Vec<String>>,
 }
 
 impl Default for ClassMatcher {
@@ -39,6 +49,9 @@ impl Default for ClassMatcher {
             fields_matcher: None,
             methods_matcher: None,
             using_strings_matcher: None,
+            outer_class_matcher: None,
+            anonymous: None,
+            enum_constant_names: None,
         }
     }
 }
@@ -50,12 +63,17 @@ impl IAnnotationEncodeValue for ClassMatcher {
     ) -> WIPOffset<flatbuffers::UnionWIPOffset> {
         self.inner_build(fbb).as_union_value()
     }
+
+    fn has_post_match_value(&self) -> bool {
+        self.has_post_match()
+    }
 }
 
 impl<'a> BaseQuery<'a, WIPOffset<FBClassMatcher<'a>>> for ClassMatcher {
     fn inner_build(&self, fbb: &mut FlatBufferBuilder<'a>) -> WIPOffset<FBClassMatcher<'a>> {
-        let smali_source = self.source_matcher.as_ref().map(|m| m.inner_build(fbb));
-        let class_name = self.class_name_matcher.as_ref().map(|m| m.inner_build(fbb));
+        let smali_source = StringMatcher::native(&self.source_matcher).map(|m| m.inner_build(fbb));
+        let class_name =
+            StringMatcher::native(&self.class_name_matcher).map(|m| m.inner_build(fbb));
         let access_flags = self.modifiers_matcher.as_ref().map(|m| m.inner_build(fbb));
         let super_class = self
             .super_class_name_matcher
@@ -69,7 +87,11 @@ impl<'a> BaseQuery<'a, WIPOffset<FBClassMatcher<'a>>> for ClassMatcher {
         let fields = self.fields_matcher.as_ref().map(|m| m.inner_build(fbb));
         let methods = self.methods_matcher.as_ref().map(|m| m.inner_build(fbb));
         let using_strings = self.using_strings_matcher.as_ref().map(|v| {
-            let vec: Vec<_> = v.iter().map(|m| m.inner_build(fbb)).collect();
+            let vec: Vec<_> = v
+                .iter()
+                .filter(|m| m.is_native())
+                .map(|m| m.inner_build(fbb))
+                .collect();
             fbb.create_vector(&vec)
         });
 
@@ -90,6 +112,111 @@ impl<'a> BaseQuery<'a, WIPOffset<FBClassMatcher<'a>>> for ClassMatcher {
     }
 }
 
+impl<'a> IPostMatch<ClassData<'a>> for ClassMatcher {
+    fn has_post_match(&self) -> bool {
+        StringMatcher::has_post_match(&self.source_matcher)
+            || StringMatcher::has_post_match(&self.class_name_matcher)
+            || StringMatcher::has_post_match_any(&self.using_strings_matcher)
+            || self
+                .super_class_name_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .interfaces_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .fields_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self.outer_class_matcher.is_some()
+            || self.anonymous.is_some()
+            || self.enum_constant_names.is_some()
+    }
+
+    fn post_match(&self, class: &ClassData<'a>, cache: &PostMatchCache) -> bool {
+        StringMatcher::post_match(&self.source_matcher, || Some(class.source_file()))
+            && StringMatcher::post_match(&self.class_name_matcher, || class.name())
+            && StringMatcher::post_match_any(&self.using_strings_matcher, || {
+                class
+                    .methods()
+                    .iter()
+                    .flat_map(|method| method.using_strings())
+                    .collect()
+            })
+            && self
+                .super_class_name_matcher
+                .as_ref()
+                .filter(|m| m.has_post_match())
+                .is_none_or(|m| {
+                    class
+                        .supper_class()
+                        .is_some_and(|c| m.post_match(&c, cache))
+                })
+            && self
+                .anonymous
+                .is_none_or(|anonymous| class.is_anonymous() == anonymous)
+            && self.outer_class_matcher.as_ref().is_none_or(|m| {
+                class.outer_class().is_some_and(|outer| {
+                    cache.is_outer_class(m, outer.get_mine_encode_id(), || {
+                        class.bridge().matching_class_ids(m, cache)
+                    })
+                })
+            })
+            && self.enum_constant_names.as_ref().is_none_or(|names| {
+                let constants = class.enum_constants();
+                class.is_enum()
+                    && names
+                        .iter()
+                        .all(|name| constants.iter().any(|c| c.name() == name))
+            })
+    }
+
+    fn check_post_match(&self) -> Result<(), Error> {
+        StringMatcher::check(&self.source_matcher)?;
+        StringMatcher::check(&self.class_name_matcher)?;
+        StringMatcher::check_all(&self.using_strings_matcher)?;
+        for matcher in [&self.super_class_name_matcher, &self.outer_class_matcher]
+            .into_iter()
+            .flatten()
+        {
+            matcher.check_post_match()?;
+        }
+        reject_post_match(
+            "InterfacesMatcher",
+            self.interfaces_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "AnnotationsMatcher",
+            self.annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "FieldsMatcher",
+            self.fields_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "MethodsMatcher",
+            self.methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )
+    }
+}
+
 impl ClassMatcher {
     pub fn create() -> Self {
         ClassMatcher::default()
@@ -141,6 +268,36 @@ impl ClassMatcher {
         self
     }
 
+    /// Only classes declared in a class matching `outer_class_matcher`: member, local and
+    /// anonymous classes, see `ClassData::outer_class`.
+    /// Checked after the native search, the outer classes are searched once per query.
+    pub fn set_outer_class_matcher(mut self, outer_class_matcher: ClassMatcher) -> Self {
+        self.outer_class_matcher = Some(Box::new(outer_class_matcher));
+        self
+    }
+
+    /// Only anonymous classes, or only the others, see `ClassData::is_anonymous`.
+    /// Checked after the native search
+    pub fn set_anonymous(mut self, anonymous: bool) -> Self {
+        self.anonymous = Some(anonymous);
+        self
+    }
+
+    /// Only enum classes declaring a constant for each of `names`, e.g. `["IDLE", "RUNNING"]`,
+    /// by the names passed to the enum constructor, see `ClassData::enum_constants`.
+    /// Checked after the native search
+    pub fn set_enum_constant_names<S: Into<String>>(mut self, names: Vec<S>) -> Self {
+        self.enum_constant_names = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn add_enum_constant_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.enum_constant_names
+            .get_or_insert_with(Vec::new)
+            .push(name.into());
+        self
+    }
+
     // extend source
     pub fn set_source_str<S: Into<String>>(self, source: S) -> Self {
         self.set_source_matcher(StringMatcher::create_string_str(source))
@@ -179,6 +336,12 @@ impl ClassMatcher {
         self.set_super_class_name_matcher(matcher)
     }
 
+    // extend outer_class
+    pub fn set_outer_class_name_str<S: Into<String>>(self, outer_class_name: S) -> Self {
+        let matcher = ClassMatcher::create().set_class_name_str(outer_class_name);
+        self.set_outer_class_matcher(matcher)
+    }
+
     // extend interfaces
     pub fn set_interfaces(mut self, interfaces: Vec<ClassMatcher>) -> Self {
         self.interfaces_matcher =
@@ -538,4 +701,103 @@ impl ClassMatcher {
                 .set_match_type(StringMatchType::Contains),
         )
     }
+
+    // logical, see `Logical`
+    pub fn any_of<I, L>(matchers: I) -> Logical<ClassMatcher>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<ClassMatcher>>,
+    {
+        Logical::any_of(matchers)
+    }
+
+    pub fn all_of<I, L>(matchers: I) -> Logical<ClassMatcher>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<ClassMatcher>>,
+    {
+        Logical::all_of(matchers)
+    }
+
+    pub fn not<L: Into<Logical<ClassMatcher>>>(matcher: L) -> Logical<ClassMatcher> {
+        Logical::not(matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexkitBridge;
+
+    fn regex(pattern: &str) -> StringMatcher {
+        StringMatcher::create()
+            .set_value(pattern)
+            .set_match_type(StringMatchType::Regex)
+    }
+
+    #[test]
+    fn test_check_post_match() {
+        let matcher = ClassMatcher::create()
+            .set_class_name_matcher(regex(r"^com\..*Activity$"))
+            .set_super_class_name_matcher(
+                ClassMatcher::create().set_class_name_matcher(regex("Base")),
+            );
+        assert!(matcher.check_post_match().is_ok());
+    }
+
+    #[test]
+    fn test_post_match_class_name() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let cache = PostMatchCache::default();
+        let activity = ClassData::for_test(&bridge, 0, 1, "Lcom/example/MainActivity;");
+        let helper = ClassData::for_test(&bridge, 0, 2, "Lcom/example/ActivityHelper;");
+
+        // the regex sees the class name, e.g. "com.example.MainActivity", not the descriptor
+        let matcher =
+            ClassMatcher::create().set_class_name_matcher(regex(r"^com\.example\.\w+Activity$"));
+        assert!(matcher.has_post_match());
+        assert!(matcher.post_match(&activity, &cache));
+        assert!(!matcher.post_match(&helper, &cache));
+
+        let matcher = ClassMatcher::create().set_class_name_matcher(regex("^Lcom/.*Activity;$"));
+        assert!(!matcher.post_match(&activity, &cache));
+
+        let matcher = ClassMatcher::create()
+            .set_class_name_matcher(regex("main|helper").set_ignore_case(true));
+        assert!(matcher.post_match(&activity, &cache));
+        assert!(matcher.post_match(&helper, &cache));
+    }
+
+    #[test]
+    fn test_check_post_match_invalid_regex() {
+        let matcher = ClassMatcher::create().set_super_class_name_matcher(
+            ClassMatcher::create().set_class_name_matcher(regex("(")),
+        );
+        assert!(matches!(
+            matcher.check_post_match(),
+            Err(Error::RegexError(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_post_match_in_list() {
+        let matcher = ClassMatcher::create().set_methods_matcher(
+            MethodsMatcher::create()
+                .add_method_matcher(MethodMatcher::create().set_name_matcher(regex("^on"))),
+        );
+        assert!(matches!(
+            matcher.check_post_match(),
+            Err(Error::MatcherError(_))
+        ));
+
+        let matcher = ClassMatcher::create().add_interface(
+            ClassMatcher::create().set_super_class_name_matcher(
+                ClassMatcher::create().set_class_name_matcher(regex("Base")),
+            ),
+        );
+        assert!(matches!(
+            matcher.check_post_match(),
+            Err(Error::MatcherError(_))
+        ));
+    }
 }
@@ -19,6 +19,7 @@ pub trait EncodeValue {
     fn value(&self) -> &Self::Value;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueByte(pub i8);
 impl EncodeValue for EncodeValueByte {
     type Value = i8;
@@ -41,6 +42,7 @@ impl INumberEncodeValue for EncodeValueByte {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueShort(pub i16);
 impl EncodeValue for EncodeValueShort {
     type Value = i16;
@@ -63,6 +65,7 @@ impl INumberEncodeValue for EncodeValueShort {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueChar(pub char);
 impl EncodeValue for EncodeValueChar {
     type Value = char;
@@ -85,6 +88,7 @@ impl INumberEncodeValue for EncodeValueChar {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueInt(pub i32);
 impl EncodeValue for EncodeValueInt {
     type Value = i32;
@@ -107,6 +111,7 @@ impl INumberEncodeValue for EncodeValueInt {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueLong(pub i64);
 impl EncodeValue for EncodeValueLong {
     type Value = i64;
@@ -129,6 +134,7 @@ impl INumberEncodeValue for EncodeValueLong {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueFloat(pub f32);
 impl EncodeValue for EncodeValueFloat {
     type Value = f32;
@@ -151,6 +157,7 @@ impl INumberEncodeValue for EncodeValueFloat {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueDouble(pub f64);
 impl EncodeValue for EncodeValueDouble {
     type Value = f64;
@@ -173,6 +180,7 @@ impl INumberEncodeValue for EncodeValueDouble {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct EncodeValueString(pub String);
 impl EncodeValue for EncodeValueString {
     type Value = String;
@@ -181,6 +189,7 @@ impl EncodeValue for EncodeValueString {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueNull;
 impl EncodeValue for EncodeValueNull {
     type Value = ();
@@ -198,6 +207,7 @@ impl IAnnotationEncodeValue for EncodeValueNull {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct EncodeValueBoolean(pub bool);
 impl EncodeValue for EncodeValueBoolean {
     type Value = bool;
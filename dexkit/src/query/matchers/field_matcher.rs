@@ -1,12 +1,17 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     FieldMatcher as FBFieldMatcher, FieldMatcherArgs as FBFieldMatcherArgs,
 };
-use crate::query::base::{BaseQuery, IAnnotationEncodeValue};
+use crate::query::Logical;
+use crate::query::base::{
+    BaseQuery, IAnnotationEncodeValue, IPostMatch, IStaticValue, PostMatchCache, reject_post_match,
+};
 use crate::query::matchers::MethodsMatcher;
 use crate::query::matchers::base::AccessFlagsMatcher;
 use crate::query::matchers::base::StringMatcher;
 use crate::query::matchers::{AnnotationMatcher, AnnotationsMatcher};
 use crate::query::matchers::{ClassMatcher, MethodMatcher};
+use crate::result::FieldData;
 use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
 
 pub struct FieldMatcher {
@@ -17,6 +22,8 @@ pub struct FieldMatcher {
     annotations_matcher: Option<AnnotationsMatcher>,
     get_methods_matcher: Option<MethodsMatcher>,
     put_methods_matcher: Option<MethodsMatcher>,
+    // post match only, from the static_values of the declaring class
+    static_value_matcher: Option<Box<dyn IStaticValue>>,
 }
 
 impl Default for FieldMatcher {
@@ -29,6 +36,7 @@ impl Default for FieldMatcher {
             annotations_matcher: None,
             get_methods_matcher: None,
             put_methods_matcher: None,
+            static_value_matcher: None,
         }
     }
 }
@@ -40,11 +48,15 @@ impl IAnnotationEncodeValue for FieldMatcher {
     ) -> WIPOffset<UnionWIPOffset> {
         self.inner_build(fbb).as_union_value()
     }
+
+    fn has_post_match_value(&self) -> bool {
+        self.has_post_match()
+    }
 }
 
 impl<'a> BaseQuery<'a, WIPOffset<FBFieldMatcher<'a>>> for FieldMatcher {
     fn inner_build(&self, fbb: &mut FlatBufferBuilder<'a>) -> WIPOffset<FBFieldMatcher<'a>> {
-        let field_name = self.name_matcher.as_ref().map(|m| m.inner_build(fbb));
+        let field_name = StringMatcher::native(&self.name_matcher).map(|m| m.inner_build(fbb));
         let access_flags = self.modifiers_matcher.as_ref().map(|m| m.inner_build(fbb));
         let declaring_class = self.class_matcher.as_ref().map(|m| m.inner_build(fbb));
         let type_class = self.type_matcher.as_ref().map(|m| m.inner_build(fbb));
@@ -76,6 +88,84 @@ impl<'a> BaseQuery<'a, WIPOffset<FBFieldMatcher<'a>>> for FieldMatcher {
     }
 }
 
+impl<'a> IPostMatch<FieldData<'a>> for FieldMatcher {
+    fn has_post_match(&self) -> bool {
+        StringMatcher::has_post_match(&self.name_matcher)
+            || self
+                .class_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .type_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .get_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .put_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self.static_value_matcher.is_some()
+    }
+
+    fn post_match(&self, field: &FieldData<'a>, cache: &PostMatchCache) -> bool {
+        StringMatcher::post_match(&self.name_matcher, || field.name())
+            && self
+                .class_matcher
+                .as_ref()
+                .filter(|m| m.has_post_match())
+                .is_none_or(|m| {
+                    field
+                        .declared_class()
+                        .is_some_and(|c| m.post_match(&c, cache))
+                })
+            && self
+                .type_matcher
+                .as_ref()
+                .filter(|m| m.has_post_match())
+                .is_none_or(|m| field.type_class().is_some_and(|c| m.post_match(&c, cache)))
+            && self.static_value_matcher.as_ref().is_none_or(|m| {
+                field
+                    .static_value()
+                    .is_some_and(|value| m.match_static_value(value.value()))
+            })
+    }
+
+    fn check_post_match(&self) -> Result<(), Error> {
+        StringMatcher::check(&self.name_matcher)?;
+        for matcher in [&self.class_matcher, &self.type_matcher]
+            .into_iter()
+            .flatten()
+        {
+            matcher.check_post_match()?;
+        }
+        reject_post_match(
+            "AnnotationsMatcher",
+            self.annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "get MethodsMatcher",
+            self.get_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "put MethodsMatcher",
+            self.put_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )
+    }
+}
+
 impl FieldMatcher {
     pub fn create() -> Self {
         Self::default()
@@ -117,6 +207,16 @@ impl FieldMatcher {
         self
     }
 
+    /// Only static fields initialized to `value` by the static_values of their class,
+    /// see `FieldData::static_value`, e.g. `EncodeValueString("https://api.example.com".into())`.
+    /// Fields left to the default value match `EncodeValueInt(0)`, `EncodeValueBoolean(false)`
+    /// or `EncodeValueNull`.
+    /// Checked after the native search
+    pub fn set_static_value<V: IStaticValue + 'static>(mut self, value: V) -> Self {
+        self.static_value_matcher = Some(Box::new(value));
+        self
+    }
+
     // extend name_matcher
     pub fn set_field_name_str<S: Into<String>>(self, name: S) -> Self {
         self.set_field_name_matcher(StringMatcher::create_string_str(name))
@@ -265,4 +365,25 @@ impl FieldMatcher {
         }
         self
     }
+
+    // logical, see `Logical`
+    pub fn any_of<I, L>(matchers: I) -> Logical<FieldMatcher>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FieldMatcher>>,
+    {
+        Logical::any_of(matchers)
+    }
+
+    pub fn all_of<I, L>(matchers: I) -> Logical<FieldMatcher>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<FieldMatcher>>,
+    {
+        Logical::all_of(matchers)
+    }
+
+    pub fn not<L: Into<Logical<FieldMatcher>>>(matcher: L) -> Logical<FieldMatcher> {
+        Logical::not(matcher)
+    }
 }
@@ -2,7 +2,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
     FieldsMatcher as FBFieldsMatcher, FieldsMatcherArgs as FBFieldsMatcherArgs,
     MatchType as FBMatchType,
 };
-use crate::query::base::BaseQuery;
+use crate::query::base::{BaseQuery, IPostMatch};
 use crate::query::enums::MatchType;
 use crate::query::matchers::FieldMatcher;
 use crate::query::matchers::base::IntRange;
@@ -47,6 +47,14 @@ impl<'a> BaseQuery<'a, WIPOffset<FBFieldsMatcher<'a>>> for FieldsMatcher {
 }
 
 impl FieldsMatcher {
+    /// internal use, whether one of the items needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.fields_matcher
+            .iter()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
+
     pub fn create() -> Self {
         Self::default()
     }
@@ -2,7 +2,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
     InterfacesMatcher as FBInterfacesMatcher, InterfacesMatcherArgs as FBInterfacesMatcherArgs,
     MatchType as FBMatchType,
 };
-use crate::query::base::BaseQuery;
+use crate::query::base::{BaseQuery, IPostMatch};
 use crate::query::enums::MatchType;
 use crate::query::matchers::ClassMatcher;
 use crate::query::matchers::base::IntRange;
@@ -46,6 +46,14 @@ impl<'a> BaseQuery<'a, WIPOffset<FBInterfacesMatcher<'a>>> for InterfacesMatcher
 }
 
 impl InterfacesMatcher {
+    /// internal use, whether one of the items needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.interface_matcher
+            .iter()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
+
     pub fn create() -> Self {
         Self::default()
     }
@@ -1,8 +1,13 @@
+use crate::apk::ResourceTable;
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     MethodMatcher as FBMethodMatcher, MethodMatcherArgs as FBMethodMatcherArgs, Number as FBNumber,
     NumberWrapper as FBNumberWrapper, NumberWrapperArgs as FBNumberWrapperArgs,
 };
-use crate::query::base::{BaseQuery, IAnnotationEncodeValue};
+use crate::query::Logical;
+use crate::query::base::{
+    BaseQuery, IAnnotationEncodeValue, IPostMatch, PostMatchCache, reject_post_match,
+};
 use crate::query::matchers::AnnotationsMatcher;
 use crate::query::matchers::ClassMatcher;
 use crate::query::matchers::MethodsMatcher;
@@ -12,6 +17,9 @@ use crate::query::matchers::base::AccessFlagsMatcher;
 use crate::query::matchers::base::NumberEncodeValueMatcher;
 use crate::query::matchers::base::OpCodesMatcher;
 use crate::query::matchers::base::StringMatcher;
+use crate::result::base::BaseData;
+use crate::result::{MethodData, UsingNumber};
+use crate::uitls::DexSignature;
 use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
 
 pub struct MethodMatcher {
@@ -28,6 +36,16 @@ pub struct MethodMatcher {
     using_numbers_matcher: Option<Vec<NumberEncodeValueMatcher>>,
     invoke_methods_matcher: Option<MethodsMatcher>,
     caller_methods_matcher: Option<MethodsMatcher>,
+    // "type/name" of resources, compared by bit pattern after the native search
+    using_resources: Option<Vec<String>>,
+    // ids of `using_resources`, resolved by the bridge before the native search
+    using_resource_ids: Option<Vec<u32>>,
+    // post match only, from the debug_info_item
+    line_number: Option<u32>,
+    // post match only, exception type descriptors
+    catch_types: Option<Vec<String>>,
+    throws_types: Option<Vec<String>>,
+    thrown_types: Option<Vec<String>>,
 }
 
 impl Default for MethodMatcher {
@@ -46,6 +64,12 @@ impl Default for MethodMatcher {
             using_numbers_matcher: None,
             invoke_methods_matcher: None,
             caller_methods_matcher: None,
+            using_resources: None,
+            using_resource_ids: None,
+            line_number: None,
+            catch_types: None,
+            throws_types: None,
+            thrown_types: None,
         }
     }
 }
@@ -57,11 +81,15 @@ impl IAnnotationEncodeValue for MethodMatcher {
     ) -> WIPOffset<UnionWIPOffset> {
         self.inner_build(fbb).as_union_value()
     }
+
+    fn has_post_match_value(&self) -> bool {
+        self.has_post_match()
+    }
 }
 
 impl<'a> BaseQuery<'a, WIPOffset<FBMethodMatcher<'a>>> for MethodMatcher {
     fn inner_build(&self, fbb: &mut FlatBufferBuilder<'a>) -> WIPOffset<FBMethodMatcher<'a>> {
-        let method_name = self.name_matcher.as_ref().map(|m| m.inner_build(fbb));
+        let method_name = StringMatcher::native(&self.name_matcher).map(|m| m.inner_build(fbb));
         let access_flags = self.modifiers_matcher.as_ref().map(|m| m.inner_build(fbb));
         let declaring_class = self.class_matcher.as_ref().map(|m| m.inner_build(fbb));
         let return_type = self
@@ -75,15 +103,31 @@ impl<'a> BaseQuery<'a, WIPOffset<FBMethodMatcher<'a>>> for MethodMatcher {
             .map(|m| m.inner_build(fbb));
         let op_codes = self.op_codes_matcher.as_ref().map(|m| m.inner_build(fbb));
         let using_strings = self.using_strings_matcher.as_ref().map(|vec| {
-            let built_vec: Vec<_> = vec.iter().map(|m| m.inner_build(fbb)).collect();
+            let built_vec: Vec<_> = vec
+                .iter()
+                .filter(|m| m.is_native())
+                .map(|m| m.inner_build(fbb))
+                .collect();
             fbb.create_vector(&built_vec)
         });
         let using_fields = self.using_fields_matcher.as_ref().map(|vec| {
             let built_vec: Vec<_> = vec.iter().map(|m| m.inner_build(fbb)).collect();
             fbb.create_vector(&built_vec)
         });
-        let using_numbers = self.using_numbers_matcher.as_ref().map(|vec| {
-            let vec = vec.iter().map(|n| {
+        // a resource id is loaded with const/high16 when its low 16 bits are zero, which the
+        // native search reads as a float, and with const otherwise
+        let resource_numbers = self.using_resource_ids.iter().flatten().map(|&id| {
+            if id & 0xffff == 0 {
+                NumberEncodeValueMatcher::create_float(f32::from_bits(id))
+            } else {
+                NumberEncodeValueMatcher::create_int(id as i32)
+            }
+        });
+        let resource_numbers: Vec<_> = resource_numbers.collect();
+        let has_numbers = self.using_numbers_matcher.is_some() || !resource_numbers.is_empty();
+        let using_numbers = has_numbers.then(|| {
+            let numbers = self.using_numbers_matcher.iter().flatten();
+            let vec = numbers.chain(&resource_numbers).map(|n| {
                 let value_type: FBNumber = n.into();
                 let value = n.inner_build(fbb);
                 FBNumberWrapper::create(
@@ -132,6 +176,132 @@ impl<'a> BaseQuery<'a, WIPOffset<FBMethodMatcher<'a>>> for MethodMatcher {
     }
 }
 
+impl<'a> IPostMatch<MethodData<'a>> for MethodMatcher {
+    fn has_post_match(&self) -> bool {
+        StringMatcher::has_post_match(&self.name_matcher)
+            || StringMatcher::has_post_match_any(&self.using_strings_matcher)
+            || self
+                .using_fields_matcher
+                .iter()
+                .flatten()
+                .any(|m| m.has_post_match())
+            || self
+                .class_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .return_type_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .params_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .invoke_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self
+                .caller_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+            || self.using_resources.is_some()
+            || self.line_number.is_some()
+            || self.catch_types.is_some()
+            || self.throws_types.is_some()
+            || self.thrown_types.is_some()
+    }
+
+    fn post_match(&self, method: &MethodData<'a>, cache: &PostMatchCache) -> bool {
+        StringMatcher::post_match(&self.name_matcher, || method.name())
+            && StringMatcher::post_match_any(&self.using_strings_matcher, || method.using_strings())
+            && self
+                .class_matcher
+                .as_ref()
+                .filter(|m| m.has_post_match())
+                .is_none_or(|m| {
+                    method
+                        .declared_class()
+                        .is_some_and(|c| m.post_match(&c, cache))
+                })
+            && self
+                .return_type_matcher
+                .as_ref()
+                .filter(|m| m.has_post_match())
+                .is_none_or(|m| {
+                    method
+                        .return_type_class()
+                        .is_some_and(|c| m.post_match(&c, cache))
+                })
+            && self
+                .params_matcher
+                .as_ref()
+                .is_none_or(|m| m.post_match(method, cache))
+            && self.using_resources.as_ref().is_none_or(|names| {
+                let Ok(resources) = method.bridge().resources() else {
+                    return false;
+                };
+                let numbers = method.using_numbers();
+                names.iter().all(|name| {
+                    resources.get_id(name).is_some_and(|id| {
+                        numbers.iter().any(|number| {
+                            matches!(number, UsingNumber::Int(value) if value.0 as u32 == id)
+                        })
+                    })
+                })
+            })
+            && self
+                .line_number
+                .is_none_or(|line| method.debug_info().is_some_and(|info| info.has_line(line)))
+            && Self::post_match_types(&self.catch_types, || method.catch_type_descriptors())
+            && Self::post_match_types(&self.throws_types, || method.throws_type_descriptors())
+            && Self::post_match_types(&self.thrown_types, || method.thrown_type_descriptors())
+    }
+
+    fn check_post_match(&self) -> Result<(), Error> {
+        StringMatcher::check(&self.name_matcher)?;
+        StringMatcher::check_all(&self.using_strings_matcher)?;
+        for matcher in [&self.class_matcher, &self.return_type_matcher]
+            .into_iter()
+            .flatten()
+        {
+            matcher.check_post_match()?;
+        }
+        if let Some(matcher) = &self.params_matcher {
+            matcher.check_post_match()?;
+        }
+        reject_post_match(
+            "AnnotationsMatcher",
+            self.annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "UsingFieldMatcher",
+            self.using_fields_matcher
+                .iter()
+                .flatten()
+                .any(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "invoke MethodsMatcher",
+            self.invoke_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )?;
+        reject_post_match(
+            "caller MethodsMatcher",
+            self.caller_methods_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )
+    }
+}
+
 impl MethodMatcher {
     pub fn create() -> Self {
         MethodMatcher::default()
@@ -193,6 +363,48 @@ impl MethodMatcher {
         self
     }
 
+    pub fn add_using_number(mut self, matcher: NumberEncodeValueMatcher) -> Self {
+        self.using_numbers_matcher
+            .get_or_insert_with(Vec::new)
+            .push(matcher);
+        self
+    }
+
+    /// Match methods loading the id of a resource, e.g. `R.string.login_failed`.
+    /// The id is looked up in the resource table of the apk when searching and passed to the
+    /// native search as a using number, then checked again on the bits of the literal, D8 loads
+    /// ids like `0x7f020000` with const/high16 which reads as a float, see `MethodData::using_numbers`.
+    /// A resource the apk does not define makes the query match nothing.
+    /// # Arguments
+    /// * `name` - "type/name" of the resource, e.g. "string/login_failed"
+    pub fn add_using_resource<S: Into<String>>(mut self, name: S) -> Self {
+        self.using_resources
+            .get_or_insert_with(Vec::new)
+            .push(name.into());
+        self
+    }
+
+    /// internal use, whether the matcher loads resources, see `add_using_resource`
+    pub(crate) fn has_using_resources(&self) -> bool {
+        self.using_resources.is_some()
+    }
+
+    /// internal use, look up the ids of the resources before building the native query
+    pub(crate) fn resolve_resources(&mut self, resources: &ResourceTable) -> Result<(), Error> {
+        let ids = self
+            .using_resources
+            .iter()
+            .flatten()
+            .map(|name| {
+                resources
+                    .get_id(name)
+                    .ok_or_else(|| Error::ResourceError(format!("resource not found: {}", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.using_resource_ids = Some(ids);
+        Ok(())
+    }
+
     pub fn set_invoke_methods_matcher(mut self, matcher: MethodsMatcher) -> Self {
         self.invoke_methods_matcher = Some(matcher);
         self
@@ -203,6 +415,13 @@ impl MethodMatcher {
         self
     }
 
+    /// Only methods with `line` in their line table, see `MethodData::debug_info`.
+    /// Checked after the native search
+    pub fn set_line_number(mut self, line: u32) -> Self {
+        self.line_number = Some(line);
+        self
+    }
+
     pub fn set_method_name_str<S: Into<String>>(mut self, name: S) -> Self {
         self.name_matcher = Some(StringMatcher::create_string_str(name));
         self
@@ -213,6 +432,91 @@ impl MethodMatcher {
         self
     }
 
+    /// Only methods with a try block catching `exception`, a class name or a descriptor,
+    /// e.g. "javax.net.ssl.SSLPeerUnverifiedException". Checked after the native search
+    pub fn add_catch_type<S: Into<String>>(mut self, exception: S) -> Self {
+        let descriptor = Self::type_descriptor(exception.into());
+        self.catch_types
+            .get_or_insert_with(Vec::new)
+            .push(descriptor);
+        self
+    }
+
+    /// Only methods declaring `throws exception`, see `MethodData::throws_types`.
+    /// Checked after the native search
+    pub fn add_throws_type<S: Into<String>>(mut self, exception: S) -> Self {
+        let descriptor = Self::type_descriptor(exception.into());
+        self.throws_types
+            .get_or_insert_with(Vec::new)
+            .push(descriptor);
+        self
+    }
+
+    /// Only methods containing `throw new exception(...)`, see `MethodData::thrown_types`.
+    /// Checked after the native search
+    pub fn add_thrown_type<S: Into<String>>(mut self, exception: S) -> Self {
+        let descriptor = Self::type_descriptor(exception.into());
+        self.thrown_types
+            .get_or_insert_with(Vec::new)
+            .push(descriptor);
+        self
+    }
+
+    /// Match a stack trace frame, e.g. "at a.b.c(MainActivity.java:42)":
+    /// the source file of the declaring class and a line of the method
+    pub fn set_source_line<S: Into<String>>(mut self, source_file: S, line: u32) -> Self {
+        let class_matcher = self
+            .class_matcher
+            .take()
+            .unwrap_or_default()
+            .set_source_matcher(StringMatcher::create_eq_string_str(source_file));
+        self.class_matcher = Some(class_matcher);
+        self.set_line_number(line)
+    }
+
     // extend
     // todo!
+
+    /// internal use, the descriptor of a class name, descriptors are kept
+    fn type_descriptor(name: String) -> String {
+        if DexSignature::get_type_name(&name).is_some() {
+            return name;
+        }
+        DexSignature::get_type_signature(&name).unwrap_or(name)
+    }
+
+    /// internal use, every expected descriptor is in `actual`
+    fn post_match_types(
+        expected: &Option<Vec<String>>,
+        actual: impl FnOnce() -> Vec<String>,
+    ) -> bool {
+        let Some(expected) = expected else {
+            return true;
+        };
+        let actual = actual();
+        expected
+            .iter()
+            .all(|descriptor| actual.contains(descriptor))
+    }
+
+    // logical, see `Logical`
+    pub fn any_of<I, L>(matchers: I) -> Logical<MethodMatcher>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<MethodMatcher>>,
+    {
+        Logical::any_of(matchers)
+    }
+
+    pub fn all_of<I, L>(matchers: I) -> Logical<MethodMatcher>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Logical<MethodMatcher>>,
+    {
+        Logical::all_of(matchers)
+    }
+
+    pub fn not<L: Into<Logical<MethodMatcher>>>(matcher: L) -> Logical<MethodMatcher> {
+        Logical::not(matcher)
+    }
 }
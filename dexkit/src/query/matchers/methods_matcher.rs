@@ -2,7 +2,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
     MatchType as FBMatchType, MethodsMatcher as FBMethodsMatcher,
     MethodsMatcherArgs as FBMethodsMatcherArgs,
 };
-use crate::query::base::BaseQuery;
+use crate::query::base::{BaseQuery, IPostMatch};
 use crate::query::enums::MatchType;
 use crate::query::matchers::MethodMatcher;
 use crate::query::matchers::base::IntRange;
@@ -45,6 +45,14 @@ impl<'a> BaseQuery<'a, WIPOffset<FBMethodsMatcher<'a>>> for MethodsMatcher {
 }
 
 impl MethodsMatcher {
+    /// internal use, whether one of the items needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.methods_matcher
+            .iter()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
+
     pub fn create() -> Self {
         MethodsMatcher::default()
     }
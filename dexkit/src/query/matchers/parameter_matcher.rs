@@ -1,8 +1,10 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     ParameterMatcher as FBParameterMatcher, ParameterMatcherArgs as FBParameterMatcherArgs,
 };
-use crate::query::base::BaseQuery;
+use crate::query::base::{BaseQuery, IPostMatch, PostMatchCache, reject_post_match};
 use crate::query::matchers::{AnnotationsMatcher, ClassMatcher};
+use crate::result::ClassData;
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
 
 pub struct ParameterMatcher {
@@ -37,6 +39,37 @@ impl<'a> BaseQuery<'a, WIPOffset<FBParameterMatcher<'a>>> for ParameterMatcher {
     }
 }
 
+/// only the parameter type is post matched, parameter annotations are not available
+impl<'a> IPostMatch<ClassData<'a>> for ParameterMatcher {
+    fn has_post_match(&self) -> bool {
+        self.type_matcher
+            .as_ref()
+            .is_some_and(|m| m.has_post_match())
+            || self
+                .annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match())
+    }
+
+    fn post_match(&self, param_type: &ClassData<'a>, cache: &PostMatchCache) -> bool {
+        self.type_matcher
+            .as_ref()
+            .is_none_or(|m| m.post_match(param_type, cache))
+    }
+
+    fn check_post_match(&self) -> Result<(), Error> {
+        if let Some(matcher) = &self.type_matcher {
+            matcher.check_post_match()?;
+        }
+        reject_post_match(
+            "parameter AnnotationsMatcher",
+            self.annotations_matcher
+                .as_ref()
+                .is_some_and(|m| m.has_post_match()),
+        )
+    }
+}
+
 impl<'a> ParameterMatcher {
     pub fn create() -> Self {
         Self::default()
@@ -1,9 +1,11 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     ParametersMatcher as FBParametersMatcher, ParametersMatcherArgs as FBParametersMatcherArgs,
 };
-use crate::query::base::BaseQuery;
+use crate::query::base::{BaseQuery, IPostMatch, PostMatchCache};
 use crate::query::matchers::ParameterMatcher;
 use crate::query::matchers::base::IntRange;
+use crate::result::{MethodData, TypeRef};
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
 
 pub struct ParametersMatcher {
@@ -49,6 +51,51 @@ impl<'a> BaseQuery<'a, WIPOffset<FBParametersMatcher<'a>>> for ParametersMatcher
     }
 }
 
+impl<'a> IPostMatch<MethodData<'a>> for ParametersMatcher {
+    fn has_post_match(&self) -> bool {
+        self.params_matcher
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|m| m.has_post_match())
+    }
+
+    fn post_match(&self, method: &MethodData<'a>, cache: &PostMatchCache) -> bool {
+        if !self.has_post_match() {
+            return true;
+        }
+        // `param_types` may skip types, the positions come from `param_type_refs`
+        let Ok(type_refs) = method.param_type_refs() else {
+            return false;
+        };
+        let param_types = method.param_types().map(Vec::from).unwrap_or_default();
+        let param_type = |index: usize| match type_refs.get(index)? {
+            TypeRef::Class(class) => Some(class.clone()),
+            TypeRef::External(descriptor) => param_types
+                .iter()
+                .find(|class| &class.descriptor() == descriptor)
+                .cloned(),
+        };
+        self.params_matcher
+            .iter()
+            .flatten()
+            .enumerate()
+            .all(|(index, matcher)| match matcher {
+                Some(matcher) if matcher.has_post_match() => param_type(index)
+                    .is_some_and(|param_type| matcher.post_match(&param_type, cache)),
+                _ => true,
+            })
+    }
+
+    fn check_post_match(&self) -> Result<(), Error> {
+        self.params_matcher
+            .iter()
+            .flatten()
+            .flatten()
+            .try_for_each(|m| m.check_post_match())
+    }
+}
+
 impl ParametersMatcher {
     pub fn create() -> Self {
         Self::default()
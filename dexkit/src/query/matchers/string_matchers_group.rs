@@ -1,7 +1,9 @@
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::{
     BatchUsingStringsMatcher as FBBatchUsingStringsMatcher,
     BatchUsingStringsMatcherArgs as FBBatchUsingStringsMatcherArgs,
 };
+use crate::query::base::reject_post_match;
 use crate::query::{base::BaseQuery, matchers::base::StringMatcher};
 
 pub struct StringMatchersGroup {
@@ -61,17 +63,34 @@ impl StringMatchersGroup {
         self
     }
 
+    /// `StringMatchType::Regex` is not allowed, batch groups are only evaluated natively.
+    /// A batch search with such a group finds nothing, see `try_set_string_matchers`
     pub fn set_string_matchers(mut self, matchers: Vec<StringMatcher>) -> Self {
         self.string_matchers = matchers;
         self
     }
 
+    /// `set_string_matchers`, Err for `StringMatchType::Regex`
+    pub fn try_set_string_matchers(self, matchers: Vec<StringMatcher>) -> Result<Self, Error> {
+        for matcher in &matchers {
+            reject_post_match("StringMatchersGroup", !matcher.is_native())?;
+        }
+        Ok(self.set_string_matchers(matchers))
+    }
+
     // extend string_matchers
+    /// `StringMatchType::Regex` is not allowed, see `set_string_matchers`
     pub fn add_string_matcher(mut self, matcher: StringMatcher) -> Self {
         self.string_matchers.push(matcher);
         self
     }
 
+    /// `add_string_matcher`, Err for `StringMatchType::Regex`
+    pub fn try_add_string_matcher(self, matcher: StringMatcher) -> Result<Self, Error> {
+        reject_post_match("StringMatchersGroup", !matcher.is_native())?;
+        Ok(self.add_string_matcher(matcher))
+    }
+
     pub fn add_string_matchers_str<S: Into<String>>(mut self, strings: Vec<S>) -> Self {
         self.string_matchers.extend(
             strings
@@ -101,4 +120,11 @@ impl StringMatchersGroup {
             .push(StringMatcher::create_eq_string_str(string));
         self
     }
+
+    /// internal use, Err for a matcher `try_add_string_matcher` rejects
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        self.string_matchers
+            .iter()
+            .try_for_each(|m| reject_post_match("StringMatchersGroup", !m.is_native()))
+    }
 }
@@ -2,7 +2,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
     UsingFieldMatcher as FBUsingFieldMatcher, UsingFieldMatcherArgs as FBUsingFieldMatcherArgs,
     UsingType as FBUsingType,
 };
-use crate::query::base::BaseQuery;
+use crate::query::base::{BaseQuery, IPostMatch};
 use crate::query::enums::UsingType;
 use crate::query::matchers::FieldMatcher;
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
@@ -29,3 +29,10 @@ impl<'a> BaseQuery<'a, WIPOffset<FBUsingFieldMatcher<'a>>> for UsingFieldMatcher
         FBUsingFieldMatcher::create(fbb, &FBUsingFieldMatcherArgs { field, using_type })
     }
 }
+
+impl UsingFieldMatcher {
+    /// internal use, whether the field matcher needs post matching, see `reject_post_match`
+    pub(crate) fn has_post_match(&self) -> bool {
+        self.matcher.as_ref().is_some_and(|m| m.has_post_match())
+    }
+}
@@ -0,0 +1,276 @@
+use crate::query::enums::{AnnotationVisibilityType, RetentionPolicyType, TargetElementType};
+use crate::result::{
+    AnnotationElementData, AnnotationEncodeValue, ClassData, MethodData, MethodDataList,
+};
+use crate::uitls::Modifier;
+
+const RETENTION: &str = "Ljava/lang/annotation/Retention;";
+const TARGET: &str = "Ljava/lang/annotation/Target;";
+const ANNOTATION_DEFAULT: &str = "Ldalvik/annotation/AnnotationDefault;";
+
+/// The declaration of an annotation class: its own `@Retention`, `@Target` and the default
+/// values of its elements, see `ClassData::annotation_class_info`.
+#[derive(Debug, Clone)]
+pub struct AnnotationClassInfo<'a> {
+    class: ClassData<'a>,
+    retention: Option<RetentionPolicyType>,
+    targets: Option<Vec<TargetElementType>>,
+    defaults: Vec<AnnotationElementData<'a>>,
+}
+
+impl<'a> AnnotationClassInfo<'a> {
+    /// get the annotation class
+    pub fn class(&self) -> &ClassData<'a> {
+        &self.class
+    }
+
+    /// get the retention declared by `@Retention`, None without it
+    pub fn declared_retention(&self) -> Option<RetentionPolicyType> {
+        self.retention
+    }
+
+    /// get the retention, `RetentionPolicyType::Class` when `@Retention` is absent
+    pub fn retention(&self) -> RetentionPolicyType {
+        self.retention.unwrap_or(RetentionPolicyType::Class)
+    }
+
+    /// get the visibility the usages of the annotation are stored with,
+    /// None for `Source` retention, those usages are not kept in the dex
+    pub fn visibility(&self) -> Option<AnnotationVisibilityType> {
+        match self.retention() {
+            RetentionPolicyType::Source => None,
+            RetentionPolicyType::Class => Some(AnnotationVisibilityType::Build),
+            RetentionPolicyType::Runtime => Some(AnnotationVisibilityType::Runtime),
+        }
+    }
+
+    /// get the targets declared by `@Target`, None without it, i.e. any declaration.
+    /// Targets unknown to `TargetElementType`, e.g. `MODULE`, are skipped
+    pub fn targets(&self) -> Option<&[TargetElementType]> {
+        self.targets.as_deref()
+    }
+
+    /// can be applied to `target`
+    pub fn is_applicable_to(&self, target: TargetElementType) -> bool {
+        match &self.targets {
+            Some(targets) => targets.contains(&target),
+            // without @Target, any declaration context except type parameters
+            None => !matches!(
+                target,
+                TargetElementType::TypeParameter | TargetElementType::TypeUse
+            ),
+        }
+    }
+
+    /// get the elements of the annotation, i.e. its abstract methods
+    pub fn elements(&self) -> MethodDataList<'a> {
+        self.class
+            .methods()
+            .iter()
+            .filter(|method| Modifier::from_bits(method.modifiers()).contains(Modifier::ABSTRACT))
+            .cloned()
+            .collect()
+    }
+
+    /// get the element with the given name
+    pub fn element(&self, name: &str) -> Option<MethodData<'a>> {
+        self.elements()
+            .into_iter()
+            .find(|method| method.name().as_deref() == Some(name))
+    }
+
+    /// get the default values from `dalvik.annotation.AnnotationDefault`,
+    /// elements without default are absent
+    pub fn defaults(&self) -> &Vec<AnnotationElementData<'a>> {
+        &self.defaults
+    }
+
+    /// get the default value of an element, None if it has no default
+    pub fn default_value(&self, name: &str) -> Option<&AnnotationEncodeValue<'a>> {
+        self.defaults
+            .iter()
+            .find(|element| element.name() == name)
+            .map(|element| element.value())
+    }
+
+    /// internal use, read the declaration of an annotation class
+    pub(crate) fn from_class(class: ClassData<'a>) -> Self {
+        let annotations = class.annotations();
+        let find = |descriptor: &str| {
+            annotations
+                .iter()
+                .find(|annotation| annotation.is_type(descriptor))
+        };
+        let enum_name = |value: &AnnotationEncodeValue<'a>| value.enum_value()?.name();
+        let retention = find(RETENTION)
+            .and_then(|annotation| enum_name(annotation.element_value("value")?))
+            .and_then(|name| RetentionPolicyType::from_java_name(&name));
+        let targets = find(TARGET).map(|annotation| {
+            annotation
+                .element_value("value")
+                .and_then(|value| value.array_value())
+                .map(|array| {
+                    array
+                        .values()
+                        .iter()
+                        .filter_map(|value| TargetElementType::from_java_name(&enum_name(value)?))
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+        let defaults = find(ANNOTATION_DEFAULT)
+            .and_then(|annotation| annotation.element_value("value")?.annotation_value())
+            .map(|annotation| annotation.elements().clone())
+            .unwrap_or_default();
+        Self {
+            class,
+            retention,
+            targets,
+            defaults,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DexkitBridge;
+    use crate::result::{AnnotationData, AnnotationEncodeArrayData, EncodeValueData, FieldData};
+
+    const INFO: &str = "Lcom/example/Info;";
+
+    fn enum_constant<'a>(bridge: &'a DexkitBridge, descriptor: &str) -> EncodeValueData<'a> {
+        EncodeValueData::Enum(FieldData::for_test(bridge, 0, 0, descriptor))
+    }
+
+    fn info<'a>(
+        bridge: &'a DexkitBridge,
+        annotations: Vec<AnnotationData<'a>>,
+    ) -> AnnotationClassInfo<'a> {
+        let abstract_method = |id, descriptor| {
+            MethodData::for_test(bridge, 0, id, descriptor)
+                .with_test_modifiers((Modifier::PUBLIC | Modifier::ABSTRACT).bits())
+        };
+        let class = ClassData::for_test(bridge, 0, 1, INFO)
+            .with_test_annotations(annotations)
+            .with_test_methods(vec![
+                abstract_method(2, "Lcom/example/Info;->name()Ljava/lang/String;"),
+                abstract_method(3, "Lcom/example/Info;->priority()I"),
+                MethodData::for_test(bridge, 0, 4, "Lcom/example/Info;-><clinit>()V"),
+            ]);
+        AnnotationClassInfo::from_class(class)
+    }
+
+    #[test]
+    fn test_declared() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let targets = [
+            "Ljava/lang/annotation/ElementType;->TYPE:Ljava/lang/annotation/ElementType;",
+            "Ljava/lang/annotation/ElementType;->METHOD:Ljava/lang/annotation/ElementType;",
+            "Ljava/lang/annotation/ElementType;->MODULE:Ljava/lang/annotation/ElementType;",
+        ]
+        .iter()
+        .map(|descriptor| AnnotationEncodeValue::for_test(enum_constant(&bridge, descriptor)))
+        .collect();
+        let defaults =
+            AnnotationData::for_test(&bridge, INFO, vec![("priority", EncodeValueData::Int(5))]);
+        let info = info(
+            &bridge,
+            vec![
+                AnnotationData::for_test(
+                    &bridge,
+                    RETENTION,
+                    vec![(
+                        "value",
+                        enum_constant(
+                            &bridge,
+                            "Ljava/lang/annotation/RetentionPolicy;->RUNTIME:Ljava/lang/annotation/RetentionPolicy;",
+                        ),
+                    )],
+                ),
+                AnnotationData::for_test(
+                    &bridge,
+                    TARGET,
+                    vec![(
+                        "value",
+                        EncodeValueData::Array(AnnotationEncodeArrayData::from_values(
+                            &bridge, targets,
+                        )),
+                    )],
+                ),
+                AnnotationData::for_test(
+                    &bridge,
+                    ANNOTATION_DEFAULT,
+                    vec![("value", EncodeValueData::Annotation(defaults))],
+                ),
+            ],
+        );
+        assert_eq!(
+            info.declared_retention(),
+            Some(RetentionPolicyType::Runtime)
+        );
+        assert_eq!(info.visibility(), Some(AnnotationVisibilityType::Runtime));
+        // MODULE is skipped
+        assert_eq!(
+            info.targets(),
+            Some(&[TargetElementType::Type, TargetElementType::Method][..])
+        );
+        assert!(info.is_applicable_to(TargetElementType::Method));
+        assert!(!info.is_applicable_to(TargetElementType::Field));
+
+        assert_eq!(
+            info.elements()
+                .iter()
+                .map(|method| method.descriptor())
+                .collect::<Vec<_>>(),
+            [
+                "Lcom/example/Info;->name()Ljava/lang/String;",
+                "Lcom/example/Info;->priority()I"
+            ]
+        );
+        assert!(info.element("priority").is_some());
+        assert!(info.element("<clinit>").is_none());
+        assert_eq!(info.defaults().len(), 1);
+        assert_eq!(
+            info.default_value("priority")
+                .and_then(|value| value.int_value()),
+            Some(5)
+        );
+        assert!(info.default_value("name").is_none());
+    }
+
+    #[test]
+    fn test_undeclared() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let info = info(&bridge, Vec::new());
+        assert_eq!(info.declared_retention(), None);
+        assert_eq!(info.retention(), RetentionPolicyType::Class);
+        assert_eq!(info.visibility(), Some(AnnotationVisibilityType::Build));
+        assert!(info.targets().is_none());
+        assert!(info.is_applicable_to(TargetElementType::Field));
+        assert!(!info.is_applicable_to(TargetElementType::TypeUse));
+        assert!(info.defaults().is_empty());
+        assert!(info.default_value("priority").is_none());
+    }
+
+    #[test]
+    fn test_source_retention() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let info = info(
+            &bridge,
+            vec![AnnotationData::for_test(
+                &bridge,
+                RETENTION,
+                vec![(
+                    "value",
+                    enum_constant(
+                        &bridge,
+                        "Ljava/lang/annotation/RetentionPolicy;->SOURCE:Ljava/lang/annotation/RetentionPolicy;",
+                    ),
+                )],
+            )],
+        );
+        assert_eq!(info.retention(), RetentionPolicyType::Source);
+        assert_eq!(info.visibility(), None);
+    }
+}
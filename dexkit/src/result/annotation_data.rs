@@ -3,7 +3,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
     AnnotationVisibilityType as FBAnnotationVisibilityType,
     ParametersAnnotationMetaArrayHoler as FBParametersAnnotationMetaArrayHolder,
 };
-use crate::result::AnnotationElementData;
+use crate::result::{AnnotationElementData, AnnotationEncodeValue, ClassData, TypeRef};
 use crate::wrap::DexClass;
 use crate::{DexkitBridge, query::enums::AnnotationVisibilityType, result::base::BaseData};
 use std::cell::OnceCell;
@@ -63,6 +63,43 @@ impl<'a> AnnotationData<'a> {
         self.get_dex_class().map(|dex_class| dex_class.type_name())
     }
 
+    /// get the class of the annotation type
+    pub fn type_class(&self) -> Option<ClassData<'a>> {
+        let encode_id = Self::get_encode_id(self.dex_id, self.type_id);
+        self.bridge
+            .get_type_by_ids(&vec![encode_id])
+            .get(0)
+            .cloned()
+    }
+
+    /// get the annotation type, `TypeRef::External` for annotations not defined in the apk
+    /// or an attached library, e.g. "Ldalvik/annotation/Signature;"
+    pub fn annotation_type(&self) -> TypeRef<'a> {
+        TypeRef::from_descriptor(self.bridge, self.type_descriptor.clone())
+    }
+
+    /// get the string values of the elements, nested arrays and annotations included
+    pub fn using_strings(&self) -> Vec<String> {
+        let mut strings = Vec::new();
+        for element in &self.elements {
+            element.value().collect_strings(&mut strings);
+        }
+        strings
+    }
+
+    /// get the value of the element with the given name
+    pub fn element_value(&self, name: &str) -> Option<&AnnotationEncodeValue<'a>> {
+        self.elements
+            .iter()
+            .find(|element| element.name() == name)
+            .map(|element| element.value())
+    }
+
+    /// internal use, is of the given annotation type, e.g. "Ldalvik/annotation/InnerClass;"
+    pub(crate) fn is_type(&self, descriptor: &str) -> bool {
+        self.type_descriptor == descriptor
+    }
+
     /// internal use, get dex class
     pub(crate) fn get_dex_class(&self) -> Option<&DexClass> {
         self.dex_class
@@ -150,3 +187,28 @@ impl<'a> AnnotationData<'a> {
         })
     }
 }
+
+#[cfg(test)]
+impl<'a> AnnotationData<'a> {
+    /// internal use, an annotation without native meta data,
+    /// e.g. "Ldalvik/annotation/InnerClass;" with its named element values
+    pub(crate) fn for_test(
+        bridge: &'a DexkitBridge,
+        type_descriptor: &str,
+        elements: Vec<(&str, crate::result::EncodeValueData<'a>)>,
+    ) -> Self {
+        Self {
+            bridge,
+            dex_id: 0,
+            type_id: 0,
+            type_descriptor: type_descriptor.to_string(),
+            visibility: Some(AnnotationVisibilityType::System),
+            elements: elements
+                .into_iter()
+                .map(|(name, value)| AnnotationElementData::for_test(bridge, name, value))
+                .collect(),
+            // Lazy loaded fields
+            dex_class: OnceCell::new(),
+        }
+    }
+}
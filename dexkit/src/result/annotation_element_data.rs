@@ -22,6 +22,11 @@ impl<'a> Debug for AnnotationElementData<'a> {
 }
 
 impl<'a> AnnotationElementData<'a> {
+    /// get element name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// get value
     pub fn value(&self) -> &AnnotationEncodeValue<'a> {
         &self.value
@@ -45,3 +50,19 @@ impl<'a> AnnotationElementData<'a> {
         }
     }
 }
+
+#[cfg(test)]
+impl<'a> AnnotationElementData<'a> {
+    /// internal use, an element without native meta data
+    pub(crate) fn for_test(
+        bridge: &'a DexkitBridge,
+        name: &str,
+        value: crate::result::EncodeValueData<'a>,
+    ) -> Self {
+        Self {
+            bridge,
+            name: name.to_string(),
+            value: AnnotationEncodeValue::for_test(value),
+        }
+    }
+}
@@ -17,6 +17,19 @@ impl<'a> Debug for AnnotationEncodeArrayData<'a> {
 }
 
 impl<'a> AnnotationEncodeArrayData<'a> {
+    /// get values
+    pub fn values(&self) -> &Vec<AnnotationEncodeValue<'a>> {
+        &self.values
+    }
+
+    /// internal use, wrap values decoded on the Rust side
+    pub(crate) fn from_values(
+        bridge: &'a DexkitBridge,
+        values: Vec<AnnotationEncodeValue<'a>>,
+    ) -> AnnotationEncodeArrayData<'a> {
+        Self { bridge, values }
+    }
+
     pub(crate) fn with_meta(
         bridge: &'a DexkitBridge,
         meta: FBAnnotationEncodeArray<'a>,
@@ -5,6 +5,7 @@ use std::str::{from_utf8, from_utf8_unchecked};
 use flatbuffers::{UOffsetT, Verifier, VerifierOptions, read_scalar_at};
 
 use crate::DexkitBridge;
+use crate::dex::{DexFile, EncodedValue};
 use crate::gen_flatbuffers::dexkit::schema::{
     AnnotationEncodeValueMeta as FBAnnotationEncodeValueMeta,
     AnnotationEncodeValueType as FBAnnotationEncodeValueType,
@@ -12,6 +13,7 @@ use crate::gen_flatbuffers::dexkit::schema::{
 };
 use crate::query::enums::AnnotationEncodeValueType;
 use crate::query::matchers::EncodeValue;
+use crate::result::base::BaseData;
 use crate::result::{AnnotationData, AnnotationEncodeArrayData, ClassData, FieldData, MethodData};
 use crate::uitls::{MUtf8, StringUnicodeEncoderDecoder};
 
@@ -186,6 +188,101 @@ impl<'a> AnnotationEncodeValue<'a> {
         matches!(self.value, EncodeValueData::Null)
     }
 
+    /// internal use, collect the string values, nested arrays and annotations included
+    pub(crate) fn collect_strings(&self, strings: &mut Vec<String>) {
+        match &self.value {
+            EncodeValueData::String(s) => strings.push(s.clone()),
+            EncodeValueData::Array(array) => {
+                for value in array.values() {
+                    value.collect_strings(strings);
+                }
+            }
+            EncodeValueData::Annotation(annotation) => strings.extend(annotation.using_strings()),
+            _ => {}
+        }
+    }
+
+    /// internal use, convert a value decoded from a dex file, e.g. a static field value.
+    /// None for method types, method handles, field references and annotations
+    pub(crate) fn from_encoded_value(
+        bridge: &'a DexkitBridge,
+        dex: &DexFile,
+        value: &EncodedValue,
+    ) -> Option<AnnotationEncodeValue<'a>> {
+        let encode_id = |idx: u32| ClassData::get_encode_id(dex.dex_id(), idx);
+        let (value, value_type) = match value {
+            EncodedValue::Byte(v) => (
+                EncodeValueData::Byte(*v),
+                AnnotationEncodeValueType::ByteValue,
+            ),
+            EncodedValue::Short(v) => (
+                EncodeValueData::Short(*v),
+                AnnotationEncodeValueType::ShortValue,
+            ),
+            EncodedValue::Char(v) => (
+                EncodeValueData::Char(char::from_u32(*v as u32).unwrap_or('\0')),
+                AnnotationEncodeValueType::CharValue,
+            ),
+            EncodedValue::Int(v) => (
+                EncodeValueData::Int(*v),
+                AnnotationEncodeValueType::IntValue,
+            ),
+            EncodedValue::Long(v) => (
+                EncodeValueData::Long(*v),
+                AnnotationEncodeValueType::LongValue,
+            ),
+            EncodedValue::Float(v) => (
+                EncodeValueData::Float(*v),
+                AnnotationEncodeValueType::FloatValue,
+            ),
+            EncodedValue::Double(v) => (
+                EncodeValueData::Double(*v),
+                AnnotationEncodeValueType::DoubleValue,
+            ),
+            EncodedValue::String(idx) => (
+                EncodeValueData::String(dex.string(*idx).ok()?),
+                AnnotationEncodeValueType::StringValue,
+            ),
+            EncodedValue::Type(idx) => (
+                EncodeValueData::Type(bridge.get_type_by_ids(&[encode_id(*idx)]).first()?.clone()),
+                AnnotationEncodeValueType::TypeValue,
+            ),
+            EncodedValue::Method(idx) => (
+                EncodeValueData::Method(
+                    bridge
+                        .get_method_by_ids(&[encode_id(*idx)])
+                        .first()?
+                        .clone(),
+                ),
+                AnnotationEncodeValueType::MethodValue,
+            ),
+            EncodedValue::Enum(idx) => (
+                EncodeValueData::Enum(bridge.get_field_by_ids(&[encode_id(*idx)]).first()?.clone()),
+                AnnotationEncodeValueType::EnumValue,
+            ),
+            EncodedValue::Array(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| Self::from_encoded_value(bridge, dex, value))
+                    .collect::<Option<Vec<_>>>()?;
+                (
+                    EncodeValueData::Array(AnnotationEncodeArrayData::from_values(bridge, values)),
+                    AnnotationEncodeValueType::ArrayValue,
+                )
+            }
+            EncodedValue::Null => (EncodeValueData::Null, AnnotationEncodeValueType::NullValue),
+            EncodedValue::Boolean(v) => (
+                EncodeValueData::Bool(*v),
+                AnnotationEncodeValueType::BoolValue,
+            ),
+            EncodedValue::MethodType(_)
+            | EncodedValue::MethodHandle(_)
+            | EncodedValue::Field(_)
+            | EncodedValue::Annotation(_) => return None,
+        };
+        Some(Self { value, value_type })
+    }
+
     /// ...
     pub(crate) fn with_meta(
         bridge: &'a DexkitBridge,
@@ -358,3 +455,28 @@ impl<'a> AnnotationEncodeValue<'a> {
         }
     }
 }
+
+#[cfg(test)]
+impl<'a> AnnotationEncodeValue<'a> {
+    /// internal use, a value without native meta data
+    pub(crate) fn for_test(value: EncodeValueData<'a>) -> Self {
+        let value_type = match &value {
+            EncodeValueData::Byte(_) => AnnotationEncodeValueType::ByteValue,
+            EncodeValueData::Short(_) => AnnotationEncodeValueType::ShortValue,
+            EncodeValueData::Char(_) => AnnotationEncodeValueType::CharValue,
+            EncodeValueData::Int(_) => AnnotationEncodeValueType::IntValue,
+            EncodeValueData::Long(_) => AnnotationEncodeValueType::LongValue,
+            EncodeValueData::Float(_) => AnnotationEncodeValueType::FloatValue,
+            EncodeValueData::Double(_) => AnnotationEncodeValueType::DoubleValue,
+            EncodeValueData::String(_) => AnnotationEncodeValueType::StringValue,
+            EncodeValueData::Type(_) => AnnotationEncodeValueType::TypeValue,
+            EncodeValueData::Method(_) => AnnotationEncodeValueType::MethodValue,
+            EncodeValueData::Enum(_) => AnnotationEncodeValueType::EnumValue,
+            EncodeValueData::Array(_) => AnnotationEncodeValueType::ArrayValue,
+            EncodeValueData::Annotation(_) => AnnotationEncodeValueType::AnnotationValue,
+            EncodeValueData::Null => AnnotationEncodeValueType::NullValue,
+            EncodeValueData::Bool(_) => AnnotationEncodeValueType::BoolValue,
+        };
+        Self { value, value_type }
+    }
+}
@@ -1,14 +1,24 @@
 use std::cell::OnceCell;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::sync::LazyLock;
 
+use crate::dex::{ClassDef, DexFile};
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::ClassMeta as FBClassMeta;
 use crate::result::{
-    AnnotationData, ClassDataList, FieldData, FieldDataList, MethodData, MethodDataList,
+    AnnotationClassInfo, AnnotationData, ClassDataList, EnumConstant, FieldData, FieldDataList,
+    MethodData, MethodDataList, TypeRef,
 };
+use crate::uitls::{ClassSignature, Modifier};
 use crate::wrap::DexClass;
 use crate::{DexkitBridge, result::base::BaseData};
 
+const INNER_CLASS: &str = "Ldalvik/annotation/InnerClass;";
+const ENCLOSING_CLASS: &str = "Ldalvik/annotation/EnclosingClass;";
+const ENCLOSING_METHOD: &str = "Ldalvik/annotation/EnclosingMethod;";
+const MEMBER_CLASSES: &str = "Ldalvik/annotation/MemberClasses;";
+
 #[allow(unused)]
 #[derive(Clone)]
 pub struct ClassData<'a> {
@@ -61,7 +71,32 @@ impl<'a> BaseData for ClassData<'a> {
     }
 }
 
+impl PartialEq for ClassData<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_mine_encode_id() == other.get_mine_encode_id()
+    }
+}
+
+impl Eq for ClassData<'_> {}
+
+impl Hash for ClassData<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_mine_encode_id().hash(state);
+    }
+}
+
 impl<'a> ClassData<'a> {
+    /// index of the dex defining the class, see `DexkitBridge::dex_files`
+    pub fn dex_index(&self) -> u32 {
+        self.dex_id
+    }
+
+    /// is defined in a library attached with `DexkitBridge::add_library_path`.
+    /// Members and annotations of library classes are not resolved.
+    pub fn is_library(&self) -> bool {
+        self.bridge.is_library_dex(self.dex_id)
+    }
+
     /// source file name, e.g. "MyClass.java"
     pub fn source_file(&self) -> String {
         self.source_file.to_string()
@@ -87,49 +122,139 @@ impl<'a> ClassData<'a> {
         self.get_dex_class().map(|dc| dc.simple_name())
     }
 
+    /// package name, e.g. "com.example"
+    pub fn package_name(&self) -> Option<String> {
+        self.get_dex_class().map(|dc| dc.package_name())
+    }
+
     /// is array type, e.g. "com.example.MyClass[]" -> true
     pub fn is_array(&self) -> bool {
         self.get_dex_class().map_or(false, |dc| dc.is_array())
     }
 
-    /// get super class, None if no super class
+    /// get super class, None if no super class.
+    /// A super class outside the apk is looked up in the attached libraries.
     pub fn supper_class(&self) -> Option<ClassData<'a>> {
         let cls = self.super_class.get_or_init(|| {
-            self.super_class_id.and_then(|id| {
-                let encode_id = Self::get_encode_id(self.dex_id, id);
-                self.bridge
-                    .get_type_by_ids(&vec![encode_id])
-                    .get(0)
-                    .cloned()
-                    .map(Box::new)
-            })
+            if self.is_library() {
+                return self
+                    .super_class_id
+                    .and_then(|id| self.bridge.get_library_type(self.dex_id, id))
+                    .map(Box::new);
+            }
+            self.super_class_id
+                .and_then(|id| {
+                    let encode_id = Self::get_encode_id(self.dex_id, id);
+                    self.bridge
+                        .get_type_by_ids(&vec![encode_id])
+                        .get(0)
+                        .cloned()
+                })
+                .or_else(|| {
+                    let super_idx = self.raw_class_def()?.superclass_idx?;
+                    self.bridge.get_library_type(self.dex_id, super_idx)
+                })
+                .map(Box::new)
         });
         cls.as_deref().cloned()
     }
 
-    /// get implemented interfaces of this class
+    /// get implemented interfaces of this class.
+    /// Interfaces outside the apk are looked up in the attached libraries.
     pub fn interfaces(&self) -> ClassDataList<'a> {
         self.interfaces
             .get_or_init(|| {
-                let encode_ids: Vec<i64> = self
-                    .interface_ids
-                    .iter()
-                    .map(|&id| Self::get_encode_id(self.dex_id, id as u32))
-                    .collect();
-                self.bridge.get_type_by_ids(&encode_ids)
+                let mut interfaces = if self.is_library() {
+                    ClassDataList::new()
+                } else {
+                    let encode_ids: Vec<i64> = self
+                        .interface_ids
+                        .iter()
+                        .map(|&id| Self::get_encode_id(self.dex_id, id as u32))
+                        .collect();
+                    self.bridge.get_type_by_ids(&encode_ids)
+                };
+                if interfaces.len() < self.interface_ids.len() {
+                    let resolved: Vec<String> = interfaces.iter().map(|c| c.descriptor()).collect();
+                    for &id in &self.interface_ids {
+                        if let Some(interface) =
+                            self.bridge.get_library_type(self.dex_id, id as u32)
+                            && !resolved.contains(&interface.descriptor)
+                        {
+                            interfaces.add(interface);
+                        }
+                    }
+                }
+                interfaces
             })
             .clone()
     }
 
+    /// get the super type, `TypeRef::External` when the super class is not defined in the
+    /// apk or an attached library, None if no super class
+    /// # Returns
+    /// * `Err(Error)` - If the super class is outside the apk and its dex can not be read
+    pub fn super_type(&self) -> Result<Option<TypeRef<'a>>, Error> {
+        if let Some(class) = self.supper_class() {
+            return Ok(Some(TypeRef::Class(class)));
+        }
+        let class_def = self
+            .bridge
+            .any_dex_file(self.dex_id)?
+            .class_def_by_type(self.id)?
+            .ok_or_else(|| Error::DexParseError(format!("class not found: {}", self.descriptor)))?;
+        class_def
+            .superclass_idx
+            .map(|super_idx| TypeRef::from_type_id(self.bridge, self.dex_id, super_idx))
+            .transpose()
+    }
+
+    /// get the implemented interface types, in declaration order,
+    /// `TypeRef::External` for interfaces not defined in the apk or an attached library
+    /// # Returns
+    /// * `Err(Error)` - If the dex of the class can not be read, no interface is dropped
+    pub fn interface_types(&self) -> Result<Vec<TypeRef<'a>>, Error> {
+        let interfaces = self.interfaces();
+        self.interface_ids
+            .iter()
+            .map(|&id| {
+                let descriptor = self
+                    .bridge
+                    .any_dex_file(self.dex_id)
+                    .and_then(|dex| dex.type_descriptor(id as u32))?;
+                let class = interfaces.iter().find(|c| c.descriptor == descriptor);
+                Ok(match class {
+                    Some(class) => TypeRef::Class(class.clone()),
+                    None => TypeRef::External(descriptor),
+                })
+            })
+            .collect()
+    }
+
+    /// get the generic signature from the `dalvik.annotation.Signature` of the class:
+    /// type parameters, generic super class and interfaces, None if the class is not generic
+    pub fn generic_signature(&self) -> Option<ClassSignature> {
+        let class_def = self.raw_class_def()?;
+        let signature = self
+            .bridge
+            .any_dex_file(self.dex_id)
+            .and_then(|dex| dex.class_signature(&class_def))
+            .ok()??;
+        ClassSignature::parse(&signature).ok()
+    }
+
     /// get declared interfaces count
     pub fn interface_count(&self) -> usize {
         self.interface_ids.len()
     }
 
-    /// get methods of this class
+    /// get methods of this class, empty for library classes
     pub fn methods(&self) -> MethodDataList<'a> {
         self.methods
             .get_or_init(|| {
+                if self.is_library() {
+                    return MethodDataList::new();
+                }
                 let encode_ids: Vec<i64> = self
                     .method_ids
                     .iter()
@@ -145,10 +270,13 @@ impl<'a> ClassData<'a> {
         self.method_ids.len()
     }
 
-    /// get fields of this class
+    /// get fields of this class, empty for library classes
     pub fn fields(&self) -> FieldDataList<'a> {
         self.fields
             .get_or_init(|| {
+                if self.is_library() {
+                    return FieldDataList::new();
+                }
                 let encode_ids: Vec<i64> = self
                     .field_ids
                     .iter()
@@ -164,13 +292,204 @@ impl<'a> ClassData<'a> {
         self.field_ids.len()
     }
 
-    /// get annotations of this class
+    /// get annotations of this class, empty for library classes
     pub fn annotations(&self) -> Vec<AnnotationData<'a>> {
         self.annotations
-            .get_or_init(|| self.bridge.get_class_annotations(self.id as i64))
+            .get_or_init(|| match self.is_library() {
+                true => Vec::new(),
+                false => self.bridge.get_class_annotations(self.id as i64),
+            })
             .clone()
     }
 
+    /// get the class this class is declared in, from `dalvik.annotation.EnclosingClass`
+    /// or, for local and anonymous classes, the class of `dalvik.annotation.EnclosingMethod`.
+    /// None for top level classes and library classes
+    pub fn outer_class(&self) -> Option<ClassData<'a>> {
+        let enclosing_class = self
+            .system_annotation(ENCLOSING_CLASS)
+            .and_then(|annotation| annotation.element_value("value")?.type_value());
+        enclosing_class.or_else(|| self.enclosing_method()?.declared_class())
+    }
+
+    /// get the member classes declared in this class, from `dalvik.annotation.MemberClasses`.
+    /// Local and anonymous classes are not member classes
+    pub fn inner_classes(&self) -> ClassDataList<'a> {
+        let mut classes = ClassDataList::new();
+        let values = self
+            .system_annotation(MEMBER_CLASSES)
+            .and_then(|annotation| annotation.element_value("value")?.array_value());
+        for value in values.iter().flat_map(|array| array.values()) {
+            if let Some(class) = value.type_value() {
+                classes.add(class);
+            }
+        }
+        classes
+    }
+
+    /// get the method a local or anonymous class is declared in,
+    /// from `dalvik.annotation.EnclosingMethod`.
+    /// None when declared in a field initializer or outside of a method
+    pub fn enclosing_method(&self) -> Option<MethodData<'a>> {
+        self.system_annotation(ENCLOSING_METHOD)?
+            .element_value("value")?
+            .method_value()
+    }
+
+    /// is an anonymous class, the name of its `dalvik.annotation.InnerClass` is null
+    pub fn is_anonymous(&self) -> bool {
+        self.system_annotation(INNER_CLASS)
+            .and_then(|annotation| annotation.element_value("name").cloned())
+            .is_some_and(|name| name.is_null_value())
+    }
+
+    /// get the source name of an inner class from `dalvik.annotation.InnerClass`,
+    /// kept when only the class descriptor is obfuscated, e.g. "Builder" for "La/b$c;".
+    /// None for top level and anonymous classes
+    pub fn original_inner_name(&self) -> Option<String> {
+        self.system_annotation(INNER_CLASS)?
+            .element_value("name")?
+            .string_value()
+            .map(|name| name.to_string())
+    }
+
+    /// is a synthetic lambda or method reference class, e.g.
+    /// "Lcom/example/Foo$$ExternalSyntheticLambda0;" (D8) or "Lcom/example/-$$Lambda$Foo$xyz;"
+    /// (desugar). Renamed lambda classes are recognized as synthetic final classes
+    /// implementing a single interface
+    pub fn is_lambda(&self) -> bool {
+        let modifiers = Modifier::from_bits(self.modifiers);
+        self.lambda_host().is_some()
+            || (modifiers.contains(Modifier::SYNTHETIC | Modifier::FINAL)
+                && self.interface_ids.len() == 1)
+    }
+
+    /// get the method holding the lambda body, e.g. "Lcom/example/Foo;->lambda$onCreate$0(Landroid/view/View;)V",
+    /// or the referenced method of a method reference. None if not a lambda class
+    pub fn lambda_body(&self) -> Option<MethodData<'a>> {
+        if !self.is_lambda() {
+            return None;
+        }
+        let own = format!("{}->", self.descriptor);
+        let invokes: Vec<MethodData<'a>> = self
+            .methods()
+            .iter()
+            .filter(|method| method.is_method())
+            .flat_map(|method| method.invokes())
+            .filter(|method| !method.descriptor().starts_with(&own))
+            .collect();
+        let host = self.lambda_host();
+        invokes
+            .iter()
+            .find(|method| {
+                method
+                    .method_name()
+                    .is_some_and(|name| name.starts_with("lambda$"))
+            })
+            .or_else(|| {
+                let host = host.as_deref()?;
+                invokes
+                    .iter()
+                    .find(|method| method.descriptor().starts_with(host))
+            })
+            .or_else(|| invokes.first())
+            .cloned()
+    }
+
+    /// get the methods creating instances of this lambda class: the callers of its constructors
+    /// and, for lambdas without captured values, the readers of its singleton instance field.
+    /// Empty if not a lambda class
+    pub fn lambda_creators(&self) -> MethodDataList<'a> {
+        if !self.is_lambda() {
+            return MethodDataList::new();
+        }
+        let own = format!("{}->", self.descriptor);
+        let constructor_callers = self
+            .methods()
+            .iter()
+            .filter(|method| method.is_constructor())
+            .flat_map(|method| method.callers())
+            .collect::<Vec<_>>();
+        let instance_readers = self
+            .fields()
+            .iter()
+            .filter(|field| {
+                Modifier::from_bits(field.modifiers()).contains(Modifier::STATIC)
+                    && field.type_name() == self.name()
+            })
+            .flat_map(|field| field.readers())
+            .collect::<Vec<_>>();
+        let mut creators: MethodDataList<'a> = constructor_callers
+            .into_iter()
+            .chain(instance_readers)
+            .filter(|method| !method.descriptor().starts_with(&own))
+            .collect();
+        creators.dedup();
+        creators
+    }
+
+    /// is an enum class, `ACC_ENUM` is set
+    pub fn is_enum(&self) -> bool {
+        Modifier::from_bits(self.modifiers()).contains(Modifier::ENUM)
+    }
+
+    /// get the constants of an enum class in ordinal order, with the names recovered from
+    /// the `<init>(name, ordinal)` calls of its `<clinit>`. Empty if not an enum class
+    pub fn enum_constants(&self) -> Vec<EnumConstant<'a>> {
+        if !self.is_enum() {
+            return Vec::new();
+        }
+        let Some(class_def) = self.raw_class_def() else {
+            return Vec::new();
+        };
+        let items = self
+            .bridge
+            .any_dex_file(self.dex_id)
+            .and_then(|dex| dex.enum_constants(&class_def))
+            .unwrap_or_default();
+        let fields = self.fields();
+        items
+            .into_iter()
+            .filter_map(|item| {
+                let field = fields.iter().find(|field| field.id() == item.field_idx)?;
+                Some(EnumConstant::new(field.clone(), item.ordinal, item.name))
+            })
+            .collect()
+    }
+
+    /// is an annotation class, `ACC_ANNOTATION` is set
+    pub fn is_annotation(&self) -> bool {
+        Modifier::from_bits(self.modifiers()).contains(Modifier::ANNOTATION)
+    }
+
+    /// get the retention, targets and element defaults of an annotation class,
+    /// None if not an annotation class
+    pub fn annotation_class_info(&self) -> Option<AnnotationClassInfo<'a>> {
+        match self.is_annotation() {
+            true => Some(AnnotationClassInfo::from_class(self.clone())),
+            false => None,
+        }
+    }
+
+    /// internal use, the class a lambda class is named after,
+    /// e.g. "Lcom/example/Foo;" for "Lcom/example/Foo$$ExternalSyntheticLambda0;"
+    /// or "Lcom/example/-$$Lambda$Foo$xyz;"
+    fn lambda_host(&self) -> Option<String> {
+        if let Some((host, _)) = self.descriptor.split_once("$$ExternalSyntheticLambda") {
+            return Some(format!("{};", host));
+        }
+        let (package, name) = self.descriptor.split_once("-$$Lambda$")?;
+        let (host, _) = name.rsplit_once('$')?;
+        Some(format!("{}{};", package, host))
+    }
+
+    /// internal use, the system annotation of the given type
+    fn system_annotation(&self, descriptor: &str) -> Option<AnnotationData<'a>> {
+        self.annotations()
+            .into_iter()
+            .find(|annotation| annotation.is_type(descriptor))
+    }
+
     /// convert to `DexClass`, None if parse failed
     pub fn to_dex_type(&self) -> Option<DexClass> {
         self.get_dex_class().cloned()
@@ -183,6 +502,57 @@ impl<'a> ClassData<'a> {
             .as_ref()
     }
 
+    /// internal use, the class_def in the dex parsed on the Rust side
+    fn raw_class_def(&self) -> Option<ClassDef> {
+        self.bridge
+            .any_dex_file(self.dex_id)
+            .and_then(|dex| dex.class_def_by_type(self.id))
+            .ok()
+            .flatten()
+    }
+
+    /// internal use, a class of a dex parsed on the Rust side, e.g. a library class
+    pub(crate) fn from_dex_file(
+        bridge: &'a DexkitBridge,
+        dex: &DexFile,
+        class_def: &ClassDef,
+    ) -> Result<Self, Error> {
+        let class_data = dex.class_data(class_def)?.unwrap_or_default();
+        let source_file = match class_def.source_file_idx {
+            Some(idx) => dex.string(idx)?,
+            None => String::new(),
+        };
+        Ok(Self {
+            bridge,
+            id: class_def.class_idx,
+            dex_id: dex.dex_id(),
+            source_file,
+            modifiers: class_def.access_flags,
+            descriptor: dex.type_descriptor(class_def.class_idx)?,
+            super_class_id: class_def.superclass_idx,
+            interface_ids: dex
+                .class_interfaces(class_def)?
+                .into_iter()
+                .map(|id| id as i32)
+                .collect(),
+            method_ids: class_data
+                .methods()
+                .map(|method| method.method_idx as i32)
+                .collect(),
+            field_ids: class_data
+                .fields()
+                .map(|field| field.field_idx as i32)
+                .collect(),
+            // Lazy loaded fields
+            dex_class: OnceCell::new(),
+            super_class: OnceCell::new(),
+            interfaces: OnceCell::new(),
+            methods: OnceCell::new(),
+            fields: OnceCell::new(),
+            annotations: OnceCell::new(),
+        })
+    }
+
     /// ...
     pub(crate) fn with_meta(bridge: &'a DexkitBridge, meta: FBClassMeta<'a>) -> Self {
         let id = meta.id();
@@ -229,3 +599,312 @@ impl<'a> ClassData<'a> {
             .ok()
     }
 }
+
+#[cfg(test)]
+impl<'a> ClassData<'a> {
+    /// internal use, a class without native meta data, e.g. "Lcom/example/Foo;"
+    pub(crate) fn for_test(
+        bridge: &'a DexkitBridge,
+        dex_id: u32,
+        id: u32,
+        descriptor: &str,
+    ) -> Self {
+        Self {
+            bridge,
+            id,
+            dex_id,
+            source_file: String::new(),
+            modifiers: 0,
+            descriptor: descriptor.to_string(),
+            super_class_id: None,
+            interface_ids: Vec::new(),
+            method_ids: Vec::new(),
+            field_ids: Vec::new(),
+            // Lazy loaded fields
+            dex_class: OnceCell::new(),
+            super_class: OnceCell::new(),
+            interfaces: OnceCell::new(),
+            methods: OnceCell::new(),
+            fields: OnceCell::new(),
+            annotations: OnceCell::new(),
+        }
+    }
+
+    /// internal use, set the annotations instead of loading them from native
+    pub(crate) fn with_test_annotations(self, annotations: Vec<AnnotationData<'a>>) -> Self {
+        self.annotations.set(annotations).unwrap();
+        self
+    }
+
+    /// internal use, set the methods instead of loading them from native
+    pub(crate) fn with_test_methods(self, methods: Vec<MethodData<'a>>) -> Self {
+        self.methods.set(methods.into_iter().collect()).unwrap();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{AnnotationEncodeArrayData, AnnotationEncodeValue, EncodeValueData};
+
+    const D8_LAMBDA: &str = "Lcom/example/Foo$$ExternalSyntheticLambda0;";
+
+    fn lambda_class<'a>(
+        bridge: &'a DexkitBridge,
+        descriptor: &str,
+        methods: Vec<MethodData<'a>>,
+        fields: Vec<FieldData<'a>>,
+    ) -> ClassData<'a> {
+        let class = ClassData::for_test(bridge, 0, 1, descriptor);
+        class.methods.set(methods.into_iter().collect()).unwrap();
+        class.fields.set(fields.into_iter().collect()).unwrap();
+        class
+    }
+
+    #[test]
+    fn test_top_level_class() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let class = ClassData::for_test(&bridge, 0, 1, "Lcom/example/Foo;")
+            .with_test_annotations(Vec::new());
+        assert!(class.outer_class().is_none());
+        assert!(class.inner_classes().is_empty());
+        assert!(class.enclosing_method().is_none());
+        assert!(!class.is_anonymous());
+        assert!(class.original_inner_name().is_none());
+    }
+
+    #[test]
+    fn test_member_class() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let outer = ClassData::for_test(&bridge, 0, 1, "Lcom/example/a;");
+        let inner =
+            ClassData::for_test(&bridge, 0, 2, "Lcom/example/a$b;").with_test_annotations(vec![
+                AnnotationData::for_test(
+                    &bridge,
+                    ENCLOSING_CLASS,
+                    vec![("value", EncodeValueData::Type(outer))],
+                ),
+                AnnotationData::for_test(
+                    &bridge,
+                    INNER_CLASS,
+                    vec![
+                        ("accessFlags", EncodeValueData::Int(0x19)),
+                        ("name", EncodeValueData::String("Builder".to_string())),
+                    ],
+                ),
+            ]);
+        assert_eq!(
+            inner
+                .outer_class()
+                .map(|class| class.descriptor())
+                .as_deref(),
+            Some("Lcom/example/a;")
+        );
+        assert!(!inner.is_anonymous());
+        assert_eq!(inner.original_inner_name().as_deref(), Some("Builder"));
+    }
+
+    #[test]
+    fn test_anonymous_class() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let method = MethodData::for_test(&bridge, 0, 3, "Lcom/example/a;->run()V");
+        let anonymous = ClassData::for_test(&bridge, 0, 4, "Lcom/example/a$1;")
+            .with_test_annotations(vec![
+                AnnotationData::for_test(
+                    &bridge,
+                    ENCLOSING_METHOD,
+                    vec![("value", EncodeValueData::Method(method))],
+                ),
+                AnnotationData::for_test(
+                    &bridge,
+                    INNER_CLASS,
+                    vec![
+                        ("accessFlags", EncodeValueData::Int(0)),
+                        ("name", EncodeValueData::Null),
+                    ],
+                ),
+            ]);
+        assert_eq!(
+            anonymous
+                .enclosing_method()
+                .map(|method| method.descriptor())
+                .as_deref(),
+            Some("Lcom/example/a;->run()V")
+        );
+        assert!(anonymous.is_anonymous());
+        assert!(anonymous.original_inner_name().is_none());
+    }
+
+    #[test]
+    fn test_inner_classes() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let members = ["Lcom/example/a$b;", "Lcom/example/a$c;"]
+            .iter()
+            .enumerate()
+            .map(|(i, descriptor)| {
+                AnnotationEncodeValue::for_test(EncodeValueData::Type(ClassData::for_test(
+                    &bridge,
+                    0,
+                    i as u32 + 2,
+                    descriptor,
+                )))
+            })
+            .collect();
+        let outer =
+            ClassData::for_test(&bridge, 0, 1, "Lcom/example/a;").with_test_annotations(vec![
+                AnnotationData::for_test(
+                    &bridge,
+                    MEMBER_CLASSES,
+                    vec![(
+                        "value",
+                        EncodeValueData::Array(AnnotationEncodeArrayData::from_values(
+                            &bridge, members,
+                        )),
+                    )],
+                ),
+            ]);
+        let inner = outer.inner_classes();
+        assert_eq!(
+            inner
+                .iter()
+                .map(|class| class.descriptor())
+                .collect::<Vec<_>>(),
+            ["Lcom/example/a$b;", "Lcom/example/a$c;"]
+        );
+        assert!(outer.outer_class().is_none());
+    }
+
+    #[test]
+    fn test_lambda_host() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        for (descriptor, host) in [
+            (D8_LAMBDA, Some("Lcom/example/Foo;")),
+            (
+                "Lcom/example/-$$Lambda$Foo$Xy3kQ;",
+                Some("Lcom/example/Foo;"),
+            ),
+            ("Lcom/example/Foo$1;", None),
+        ] {
+            let class = ClassData::for_test(&bridge, 0, 1, descriptor);
+            assert_eq!(class.lambda_host().as_deref(), host, "{}", descriptor);
+            assert_eq!(class.is_lambda(), host.is_some(), "{}", descriptor);
+        }
+
+        // renamed by an obfuscator, recognized by its flags and single interface
+        let mut renamed = ClassData::for_test(&bridge, 0, 2, "La/b;");
+        renamed.modifiers = (Modifier::SYNTHETIC | Modifier::FINAL).bits();
+        renamed.interface_ids = vec![3];
+        assert!(renamed.lambda_host().is_none());
+        assert!(renamed.is_lambda());
+        renamed.interface_ids = vec![3, 4];
+        assert!(!renamed.is_lambda());
+    }
+
+    #[test]
+    fn test_lambda_body() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let method = |id, descriptor| MethodData::for_test(&bridge, 0, id, descriptor);
+        let lambda = lambda_class(
+            &bridge,
+            D8_LAMBDA,
+            vec![
+                method(10, "Lcom/example/Foo$$ExternalSyntheticLambda0;-><init>()V"),
+                method(11, "Lcom/example/Foo$$ExternalSyntheticLambda0;->run()V")
+                    .with_test_invokes(vec![
+                        method(12, "Lcom/example/Foo$$ExternalSyntheticLambda0;->get()V"),
+                        method(13, "Lkotlin/jvm/internal/Intrinsics;->check()V"),
+                        method(14, "Lcom/example/Foo;->lambda$onCreate$0()V"),
+                    ]),
+            ],
+            Vec::new(),
+        );
+        assert_eq!(
+            lambda
+                .lambda_body()
+                .map(|method| method.descriptor())
+                .as_deref(),
+            Some("Lcom/example/Foo;->lambda$onCreate$0()V")
+        );
+
+        // a method reference invokes the referenced method of the host directly
+        let reference = lambda_class(
+            &bridge,
+            D8_LAMBDA,
+            vec![
+                method(11, "Lcom/example/Foo$$ExternalSyntheticLambda0;->run()V")
+                    .with_test_invokes(vec![
+                        method(13, "Lkotlin/jvm/internal/Intrinsics;->check()V"),
+                        method(15, "Lcom/example/Foo;->refresh()V"),
+                    ]),
+            ],
+            Vec::new(),
+        );
+        assert_eq!(
+            reference
+                .lambda_body()
+                .map(|method| method.descriptor())
+                .as_deref(),
+            Some("Lcom/example/Foo;->refresh()V")
+        );
+
+        let plain = ClassData::for_test(&bridge, 0, 2, "Lcom/example/Foo$1;");
+        assert!(plain.lambda_body().is_none());
+    }
+
+    #[test]
+    fn test_lambda_creators() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let method = |id, descriptor| MethodData::for_test(&bridge, 0, id, descriptor);
+        let on_create = method(20, "Lcom/example/Foo;->onCreate()V");
+        let lambda = lambda_class(
+            &bridge,
+            D8_LAMBDA,
+            vec![
+                method(10, "Lcom/example/Foo$$ExternalSyntheticLambda0;-><init>()V")
+                    .with_test_callers(vec![
+                        on_create.clone(),
+                        method(
+                            12,
+                            "Lcom/example/Foo$$ExternalSyntheticLambda0;-><clinit>()V",
+                        ),
+                    ]),
+                method(11, "Lcom/example/Foo$$ExternalSyntheticLambda0;->run()V"),
+            ],
+            vec![
+                FieldData::for_test(
+                    &bridge,
+                    0,
+                    30,
+                    "Lcom/example/Foo$$ExternalSyntheticLambda0;->INSTANCE:Lcom/example/Foo$$ExternalSyntheticLambda0;",
+                )
+                .with_test_modifiers((Modifier::STATIC | Modifier::FINAL).bits())
+                .with_test_readers(vec![
+                    on_create,
+                    method(21, "Lcom/example/Bar;->onStart()V"),
+                ]),
+                FieldData::for_test(
+                    &bridge,
+                    0,
+                    31,
+                    "Lcom/example/Foo$$ExternalSyntheticLambda0;->f$0:Lcom/example/Foo;",
+                )
+                .with_test_modifiers(Modifier::FINAL.bits()),
+            ],
+        );
+        assert_eq!(
+            lambda
+                .lambda_creators()
+                .iter()
+                .map(|method| method.descriptor())
+                .collect::<Vec<_>>(),
+            [
+                "Lcom/example/Foo;->onCreate()V",
+                "Lcom/example/Bar;->onStart()V"
+            ]
+        );
+
+        let plain = ClassData::for_test(&bridge, 0, 2, "Lcom/example/Foo$1;");
+        assert!(plain.lambda_creators().is_empty());
+    }
+}
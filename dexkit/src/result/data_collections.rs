@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::DexkitBridge;
 use crate::gen_flatbuffers::dexkit::schema::{
@@ -121,6 +121,40 @@ impl<'a> ClassDataList<'a> {
         class_data_list
     }
 
+    /// internal use, decode the class at `index` of a native result buffer while the native
+    /// search filters it, see `DexkitBridge::find_class_filtered`
+    pub(crate) fn item_at(
+        bridge: &'a DexkitBridge,
+        data: &'a [u8],
+        index: u32,
+    ) -> Option<ClassData<'a>> {
+        // SAFETY: the native filter verifies the buffer before the first call back
+        let class_meta_list =
+            unsafe { flatbuffers::root_unchecked::<FBClassMetaArrayHolder>(data) };
+        let classes = class_meta_list.classes()?;
+        ((index as usize) < classes.len())
+            .then(|| ClassData::with_meta(bridge, classes.get(index as usize)))
+    }
+
+    /// internal use, decode the classes at `indexes` of a native result buffer
+    pub(crate) fn from_data_at(
+        bridge: &'a DexkitBridge,
+        data: &'a [u8],
+        indexes: &[u32],
+    ) -> ClassDataList<'a> {
+        let Some(classes) = flatbuffers::root::<FBClassMetaArrayHolder>(data)
+            .ok()
+            .and_then(|class_meta_list| class_meta_list.classes())
+        else {
+            return Self::new();
+        };
+        indexes
+            .iter()
+            .filter(|&&index| (index as usize) < classes.len())
+            .map(|&index| ClassData::with_meta(bridge, classes.get(index as usize)))
+            .collect()
+    }
+
     /// ...
     pub(crate) fn from_batch_data(
         bridge: &'a DexkitBridge,
@@ -231,6 +265,40 @@ impl<'a> MethodDataList<'a> {
         method_data_list
     }
 
+    /// internal use, decode the method at `index` of a native result buffer while the native
+    /// search filters it, see `DexkitBridge::find_method_filtered`
+    pub(crate) fn item_at(
+        bridge: &'a DexkitBridge,
+        data: &'a [u8],
+        index: u32,
+    ) -> Option<MethodData<'a>> {
+        // SAFETY: the native filter verifies the buffer before the first call back
+        let method_meta_array =
+            unsafe { flatbuffers::root_unchecked::<FBMethodMetaArrayHolder>(data) };
+        let methods = method_meta_array.methods()?;
+        ((index as usize) < methods.len())
+            .then(|| MethodData::with_meta(bridge, methods.get(index as usize)))
+    }
+
+    /// internal use, decode the methods at `indexes` of a native result buffer
+    pub(crate) fn from_data_at(
+        bridge: &'a DexkitBridge,
+        data: &'a [u8],
+        indexes: &[u32],
+    ) -> MethodDataList<'a> {
+        let Some(methods) = flatbuffers::root::<FBMethodMetaArrayHolder>(data)
+            .ok()
+            .and_then(|method_meta_array| method_meta_array.methods())
+        else {
+            return Self::new();
+        };
+        indexes
+            .iter()
+            .filter(|&&index| (index as usize) < methods.len())
+            .map(|&index| MethodData::with_meta(bridge, methods.get(index as usize)))
+            .collect()
+    }
+
     pub(crate) fn from_batch_data(
         bridge: &'a DexkitBridge,
         data: &'a [u8],
@@ -337,3 +405,198 @@ impl<'a> FieldDataList<'a> {
         field_data_list
     }
 }
+
+/// set algebra, ordering and grouping shared by the data lists.
+/// Identity is the encoded id of the data, see `BaseData::get_mine_encode_id`.
+macro_rules! impl_data_list_ops {
+    ($list:ident, $data:ident, $items:ident) => {
+        impl<'a> FromIterator<$data<'a>> for $list<'a> {
+            fn from_iter<I: IntoIterator<Item = $data<'a>>>(iter: I) -> Self {
+                Self {
+                    $items: iter.into_iter().collect(),
+                }
+            }
+        }
+
+        impl<'a> IntoIterator for $list<'a> {
+            type Item = $data<'a>;
+            type IntoIter = std::vec::IntoIter<$data<'a>>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.$items.into_iter()
+            }
+        }
+
+        impl<'a> $list<'a> {
+            /// items of both lists without duplicates, in order of first occurrence
+            pub fn union(&self, other: &$list<'a>) -> $list<'a> {
+                let mut seen = HashSet::new();
+                self.$items
+                    .iter()
+                    .chain(other.$items.iter())
+                    .filter(|data| seen.insert(data.get_mine_encode_id()))
+                    .cloned()
+                    .collect()
+            }
+
+            /// items of this list that are also in `other`
+            pub fn intersect(&self, other: &$list<'a>) -> $list<'a> {
+                let ids: HashSet<i64> = other
+                    .$items
+                    .iter()
+                    .map(|d| d.get_mine_encode_id())
+                    .collect();
+                self.$items
+                    .iter()
+                    .filter(|data| ids.contains(&data.get_mine_encode_id()))
+                    .cloned()
+                    .collect()
+            }
+
+            /// items of this list that are not in `other`
+            pub fn difference(&self, other: &$list<'a>) -> $list<'a> {
+                let ids: HashSet<i64> = other
+                    .$items
+                    .iter()
+                    .map(|d| d.get_mine_encode_id())
+                    .collect();
+                self.$items
+                    .iter()
+                    .filter(|data| !ids.contains(&data.get_mine_encode_id()))
+                    .cloned()
+                    .collect()
+            }
+
+            /// remove duplicates, keeps the first occurrence
+            pub fn dedup(&mut self) {
+                let mut seen = HashSet::new();
+                self.$items
+                    .retain(|data| seen.insert(data.get_mine_encode_id()));
+            }
+
+            /// stable sort by descriptor, equal descriptors (from different dex) are ordered by dex
+            pub fn sort_by_descriptor(&mut self) {
+                self.$items
+                    .sort_by_cached_key(|data| (data.descriptor(), data.get_mine_encode_id()));
+            }
+
+            /// group by package name of the (declared) class, the default package is ""
+            pub fn group_by_package(&self) -> BTreeMap<String, $list<'a>> {
+                let mut groups = BTreeMap::<String, $list<'a>>::new();
+                for data in self.$items.iter() {
+                    groups
+                        .entry(data.package_name().unwrap_or_default())
+                        .or_insert_with($list::new)
+                        .add(data.clone());
+                }
+                groups
+            }
+
+            /// group by the dex the data is declared in
+            pub fn group_by_dex(&self) -> BTreeMap<u32, $list<'a>> {
+                let mut groups = BTreeMap::<u32, $list<'a>>::new();
+                for data in self.$items.iter() {
+                    groups
+                        .entry(data.dex_id())
+                        .or_insert_with($list::new)
+                        .add(data.clone());
+                }
+                groups
+            }
+        }
+    };
+}
+
+impl_data_list_ops!(ClassDataList, ClassData, classes);
+impl_data_list_ops!(MethodDataList, MethodData, methods);
+impl_data_list_ops!(FieldDataList, FieldData, fields);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes<'a>(bridge: &'a DexkitBridge, items: &[(u32, u32, &str)]) -> ClassDataList<'a> {
+        items
+            .iter()
+            .map(|&(dex_id, id, descriptor)| ClassData::for_test(bridge, dex_id, id, descriptor))
+            .collect()
+    }
+
+    fn descriptors(list: &ClassDataList) -> Vec<String> {
+        list.iter().map(|class| class.descriptor()).collect()
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let a = classes(
+            &bridge,
+            &[(0, 1, "La/A;"), (0, 2, "La/B;"), (1, 1, "Lb/C;")],
+        );
+        let b = classes(
+            &bridge,
+            &[(1, 1, "Lb/C;"), (0, 3, "La/D;"), (0, 1, "La/A;")],
+        );
+
+        assert_eq!(
+            descriptors(&a.union(&b)),
+            ["La/A;", "La/B;", "Lb/C;", "La/D;"]
+        );
+        assert_eq!(descriptors(&a.intersect(&b)), ["La/A;", "Lb/C;"]);
+        assert_eq!(descriptors(&a.difference(&b)), ["La/B;"]);
+        assert!(a.difference(&a).is_empty());
+
+        // the same id in another dex is another class
+        let other_dex = classes(&bridge, &[(1, 2, "La/B;")]);
+        assert!(a.intersect(&other_dex).is_empty());
+
+        let mut duplicated = a.union(&b);
+        duplicated.add(ClassData::for_test(&bridge, 0, 2, "La/B;"));
+        duplicated.dedup();
+        assert_eq!(
+            descriptors(&duplicated),
+            ["La/A;", "La/B;", "Lb/C;", "La/D;"]
+        );
+    }
+
+    #[test]
+    fn test_sort_and_group() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let mut list = classes(
+            &bridge,
+            &[
+                (1, 4, "Lcom/b/Z;"),
+                (0, 9, "LTop;"),
+                (1, 2, "Lcom/a/Y;"),
+                (0, 2, "Lcom/a/Y;"),
+                (0, 5, "Lcom/a/X;"),
+            ],
+        );
+        list.sort_by_descriptor();
+        assert_eq!(
+            list.iter()
+                .map(|class| (class.descriptor(), class.dex_id()))
+                .collect::<Vec<_>>(),
+            [
+                ("LTop;".to_string(), 0),
+                ("Lcom/a/X;".to_string(), 0),
+                ("Lcom/a/Y;".to_string(), 0),
+                ("Lcom/a/Y;".to_string(), 1),
+                ("Lcom/b/Z;".to_string(), 1),
+            ]
+        );
+
+        let packages = list.group_by_package();
+        assert_eq!(
+            packages.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["", "com.a", "com.b"]
+        );
+        assert_eq!(descriptors(&packages[""]), ["LTop;"]);
+        assert_eq!(packages["com.a"].len(), 3);
+
+        let dex = list.group_by_dex();
+        assert_eq!(dex.keys().copied().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(descriptors(&dex[&0]), ["LTop;", "Lcom/a/X;", "Lcom/a/Y;"]);
+        assert_eq!(descriptors(&dex[&1]), ["Lcom/a/Y;", "Lcom/b/Z;"]);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::DexkitBridge;
+use crate::result::base::BaseData;
+use crate::result::{ClassData, FieldData, MethodData};
+
+/// default number of classes searched by one native query of a stream
+pub const DEFAULT_STREAM_CHUNK_SIZE: usize = 256;
+
+pub type ClassDataStream<'a> = DataStream<'a, ClassData<'a>>;
+pub type MethodDataStream<'a> = DataStream<'a, MethodData<'a>>;
+pub type FieldDataStream<'a> = DataStream<'a, FieldData<'a>>;
+
+/// Lazy query results.
+///
+/// The search scope is split into chunks of classes and one native query is executed per chunk,
+/// only when the results of the previous chunk are consumed. Dropping the stream (or reaching
+/// the `limit`) stops the search, a chunk that is already running is not interrupted.
+/// Queries with a logical matcher are not split, they are executed at once and the stream
+/// only iterates the materialized results.
+pub struct DataStream<'a, D> {
+    source: Box<dyn FnMut(usize) -> Option<Vec<D>> + 'a>,
+    buffer: std::vec::IntoIter<D>,
+    chunk_size: usize,
+    offset: usize,
+    limit: Option<usize>,
+    finished: bool,
+}
+
+impl<'a, D> DataStream<'a, D> {
+    /// skip the first `offset` results
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// yield at most `limit` results
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(self.limit.map_or(limit, |l| l.min(limit)));
+        self
+    }
+
+    /// number of classes searched by one native query, default `DEFAULT_STREAM_CHUNK_SIZE`
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// get the results of the next non-empty chunk, None if the stream is exhausted
+    pub fn next_chunk(&mut self) -> Option<Vec<D>> {
+        loop {
+            if self.limit == Some(0) {
+                return None;
+            }
+            if self.buffer.as_slice().is_empty() && !self.fill() {
+                return None;
+            }
+
+            let skip = self.offset.min(self.buffer.len());
+            if skip > 0 {
+                self.buffer.nth(skip - 1);
+                self.offset -= skip;
+            }
+            let take = self
+                .limit
+                .map_or(self.buffer.len(), |limit| limit.min(self.buffer.len()));
+            if take == 0 {
+                continue;
+            }
+            if let Some(limit) = self.limit.as_mut() {
+                *limit -= take;
+            }
+            return Some(self.buffer.by_ref().take(take).collect());
+        }
+    }
+
+    /// internal use, create a stream from a chunk source,
+    /// `source(chunk_size)` returns None when the scope is exhausted.
+    pub(crate) fn new(source: Box<dyn FnMut(usize) -> Option<Vec<D>> + 'a>) -> Self {
+        Self {
+            source,
+            buffer: Vec::new().into_iter(),
+            chunk_size: DEFAULT_STREAM_CHUNK_SIZE,
+            offset: 0,
+            limit: None,
+            finished: false,
+        }
+    }
+
+    /// internal use, create a stream over already materialized results
+    pub(crate) fn from_vec(data: Vec<D>) -> Self {
+        let mut stream = Self::new(Box::new(|_| None));
+        stream.buffer = data.into_iter();
+        stream
+    }
+
+    /// internal use, load the next chunk into the buffer
+    fn fill(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        match (self.source)(self.chunk_size) {
+            Some(data) => {
+                self.buffer = data.into_iter();
+                true
+            }
+            None => {
+                self.finished = true;
+                false
+            }
+        }
+    }
+}
+
+impl<'a, D> Iterator for DataStream<'a, D> {
+    type Item = D;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.limit == Some(0) {
+                return None;
+            }
+            if let Some(data) = self.buffer.next() {
+                if self.offset > 0 {
+                    self.offset -= 1;
+                    continue;
+                }
+                if let Some(limit) = self.limit.as_mut() {
+                    *limit -= 1;
+                }
+                return Some(data);
+            }
+            if !self.fill() {
+                return None;
+            }
+        }
+    }
+}
+
+/// internal use, splits the search scope of a stream into chunks of classes
+pub(crate) struct ClassChunks<'a> {
+    bridge: &'a DexkitBridge,
+    state: ChunkState<'a>,
+}
+
+enum ChunkState<'a> {
+    /// the query can not be split, run it once
+    Whole,
+    Classes(std::vec::IntoIter<ClassData<'a>>),
+    /// the class defs of the loaded dex files, read one chunk at a time
+    ClassDefs {
+        dex_id: u32,
+        next: u32,
+    },
+    Done,
+}
+
+impl<'a> ClassChunks<'a> {
+    /// scope of a query, `search_classes` is used if set, otherwise the class defs of every
+    /// dex are walked in order. Package filters stay on the query and are applied natively.
+    /// Library classes are left out, DexKit never loaded them.
+    pub(crate) fn new(
+        bridge: &'a DexkitBridge,
+        search_classes: Option<Vec<ClassData<'a>>>,
+    ) -> Self {
+        let state = match search_classes {
+            Some(mut classes) => {
+                classes.retain(|class| !class.is_library());
+                ChunkState::Classes(classes.into_iter())
+            }
+            None => ChunkState::ClassDefs { dex_id: 0, next: 0 },
+        };
+        Self { bridge, state }
+    }
+
+    /// scope of a query that can not be split, e.g. restricted to single methods
+    pub(crate) fn whole(bridge: &'a DexkitBridge) -> Self {
+        Self {
+            bridge,
+            state: ChunkState::Whole,
+        }
+    }
+
+    /// next chunk, `Some(None)` means the query runs unsplit
+    pub(crate) fn next(&mut self, chunk_size: usize) -> Option<Option<Vec<ClassData<'a>>>> {
+        match std::mem::replace(&mut self.state, ChunkState::Done) {
+            ChunkState::Whole => Some(None),
+            ChunkState::Classes(mut classes) => {
+                let chunk: Vec<ClassData<'a>> = classes.by_ref().take(chunk_size).collect();
+                if chunk.is_empty() {
+                    return None;
+                }
+                self.state = ChunkState::Classes(classes);
+                Some(Some(chunk))
+            }
+            ChunkState::ClassDefs { dex_id, next } => {
+                // the dex files could not be read on the Rust side, search everything at once
+                let Ok(dex_files) = self.bridge.raw_dex_files() else {
+                    return (dex_id == 0 && next == 0).then_some(None);
+                };
+                let dex = dex_files.get(dex_id as usize)?;
+                let end = next
+                    .saturating_add(chunk_size as u32)
+                    .min(dex.class_def_count());
+                if next >= end {
+                    self.state = ChunkState::ClassDefs {
+                        dex_id: dex_id + 1,
+                        next: 0,
+                    };
+                    return self.next(chunk_size);
+                }
+                let ids: Vec<i64> = (next..end)
+                    .filter_map(|idx| dex.class_def(idx).ok())
+                    .map(|class_def| ClassData::get_encode_id(dex_id, class_def.class_idx))
+                    .collect();
+                self.state = ChunkState::ClassDefs { dex_id, next: end };
+                Some(Some(self.bridge.get_type_by_ids(&ids).into()))
+            }
+            ChunkState::Done => None,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::result::FieldData;
+
+/// A constant of an enum class, see `ClassData::enum_constants`.
+#[derive(Debug, Clone)]
+pub struct EnumConstant<'a> {
+    field: FieldData<'a>,
+    ordinal: u32,
+    name: String,
+}
+
+impl<'a> EnumConstant<'a> {
+    /// internal use
+    pub(crate) fn new(field: FieldData<'a>, ordinal: u32, name: String) -> Self {
+        Self {
+            field,
+            ordinal,
+            name,
+        }
+    }
+
+    /// get the static field holding the constant
+    pub fn field(&self) -> &FieldData<'a> {
+        &self.field
+    }
+
+    /// get the ordinal passed to the enum constructor
+    pub fn ordinal(&self) -> u32 {
+        self.ordinal
+    }
+
+    /// get the name passed to the enum constructor, i.e. `name()` at runtime,
+    /// kept when the field itself is obfuscated
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the field was renamed, its name differs from the constant name
+    pub fn is_renamed(&self) -> bool {
+        self.field.name().as_deref() != Some(self.name.as_str())
+    }
+}
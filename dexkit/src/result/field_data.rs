@@ -1,9 +1,13 @@
+use crate::dex::EncodedValue;
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::FieldMeta as FBFieldMeta;
-use crate::result::{AnnotationData, ClassData, MethodDataList};
+use crate::result::{AnnotationData, AnnotationEncodeValue, ClassData, MethodDataList, TypeRef};
+use crate::uitls::{DexSignature, GenericType};
 use crate::wrap::DexField;
 use crate::{DexkitBridge, result::base::BaseData};
 use std::cell::OnceCell;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
 #[allow(unused)]
 #[derive(Clone)]
@@ -51,6 +55,20 @@ impl<'a> BaseData for FieldData<'a> {
     }
 }
 
+impl PartialEq for FieldData<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_mine_encode_id() == other.get_mine_encode_id()
+    }
+}
+
+impl Eq for FieldData<'_> {}
+
+impl Hash for FieldData<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_mine_encode_id().hash(state);
+    }
+}
+
 impl<'a> FieldData<'a> {
     /// modifiers bitmask, see `Modifier`
     pub fn modifiers(&self) -> u32 {
@@ -78,6 +96,11 @@ impl<'a> FieldData<'a> {
         self.class_name()
     }
 
+    /// package name of the declared class, e.g. "com.example"
+    pub fn package_name(&self) -> Option<String> {
+        self.class_name().map(DexSignature::get_package_name)
+    }
+
     /// field name, e.g. "myField"
     pub fn field_name(&self) -> Option<String> {
         self.get_dex_field().map(|f| f.field_name())
@@ -106,6 +129,27 @@ impl<'a> FieldData<'a> {
         cls.as_deref().cloned()
     }
 
+    /// get the field type, `TypeRef::External` for primitives, arrays and classes not defined
+    /// in the apk or an attached library
+    /// # Returns
+    /// * `Err(Error)` - If the field descriptor is malformed
+    pub fn field_type(&self) -> Result<TypeRef<'a>, Error> {
+        let descriptor = TypeRef::field_descriptor(&self.descriptor)?;
+        Ok(TypeRef::from_descriptor(
+            self.bridge,
+            descriptor.to_string(),
+        ))
+    }
+
+    /// get the generic type from the `dalvik.annotation.Signature` of the field,
+    /// e.g. "java.util.List<java.lang.String>", None if the field type is not generic
+    pub fn generic_signature(&self) -> Option<GenericType> {
+        let dex = self.bridge.any_dex_file(self.dex_id).ok()?;
+        let class_def = dex.class_def_by_type(self.class_id).ok()??;
+        let signature = dex.field_signature(&class_def, self.id).ok()??;
+        GenericType::parse(&signature).ok()
+    }
+
     /// get the class of the field type
     pub fn type_class(&self) -> Option<ClassData<'a>> {
         let cls = self.type_class.get_or_init(|| {
@@ -119,6 +163,27 @@ impl<'a> FieldData<'a> {
         cls.as_deref().cloned()
     }
 
+    /// get the value of a static field from the static_values of its class,
+    /// e.g. the constant of a `static final String API_KEY = "..."`.
+    /// Static fields past the end of the static_values hold the default of their type,
+    /// 0, false or null. None for instance fields
+    pub fn static_value(&self) -> Option<AnnotationEncodeValue<'a>> {
+        let dex = self.bridge.any_dex_file(self.dex_id).ok()?;
+        let class_def = dex.class_def_by_type(self.class_id).ok()??;
+        let class_data = dex.class_data(&class_def).ok()??;
+        let position = class_data
+            .static_fields
+            .iter()
+            .position(|field| field.field_idx == self.id)?;
+        let values = dex.static_values(&class_def).ok()?;
+        let value = match values.get(position) {
+            Some(value) => value.clone(),
+            // trailing default values are left out of the static_values
+            None => EncodedValue::default_of(&dex.type_descriptor(self.type_id).ok()?),
+        };
+        AnnotationEncodeValue::from_encoded_value(self.bridge, dex, &value)
+    }
+
     /// get annotations of this class
     pub fn annotations(&self) -> Vec<AnnotationData<'a>> {
         self.annotations
@@ -194,3 +259,42 @@ impl<'a> FieldData<'a> {
             .ok()
     }
 }
+
+#[cfg(test)]
+impl<'a> FieldData<'a> {
+    /// internal use, a field without native meta data, e.g. "Lcom/example/Foo;->name:I"
+    pub(crate) fn for_test(
+        bridge: &'a DexkitBridge,
+        dex_id: u32,
+        id: u32,
+        descriptor: &str,
+    ) -> Self {
+        Self {
+            bridge,
+            id,
+            dex_id,
+            class_id: 0,
+            modifiers: 0,
+            descriptor: descriptor.to_string(),
+            type_id: 0,
+            dex_field: OnceCell::new(),
+            declared_class: OnceCell::new(),
+            type_class: OnceCell::new(),
+            annotations: OnceCell::new(),
+            readers: OnceCell::new(),
+            writers: OnceCell::new(),
+        }
+    }
+
+    /// internal use, set the access flags, e.g. `ACC_STATIC`
+    pub(crate) fn with_test_modifiers(mut self, modifiers: u32) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// internal use, set the reading methods instead of loading them from native
+    pub(crate) fn with_test_readers(self, readers: Vec<MethodData<'a>>) -> Self {
+        self.readers.set(readers.into_iter().collect()).unwrap();
+        self
+    }
+}
@@ -0,0 +1,191 @@
+use crate::errors::FromAnnotationError;
+use crate::result::{AnnotationData, AnnotationEncodeValue, ClassData, FieldData, MethodData};
+
+#[cfg(feature = "derive")]
+pub use dexkit_derive::FromDexAnnotation;
+
+/// A Rust type read from an annotation, usually with `#[derive(FromDexAnnotation)]`
+/// of the `derive` feature:
+///
+/// ```ignore
+/// #[derive(FromDexAnnotation)]
+/// #[dex(annotation = "com.example.Route")]
+/// struct Route<'a> {
+///     path: String,
+///     #[dex(name = "methods")]
+///     http_methods: Vec<HttpMethod>,
+///     handler: Option<ClassData<'a>>,
+/// }
+///
+/// #[derive(FromDexAnnotation)]
+/// enum HttpMethod {
+///     Get,
+///     Post,
+///     #[dex(name = "DELETE")]
+///     Remove,
+/// }
+///
+/// let route = Route::find(&class.annotations()).transpose()?;
+/// ```
+///
+/// Struct fields are read from the element of the same name, `#[dex(name = "...")]` renames it.
+/// A missing element falls back to its default from the annotation class, then to None for
+/// `Option` fields and to `Default::default()` for `#[dex(default)]` fields.
+/// Enums are read from enum constants, by the variant name or its UPPER_SNAKE_CASE form.
+pub trait FromDexAnnotation<'a>: Sized {
+    /// the annotation type descriptor, e.g. "Lcom/example/Route;", None to accept any type
+    fn annotation_descriptor() -> Option<&'static str>;
+
+    /// read the elements of `annotation`, the type is checked by `AnnotationData::extract`
+    fn from_elements(annotation: &AnnotationData<'a>) -> Result<Self, FromAnnotationError>;
+
+    /// check the annotation type and read its elements
+    fn from_annotation(annotation: &AnnotationData<'a>) -> Result<Self, FromAnnotationError> {
+        if let Some(expected) = Self::annotation_descriptor()
+            && !annotation.is_type(expected)
+        {
+            return Err(FromAnnotationError::WrongAnnotation {
+                expected: expected.to_string(),
+                found: annotation.annotation_type().descriptor(),
+            });
+        }
+        Self::from_elements(annotation)
+    }
+
+    /// read the first annotation of the type among `annotations`, None if absent,
+    /// e.g. `Route::find(&method.annotations())`. The first one when no type is declared
+    fn find(annotations: &[AnnotationData<'a>]) -> Option<Result<Self, FromAnnotationError>> {
+        let annotation = match Self::annotation_descriptor() {
+            Some(descriptor) => annotations.iter().find(|a| a.is_type(descriptor))?,
+            None => annotations.first()?,
+        };
+        Some(Self::from_elements(annotation))
+    }
+}
+
+/// A Rust type read from an annotation element value.
+pub trait FromEncodeValue<'a>: Sized {
+    fn from_encode_value(value: &AnnotationEncodeValue<'a>) -> Result<Self, FromAnnotationError>;
+}
+
+impl<'a> AnnotationData<'a> {
+    /// read the annotation into `T`, see `FromDexAnnotation`
+    pub fn extract<T: FromDexAnnotation<'a>>(&self) -> Result<T, FromAnnotationError> {
+        T::from_annotation(self)
+    }
+
+    /// read the element with the given name, falling back to its default value
+    pub fn element<T: FromEncodeValue<'a>>(&self, name: &str) -> Result<T, FromAnnotationError> {
+        self.optional_element(name)?
+            .ok_or_else(|| FromAnnotationError::MissingElement(name.to_string()))
+    }
+
+    /// read the element with the given name, falling back to its default value,
+    /// None if it has neither a value nor a default, or if the value is null
+    pub fn optional_element<T: FromEncodeValue<'a>>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, FromAnnotationError> {
+        let value = match self.element_value(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .type_class()
+                .and_then(|class| class.annotation_class_info())
+                .and_then(|info| info.default_value(name).cloned()),
+        };
+        match value {
+            Some(value) if !value.is_null_value() => T::from_encode_value(&value)
+                .map(Some)
+                .map_err(|e| e.in_element(name)),
+            _ => Ok(None),
+        }
+    }
+}
+
+macro_rules! impl_from_encode_value {
+    ($($ty:ty => $getter:ident, $expected:literal;)*) => {
+        $(
+            impl<'a> FromEncodeValue<'a> for $ty {
+                fn from_encode_value(
+                    value: &AnnotationEncodeValue<'a>,
+                ) -> Result<Self, FromAnnotationError> {
+                    value
+                        .$getter()
+                        .map(Into::into)
+                        .ok_or_else(|| wrong_value_type($expected, value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_encode_value! {
+    i8 => byte_value, "byte";
+    i16 => short_value, "short";
+    char => char_value, "char";
+    i32 => int_value, "int";
+    i64 => long_value, "long";
+    f32 => float_value, "float";
+    f64 => double_value, "double";
+    bool => bool_value, "boolean";
+    String => string_value, "string";
+    ClassData<'a> => type_value, "class";
+    MethodData<'a> => method_value, "method";
+    FieldData<'a> => enum_value, "enum";
+    AnnotationData<'a> => annotation_value, "annotation";
+}
+
+impl<'a> FromEncodeValue<'a> for AnnotationEncodeValue<'a> {
+    fn from_encode_value(value: &AnnotationEncodeValue<'a>) -> Result<Self, FromAnnotationError> {
+        Ok(value.clone())
+    }
+}
+
+impl<'a, T: FromEncodeValue<'a>> FromEncodeValue<'a> for Vec<T> {
+    fn from_encode_value(value: &AnnotationEncodeValue<'a>) -> Result<Self, FromAnnotationError> {
+        let array = value
+            .array_value()
+            .ok_or_else(|| wrong_value_type("array", value))?;
+        array
+            .values()
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                T::from_encode_value(value).map_err(|e| e.in_element(format!("[{}]", i)))
+            })
+            .collect()
+    }
+}
+
+/// internal use, helpers of the derived impls
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    /// the name of an enum constant, for derived enums
+    pub fn enum_constant_name(
+        value: &AnnotationEncodeValue,
+    ) -> Result<String, FromAnnotationError> {
+        value
+            .enum_value()
+            .and_then(|field| field.name())
+            .ok_or_else(|| wrong_value_type("enum", value))
+    }
+
+    /// the value of a nested annotation, for derived structs
+    pub fn nested_annotation<'a, T: FromDexAnnotation<'a>>(
+        value: &AnnotationEncodeValue<'a>,
+    ) -> Result<T, FromAnnotationError> {
+        let annotation = value
+            .annotation_value()
+            .ok_or_else(|| wrong_value_type("annotation", value))?;
+        T::from_annotation(&annotation)
+    }
+}
+
+fn wrong_value_type(expected: &'static str, value: &AnnotationEncodeValue) -> FromAnnotationError {
+    FromAnnotationError::WrongValueType {
+        expected,
+        found: value.value_type(),
+    }
+}
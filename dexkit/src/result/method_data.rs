@@ -1,10 +1,17 @@
+use crate::dex::{CodeItem, DebugInfo, DexFile, TryBlock};
+use crate::errors::Error;
 use crate::gen_flatbuffers::dexkit::schema::MethodMeta as FBMethodMeta;
-use crate::result::{AnnotationData, ClassData, ClassDataList, MethodDataList, UsingFieldData};
-use crate::uitls::Opcodes;
+use crate::result::{
+    AnnotationData, ClassData, ClassDataList, FieldUsingType, MethodDataList, TypeRef,
+    UsingFieldData, UsingNumber,
+};
+use crate::uitls::{DexSignature, MethodSignature, Modifier, Opcodes};
 use crate::wrap::DexMethod;
 use crate::{dexkit_bridge::DexkitBridge, result::base::BaseData};
 use std::cell::OnceCell;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
 
 #[allow(unused)]
 #[derive(Clone)]
@@ -30,6 +37,7 @@ pub struct MethodData<'a> {
     invokes: OnceCell<MethodDataList<'a>>,
     using_strings: OnceCell<Vec<String>>,
     using_fields: OnceCell<Vec<UsingFieldData<'a>>>,
+    using_numbers: OnceCell<Vec<UsingNumber>>,
 }
 
 impl<'a> Debug for MethodData<'a> {
@@ -60,6 +68,20 @@ impl<'a> BaseData for MethodData<'a> {
     }
 }
 
+impl PartialEq for MethodData<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_mine_encode_id() == other.get_mine_encode_id()
+    }
+}
+
+impl Eq for MethodData<'_> {}
+
+impl Hash for MethodData<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get_mine_encode_id().hash(state);
+    }
+}
+
 impl<'a> MethodData<'a> {
     /// modifiers bitmask, see `Modifier`
     pub fn modifiers(&self) -> u32 {
@@ -86,6 +108,11 @@ impl<'a> MethodData<'a> {
         self.class_name()
     }
 
+    /// package name of the declared class, e.g. "com.example"
+    pub fn package_name(&self) -> Option<String> {
+        self.class_name().map(DexSignature::get_package_name)
+    }
+
     /// method name, e.g. "myMethod"
     pub fn method_name(&self) -> Option<String> {
         self.get_dex_method().map(|m| m.method_name())
@@ -170,6 +197,105 @@ impl<'a> MethodData<'a> {
             .clone()
     }
 
+    /// get the return type, `TypeRef::External` for primitives, arrays and classes not defined
+    /// in the apk or an attached library
+    /// # Returns
+    /// * `Err(Error)` - If the method descriptor is malformed
+    pub fn return_type(&self) -> Result<TypeRef<'a>, Error> {
+        let (_, return_type) = TypeRef::proto_descriptors(&self.descriptor)?;
+        Ok(TypeRef::from_descriptor(
+            self.bridge,
+            return_type.to_string(),
+        ))
+    }
+
+    /// get the parameter types, unlike `param_types` types outside the apk are kept
+    /// # Returns
+    /// * `Err(Error)` - If the method descriptor is malformed, no parameter is dropped
+    pub fn param_type_refs(&self) -> Result<Vec<TypeRef<'a>>, Error> {
+        let (params, _) = TypeRef::proto_descriptors(&self.descriptor)?;
+        Ok(params
+            .into_iter()
+            .map(|descriptor| TypeRef::from_descriptor(self.bridge, descriptor.to_string()))
+            .collect())
+    }
+
+    /// get the generic signature from the `dalvik.annotation.Signature` of the method,
+    /// None if the method is not generic
+    pub fn generic_signature(&self) -> Option<MethodSignature> {
+        let dex = self.bridge.any_dex_file(self.dex_id).ok()?;
+        let class_def = dex.class_def_by_type(self.class_id).ok()??;
+        let signature = dex.method_signature(&class_def, self.id).ok()??;
+        MethodSignature::parse(&signature).ok()
+    }
+
+    /// get the debug_info_item of the method: line table, local variables and parameter names.
+    /// None for abstract and native methods, or when stripped
+    pub fn debug_info(&self) -> Option<DebugInfo> {
+        let (dex, code) = self.raw_code()?;
+        dex.debug_info(&code).ok()?
+    }
+
+    /// get the lowest to the highest source line of the method, None without line table
+    pub fn line_range(&self) -> Option<RangeInclusive<u32>> {
+        self.debug_info()?.line_range()
+    }
+
+    /// get the try blocks of the method body with their handlers, empty without code
+    pub fn try_blocks(&self) -> Vec<TryBlock> {
+        self.raw_code()
+            .and_then(|(dex, code)| dex.try_blocks(&code).ok())
+            .unwrap_or_default()
+    }
+
+    /// get the exception types caught by the try blocks, without duplicates,
+    /// e.g. "javax.net.ssl.SSLPeerUnverifiedException"
+    pub fn catch_types(&self) -> Vec<TypeRef<'a>> {
+        self.type_refs(self.catch_type_descriptors())
+    }
+
+    /// get the exception types of the `dalvik.annotation.Throws` of the method,
+    /// the `throws` clause of the source
+    pub fn throws_types(&self) -> Vec<TypeRef<'a>> {
+        self.type_refs(self.throws_type_descriptors())
+    }
+
+    /// get the exception types created and thrown by the method body,
+    /// e.g. "java.lang.SecurityException" for `throw new SecurityException(...)`
+    pub fn thrown_types(&self) -> Vec<TypeRef<'a>> {
+        self.type_refs(self.thrown_type_descriptors())
+    }
+
+    /// internal use, descriptors of `catch_types`
+    pub(crate) fn catch_type_descriptors(&self) -> Vec<String> {
+        let mut descriptors: Vec<String> = Vec::new();
+        for (descriptor, _) in self.try_blocks().into_iter().flat_map(|b| b.catches) {
+            if !descriptors.contains(&descriptor) {
+                descriptors.push(descriptor);
+            }
+        }
+        descriptors
+    }
+
+    /// internal use, descriptors of `throws_types`
+    pub(crate) fn throws_type_descriptors(&self) -> Vec<String> {
+        let Ok(dex) = self.bridge.any_dex_file(self.dex_id) else {
+            return Vec::new();
+        };
+        dex.class_def_by_type(self.class_id)
+            .ok()
+            .flatten()
+            .and_then(|class_def| dex.method_throws(&class_def, self.id).ok())
+            .unwrap_or_default()
+    }
+
+    /// internal use, descriptors of `thrown_types`
+    pub(crate) fn thrown_type_descriptors(&self) -> Vec<String> {
+        self.raw_code()
+            .and_then(|(dex, code)| dex.thrown_new_types(&code).ok())
+            .unwrap_or_default()
+    }
+
     /// get the parameter names, may be None if not available
     pub fn param_names(&self) -> Option<Vec<Option<String>>> {
         self.param_names
@@ -234,6 +360,33 @@ impl<'a> MethodData<'a> {
             .clone()
     }
 
+    /// get the lambda classes this method creates: constructed, or read from the singleton
+    /// instance field of lambdas without captured values, see `ClassData::is_lambda`
+    pub fn created_lambdas(&self) -> ClassDataList<'a> {
+        let constructed = self
+            .invokes()
+            .iter()
+            .filter(|method| method.is_constructor())
+            .filter_map(|method| method.declared_class())
+            .collect::<Vec<_>>();
+        let singletons = self
+            .using_fields()
+            .iter()
+            .filter(|using| matches!(using.using_type(), FieldUsingType::Read))
+            .map(|using| using.field())
+            .filter(|field| Modifier::from_bits(field.modifiers()).contains(Modifier::STATIC))
+            .filter(|field| field.type_name() == field.class_name())
+            .filter_map(|field| field.declared_class())
+            .collect::<Vec<_>>();
+        let mut lambdas: ClassDataList<'a> = constructed
+            .into_iter()
+            .chain(singletons)
+            .filter(|class| class.is_lambda())
+            .collect();
+        lambdas.dedup();
+        lambdas
+    }
+
     /// get the string literals used in this method
     pub fn using_strings(&self) -> Vec<String> {
         self.using_strings
@@ -254,11 +407,40 @@ impl<'a> MethodData<'a> {
             .clone()
     }
 
+    /// get the number literals loaded in this method, distinct and in order of appearance,
+    /// empty if the method has no code or its dex can not be read.
+    /// A literal that may be a floating point number is reported in both readings, see `UsingNumber`
+    pub fn using_numbers(&self) -> Vec<UsingNumber> {
+        self.using_numbers
+            .get_or_init(|| {
+                let encode_id = Self::get_encode_id(self.dex_id, self.id);
+                self.bridge
+                    .get_method_using_numbers(encode_id)
+                    .unwrap_or_default()
+            })
+            .clone()
+    }
+
     /// get the wrapped DexMethod
     pub fn to_dex_method(&self) -> Option<DexMethod> {
         self.get_dex_method().cloned()
     }
 
+    /// internal use, the dex file and the code_item of the method, None without code
+    fn raw_code(&self) -> Option<(&'a DexFile, CodeItem)> {
+        let dex = self.bridge.any_dex_file(self.dex_id).ok()?;
+        let code = dex.method_code(self.id).ok()??;
+        Some((dex, code))
+    }
+
+    /// internal use, resolve type descriptors
+    fn type_refs(&self, descriptors: Vec<String>) -> Vec<TypeRef<'a>> {
+        descriptors
+            .into_iter()
+            .map(|descriptor| TypeRef::from_descriptor(self.bridge, descriptor))
+            .collect()
+    }
+
     /// internal use, get the wrapped DexMethod
     pub(crate) fn get_dex_method(&self) -> Option<&DexMethod> {
         self.dex_method
@@ -299,6 +481,7 @@ impl<'a> MethodData<'a> {
             invokes: OnceCell::new(),
             using_strings: OnceCell::new(),
             using_fields: OnceCell::new(),
+            using_numbers: OnceCell::new(),
         }
     }
 
@@ -309,3 +492,56 @@ impl<'a> MethodData<'a> {
             .ok()
     }
 }
+
+#[cfg(test)]
+impl<'a> MethodData<'a> {
+    /// internal use, a method without native meta data, e.g. "Lcom/example/Foo;->run()V"
+    pub(crate) fn for_test(
+        bridge: &'a DexkitBridge,
+        dex_id: u32,
+        id: u32,
+        descriptor: &str,
+    ) -> Self {
+        Self {
+            bridge,
+            id,
+            dex_id,
+            class_id: 0,
+            modifiers: 0,
+            descriptor: descriptor.to_string(),
+            return_type_id: 0,
+            param_type_ids: Vec::new(),
+            dex_method: OnceCell::new(),
+            declared_class: OnceCell::new(),
+            return_type_class: OnceCell::new(),
+            param_types: OnceCell::new(),
+            param_names: OnceCell::new(),
+            annotations: OnceCell::new(),
+            param_annotations: OnceCell::new(),
+            op_codes: OnceCell::new(),
+            callers: OnceCell::new(),
+            invokes: OnceCell::new(),
+            using_strings: OnceCell::new(),
+            using_fields: OnceCell::new(),
+            using_numbers: OnceCell::new(),
+        }
+    }
+
+    /// internal use, set the access flags, e.g. `ACC_ABSTRACT`
+    pub(crate) fn with_test_modifiers(mut self, modifiers: u32) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// internal use, set the invoked methods instead of loading them from native
+    pub(crate) fn with_test_invokes(self, invokes: Vec<MethodData<'a>>) -> Self {
+        self.invokes.set(invokes.into_iter().collect()).unwrap();
+        self
+    }
+
+    /// internal use, set the callers instead of loading them from native
+    pub(crate) fn with_test_callers(self, callers: Vec<MethodData<'a>>) -> Self {
+        self.callers.set(callers.into_iter().collect()).unwrap();
+        self
+    }
+}
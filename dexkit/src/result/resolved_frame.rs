@@ -0,0 +1,77 @@
+use crate::DexkitBridge;
+use crate::result::{BaseDataList, ClassData, MethodData, MethodDataList};
+use crate::uitls::StackFrame;
+
+/// A stack trace frame mapped to the apk, see `DexkitBridge::resolve_stack_trace`.
+#[derive(Debug, Clone)]
+pub struct ResolvedFrame<'a> {
+    frame: StackFrame,
+    class: Option<ClassData<'a>>,
+    methods: MethodDataList<'a>,
+}
+
+impl<'a> ResolvedFrame<'a> {
+    /// get the parsed frame
+    pub fn frame(&self) -> &StackFrame {
+        &self.frame
+    }
+
+    /// get the class of the frame, None if not defined in the apk or an attached library
+    pub fn class(&self) -> Option<&ClassData<'a>> {
+        self.class.as_ref()
+    }
+
+    /// get the candidate methods, the overloads whose line range contains the frame line
+    /// when the line table tells them apart
+    pub fn methods(&self) -> &MethodDataList<'a> {
+        &self.methods
+    }
+
+    /// get the method if the frame resolves to a single candidate
+    pub fn method(&self) -> Option<&MethodData<'a>> {
+        self.methods.single()
+    }
+
+    /// resolved to a single method
+    pub fn is_resolved(&self) -> bool {
+        self.method().is_some()
+    }
+
+    /// internal use, look up the class and the methods of a frame
+    pub(crate) fn resolve(bridge: &'a DexkitBridge, frame: StackFrame) -> Self {
+        let class = bridge.get_class_data(&frame.class_name);
+        let mut methods: MethodDataList<'a> = class
+            .iter()
+            .flat_map(|class| class.methods())
+            .filter(|method| method.name().as_deref() == Some(frame.method_name.as_str()))
+            .collect();
+        if let Some(line) = frame.line_number
+            && methods.len() > 1
+        {
+            let in_range: MethodDataList<'a> = methods
+                .iter()
+                .filter(|method| {
+                    method
+                        .line_range()
+                        .is_some_and(|range| range.contains(&line))
+                })
+                .cloned()
+                .collect();
+            let on_line: MethodDataList<'a> = in_range
+                .iter()
+                .filter(|method| method.debug_info().is_some_and(|info| info.has_line(line)))
+                .cloned()
+                .collect();
+            if !on_line.is_empty() {
+                methods = on_line;
+            } else if !in_range.is_empty() {
+                methods = in_range;
+            }
+        }
+        Self {
+            frame,
+            class,
+            methods,
+        }
+    }
+}
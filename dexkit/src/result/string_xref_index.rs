@@ -0,0 +1,246 @@
+use crate::DexkitBridge;
+use crate::dex::DexFile;
+use crate::errors::Error;
+use crate::result::base::BaseData;
+use crate::result::{
+    ClassData, ClassDataList, FieldData, FieldDataList, MethodData, MethodDataList,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The classes, methods and fields referencing one string.
+#[derive(Debug, Clone)]
+pub struct StringXref<'a> {
+    bridge: &'a DexkitBridge,
+    value: String,
+    usage_count: usize,
+    class_ids: Vec<i64>,
+    method_ids: Vec<i64>,
+    field_ids: Vec<i64>,
+}
+
+impl<'a> StringXref<'a> {
+    /// the referenced string
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// number of references, each const-string instruction and static field initializer counts once
+    pub fn usage_count(&self) -> usize {
+        self.usage_count
+    }
+
+    /// classes declaring a method or a static field that reference the string
+    pub fn classes(&self) -> ClassDataList<'a> {
+        self.bridge.get_type_by_ids(&self.class_ids)
+    }
+
+    /// methods loading the string with const-string
+    pub fn methods(&self) -> MethodDataList<'a> {
+        self.bridge.get_method_by_ids(&self.method_ids)
+    }
+
+    /// static fields initialized with the string, or with an array containing it
+    pub fn fields(&self) -> FieldDataList<'a> {
+        self.bridge.get_field_by_ids(&self.field_ids)
+    }
+}
+
+#[derive(Debug, Default)]
+struct XrefEntry {
+    usage_count: usize,
+    class_ids: BTreeSet<i64>,
+    method_ids: BTreeSet<i64>,
+    field_ids: BTreeSet<i64>,
+}
+
+/// Cross-reference index from strings to the classes, methods and fields using them.
+///
+/// Built once by scanning the code and static values of every loaded dex,
+/// see `DexkitBridge::string_xref_index`. Only strings referenced as a value are indexed,
+/// strings only used as names or descriptors are listed by `DexkitBridge::strings`.
+#[derive(Debug)]
+pub struct StringXrefIndex<'a> {
+    bridge: &'a DexkitBridge,
+    entries: BTreeMap<String, XrefEntry>,
+}
+
+impl<'a> StringXrefIndex<'a> {
+    /// number of indexed strings
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// indexed strings in ascending order
+    pub fn strings(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// references of exactly `value`
+    pub fn get(&self, value: &str) -> Option<StringXref<'a>> {
+        self.entries
+            .get_key_value(value)
+            .map(|(value, entry)| self.to_xref(value, entry))
+    }
+
+    /// number of references of `value`, 0 if it is not used
+    pub fn usage_count(&self, value: &str) -> usize {
+        self.entries.get(value).map_or(0, |entry| entry.usage_count)
+    }
+
+    /// references of every string containing `keyword`
+    pub fn find_contains(&self, keyword: &str, ignore_case: bool) -> Vec<StringXref<'a>> {
+        let keyword_lower = keyword.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|(value, _)| {
+                if ignore_case {
+                    value.to_lowercase().contains(&keyword_lower)
+                } else {
+                    value.contains(keyword)
+                }
+            })
+            .map(|(value, entry)| self.to_xref(value, entry))
+            .collect()
+    }
+
+    /// references of every string starting with `prefix`
+    pub fn find_prefix(&self, prefix: &str) -> Vec<StringXref<'a>> {
+        self.entries
+            .range::<str, _>(prefix..)
+            .take_while(|(value, _)| value.starts_with(prefix))
+            .map(|(value, entry)| self.to_xref(value, entry))
+            .collect()
+    }
+
+    /// references of every string within `max_distance` edits (Levenshtein, per char) of `value`,
+    /// closest first
+    pub fn find_fuzzy(&self, value: &str, max_distance: usize) -> Vec<StringXref<'a>> {
+        let target: Vec<char> = value.chars().collect();
+        let mut matches: Vec<(usize, &String, &XrefEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|(candidate, entry)| {
+                Self::edit_distance(&target, candidate, max_distance)
+                    .map(|distance| (distance, candidate, entry))
+            })
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        matches
+            .into_iter()
+            .map(|(_, value, entry)| self.to_xref(value, entry))
+            .collect()
+    }
+
+    /// the `count` most referenced strings, most used first
+    pub fn most_used(&self, count: usize) -> Vec<StringXref<'a>> {
+        let mut entries: Vec<(&String, &XrefEntry)> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            b.1.usage_count
+                .cmp(&a.1.usage_count)
+                .then_with(|| a.0.cmp(b.0))
+        });
+        entries
+            .into_iter()
+            .take(count)
+            .map(|(value, entry)| self.to_xref(value, entry))
+            .collect()
+    }
+
+    /// internal use, scan all loaded dex files
+    pub(crate) fn build(bridge: &'a DexkitBridge) -> Result<Self, Error> {
+        let mut entries: BTreeMap<String, XrefEntry> = BTreeMap::new();
+        for dex in bridge.raw_dex_files()? {
+            let dex_id = dex.dex_id();
+            let mut strings: HashMap<u32, String> = HashMap::new();
+            for class_def in dex.class_defs()? {
+                let Some(class_data) = dex.class_data(&class_def)? else {
+                    continue;
+                };
+                let class_id = ClassData::get_encode_id(dex_id, class_def.class_idx);
+
+                for method in class_data.methods() {
+                    let Some(code) = dex.code_item(method.code_off)? else {
+                        continue;
+                    };
+                    let method_id = MethodData::get_encode_id(dex_id, method.method_idx);
+                    for string_idx in code.instructions().filter_map(|i| i.string_index()) {
+                        let entry = Self::entry_of(&mut entries, &mut strings, dex, string_idx)?;
+                        entry.usage_count += 1;
+                        entry.class_ids.insert(class_id);
+                        entry.method_ids.insert(method_id);
+                    }
+                }
+
+                let static_values = dex.static_values(&class_def)?;
+                for (field, value) in class_data.static_fields.iter().zip(static_values.iter()) {
+                    let field_id = FieldData::get_encode_id(dex_id, field.field_idx);
+                    for string_idx in value.string_indexes() {
+                        let entry = Self::entry_of(&mut entries, &mut strings, dex, string_idx)?;
+                        entry.usage_count += 1;
+                        entry.class_ids.insert(class_id);
+                        entry.field_ids.insert(field_id);
+                    }
+                }
+            }
+        }
+        Ok(Self { bridge, entries })
+    }
+
+    /// internal use, entry of the string at `idx`, strings are decoded once per dex
+    fn entry_of<'e>(
+        entries: &'e mut BTreeMap<String, XrefEntry>,
+        strings: &mut HashMap<u32, String>,
+        dex: &DexFile,
+        idx: u32,
+    ) -> Result<&'e mut XrefEntry, Error> {
+        let value = match strings.get(&idx) {
+            Some(value) => value.clone(),
+            None => {
+                let value = dex.string(idx)?;
+                strings.insert(idx, value.clone());
+                value
+            }
+        };
+        Ok(entries.entry(value).or_default())
+    }
+
+    /// internal use
+    fn to_xref(&self, value: &str, entry: &XrefEntry) -> StringXref<'a> {
+        StringXref {
+            bridge: self.bridge,
+            value: value.to_string(),
+            usage_count: entry.usage_count,
+            class_ids: entry.class_ids.iter().copied().collect(),
+            method_ids: entry.method_ids.iter().copied().collect(),
+            field_ids: entry.field_ids.iter().copied().collect(),
+        }
+    }
+
+    /// internal use, Levenshtein distance if it is at most `max_distance`
+    fn edit_distance(target: &[char], candidate: &str, max_distance: usize) -> Option<usize> {
+        let candidate: Vec<char> = candidate.chars().collect();
+        if candidate.len().abs_diff(target.len()) > max_distance {
+            return None;
+        }
+        let mut previous: Vec<usize> = (0..=candidate.len()).collect();
+        let mut current = vec![0; candidate.len() + 1];
+        for (i, &a) in target.iter().enumerate() {
+            current[0] = i + 1;
+            for (j, &b) in candidate.iter().enumerate() {
+                let substitution = previous[j] + usize::from(a != b);
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            // every later row is at least the minimum of this one
+            if current.iter().min().is_some_and(|&min| min > max_distance) {
+                return None;
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+        let distance = previous[candidate.len()];
+        (distance <= max_distance).then_some(distance)
+    }
+}
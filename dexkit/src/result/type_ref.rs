@@ -0,0 +1,223 @@
+use crate::DexkitBridge;
+use crate::errors::Error;
+use crate::result::ClassData;
+use crate::uitls::DexSignature;
+
+/// A type referenced by the loaded dex files.
+///
+/// `Class` when the type is defined in the apk or an attached library, `External` keeps the
+/// descriptor of the other types: primitives, arrays and classes defined elsewhere,
+/// e.g. "Landroid/os/Bundle;" when no framework library is attached.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeRef<'a> {
+    Class(ClassData<'a>),
+    External(String),
+}
+
+impl<'a> TypeRef<'a> {
+    /// type descriptor, e.g. "Landroid/os/Bundle;" or "[I"
+    pub fn descriptor(&self) -> String {
+        match self {
+            TypeRef::Class(class) => class.descriptor(),
+            TypeRef::External(descriptor) => descriptor.clone(),
+        }
+    }
+
+    /// type name, e.g. "android.os.Bundle" or "int[]"
+    pub fn name(&self) -> Option<String> {
+        DexSignature::get_type_name(self.descriptor())
+    }
+
+    /// is not defined in the apk or an attached library
+    pub fn is_external(&self) -> bool {
+        matches!(self, TypeRef::External(_))
+    }
+
+    /// get the class data, None for external types
+    pub fn class_data(&self) -> Option<&ClassData<'a>> {
+        match self {
+            TypeRef::Class(class) => Some(class),
+            TypeRef::External(_) => None,
+        }
+    }
+
+    /// convert into the class data, None for external types
+    pub fn into_class_data(self) -> Option<ClassData<'a>> {
+        match self {
+            TypeRef::Class(class) => Some(class),
+            TypeRef::External(_) => None,
+        }
+    }
+
+    /// internal use, resolve a descriptor against the apk and the attached libraries
+    pub(crate) fn from_descriptor(bridge: &'a DexkitBridge, descriptor: String) -> Self {
+        let class = match descriptor.starts_with('L') {
+            true => bridge.get_class_data(&descriptor),
+            false => None,
+        };
+        match class {
+            Some(class) => TypeRef::Class(class),
+            None => TypeRef::External(descriptor),
+        }
+    }
+
+    /// internal use, split a method descriptor into its parameter and return type descriptors,
+    /// e.g. "Lcom/example/Foo;->bar(I[Ljava/lang/String;)V" into ["I", "[Ljava/lang/String;"], "V"
+    pub(crate) fn proto_descriptors(method_descriptor: &str) -> Result<(Vec<&str>, &str), Error> {
+        let invalid =
+            || Error::DexParseError(format!("invalid method descriptor: {}", method_descriptor));
+        let (_, proto) = method_descriptor.split_once("->").ok_or_else(invalid)?;
+        let (_, proto) = proto.split_once('(').ok_or_else(invalid)?;
+        let (mut params, return_type) = proto.split_once(')').ok_or_else(invalid)?;
+        let mut descriptors = Vec::new();
+        while !params.is_empty() {
+            let (descriptor, rest) = Self::split_descriptor(params).ok_or_else(invalid)?;
+            descriptors.push(descriptor);
+            params = rest;
+        }
+        match return_type == "V" || matches!(Self::split_descriptor(return_type), Some((_, ""))) {
+            true => Ok((descriptors, return_type)),
+            false => Err(invalid()),
+        }
+    }
+
+    /// internal use, the type descriptor of a field descriptor,
+    /// e.g. "Ljava/lang/String;" of "Lcom/example/Foo;->name:Ljava/lang/String;"
+    pub(crate) fn field_descriptor(field_descriptor: &str) -> Result<&str, Error> {
+        field_descriptor
+            .split_once("->")
+            .and_then(|(_, field)| field.split_once(':'))
+            .map(|(_, descriptor)| descriptor)
+            .filter(|descriptor| matches!(Self::split_descriptor(descriptor), Some((_, ""))))
+            .ok_or_else(|| {
+                Error::DexParseError(format!("invalid field descriptor: {}", field_descriptor))
+            })
+    }
+
+    /// the first type descriptor of `descriptors` and the rest, void is not a value type
+    fn split_descriptor(descriptors: &str) -> Option<(&str, &str)> {
+        let dims = descriptors.len() - descriptors.trim_start_matches('[').len();
+        let end = match descriptors[dims..].chars().next()? {
+            'L' => dims + descriptors[dims..].find(';')? + 1,
+            'Z' | 'B' | 'S' | 'C' | 'I' | 'J' | 'F' | 'D' => dims + 1,
+            _ => return None,
+        };
+        Some(descriptors.split_at(end))
+    }
+
+    /// internal use, resolve a type_ids index of a loaded dex
+    pub(crate) fn from_type_id(
+        bridge: &'a DexkitBridge,
+        dex_id: u32,
+        type_id: u32,
+    ) -> Result<Self, Error> {
+        let descriptor = bridge
+            .any_dex_file(dex_id)
+            .and_then(|dex| dex.type_descriptor(type_id))?;
+        Ok(Self::from_descriptor(bridge, descriptor))
+    }
+}
+
+impl<'a> From<ClassData<'a>> for TypeRef<'a> {
+    fn from(value: ClassData<'a>) -> Self {
+        TypeRef::Class(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{FieldData, MethodData};
+
+    #[test]
+    fn test_proto_descriptors() {
+        let (params, return_type) = TypeRef::proto_descriptors(
+            "Lcom/example/Foo;->bar(I[[JLjava/lang/String;[Lcom/example/Foo$Bar;Z)V",
+        )
+        .unwrap();
+        assert_eq!(
+            params,
+            [
+                "I",
+                "[[J",
+                "Ljava/lang/String;",
+                "[Lcom/example/Foo$Bar;",
+                "Z"
+            ]
+        );
+        assert_eq!(return_type, "V");
+
+        let (params, return_type) =
+            TypeRef::proto_descriptors("Lcom/example/Foo;-><init>()[Ljava/lang/Object;").unwrap();
+        assert!(params.is_empty());
+        assert_eq!(return_type, "[Ljava/lang/Object;");
+
+        for invalid in [
+            "Lcom/example/Foo;->bar(V)V",
+            "Lcom/example/Foo;->bar(Ljava/lang/String)V",
+            "Lcom/example/Foo;->bar(I)",
+            "Lcom/example/Foo;->bar(I)VI",
+            "Lcom/example/Foo;->bar(Q)V",
+            "Lcom/example/Foo;->bar",
+        ] {
+            assert!(
+                matches!(
+                    TypeRef::proto_descriptors(invalid),
+                    Err(Error::DexParseError(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_field_descriptor() {
+        assert_eq!(
+            TypeRef::field_descriptor("Lcom/example/Foo;->names:[Ljava/lang/String;").unwrap(),
+            "[Ljava/lang/String;"
+        );
+        assert_eq!(
+            TypeRef::field_descriptor("Lcom/example/Foo;->count:I").unwrap(),
+            "I"
+        );
+        for invalid in [
+            "Lcom/example/Foo;->count",
+            "Lcom/example/Foo;->count:V",
+            "Lcom/example/Foo;->count:II",
+        ] {
+            assert!(TypeRef::field_descriptor(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_external_types() {
+        let bridge = DexkitBridge::for_test(Vec::new());
+        let method = MethodData::for_test(&bridge, 0, 0, "Lcom/example/Foo;->bar(I[Z)[[J");
+        let return_type = method.return_type().unwrap();
+        assert!(return_type.is_external());
+        assert_eq!(return_type.descriptor(), "[[J");
+        assert_eq!(return_type.name().unwrap(), "long[][]");
+        assert!(return_type.class_data().is_none());
+
+        let params = method.param_type_refs().unwrap();
+        assert_eq!(
+            params,
+            [
+                TypeRef::External("I".to_string()),
+                TypeRef::External("[Z".to_string())
+            ]
+        );
+        assert_eq!(params[1].name().unwrap(), "boolean[]");
+
+        let broken = MethodData::for_test(&bridge, 0, 0, "Lcom/example/Foo;->bar(I");
+        assert!(broken.return_type().is_err());
+        assert!(broken.param_type_refs().is_err());
+
+        let field = FieldData::for_test(&bridge, 0, 0, "Lcom/example/Foo;->flags:[I");
+        assert_eq!(
+            field.field_type().unwrap(),
+            TypeRef::External("[I".to_string())
+        );
+    }
+}
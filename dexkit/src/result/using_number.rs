@@ -0,0 +1,63 @@
+use crate::dex::EncodedValue;
+use crate::query::enums::NumberEncodeValueType;
+use crate::query::matchers::base::NumberEncodeValueMatcher;
+use crate::query::matchers::{
+    EncodeValueDouble, EncodeValueFloat, EncodeValueInt, EncodeValueLong,
+};
+
+/// A number literal loaded by a method, see `MethodData::using_numbers`.
+///
+/// The dex format does not record the type of a literal and the numbers are decoded on the
+/// Rust side, so the type is not known: const and const/high16 are reported both as `Int`
+/// and `Float`, const-wide and const-wide/high16 both as `Long` and `Double`, the narrower
+/// forms as `Int` or `Long`. A fingerprint should match the reading it expects, see `to_matcher`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UsingNumber {
+    Int(EncodeValueInt),
+    Long(EncodeValueLong),
+    Float(EncodeValueFloat),
+    Double(EncodeValueDouble),
+}
+
+impl UsingNumber {
+    /// type of the value
+    pub fn value_type(&self) -> NumberEncodeValueType {
+        match self {
+            UsingNumber::Int(_) => NumberEncodeValueType::IntValue,
+            UsingNumber::Long(_) => NumberEncodeValueType::LongValue,
+            UsingNumber::Float(_) => NumberEncodeValueType::FloatValue,
+            UsingNumber::Double(_) => NumberEncodeValueType::DoubleValue,
+        }
+    }
+
+    /// the value converted to f64
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            UsingNumber::Int(value) => value.0 as f64,
+            UsingNumber::Long(value) => value.0 as f64,
+            UsingNumber::Float(value) => value.0 as f64,
+            UsingNumber::Double(value) => value.0,
+        }
+    }
+
+    /// matcher of the same value, for `MethodMatcher::set_using_numbers_matcher`
+    pub fn to_matcher(&self) -> NumberEncodeValueMatcher {
+        match self {
+            UsingNumber::Int(value) => NumberEncodeValueMatcher::create_int(value.0),
+            UsingNumber::Long(value) => NumberEncodeValueMatcher::create_long(value.0),
+            UsingNumber::Float(value) => NumberEncodeValueMatcher::create_float(value.0),
+            UsingNumber::Double(value) => NumberEncodeValueMatcher::create_double(value.0),
+        }
+    }
+
+    /// internal use, convert a literal decoded from the dex
+    pub(crate) fn from_encoded_value(value: EncodedValue) -> Option<Self> {
+        match value {
+            EncodedValue::Int(value) => Some(UsingNumber::Int(EncodeValueInt(value))),
+            EncodedValue::Long(value) => Some(UsingNumber::Long(EncodeValueLong(value))),
+            EncodedValue::Float(value) => Some(UsingNumber::Float(EncodeValueFloat(value))),
+            EncodedValue::Double(value) => Some(UsingNumber::Double(EncodeValueDouble(value))),
+            _ => None,
+        }
+    }
+}
@@ -105,6 +105,21 @@ impl DexSignature {
         None
     }
 
+    /// Get the package name from the type name. e.g. "com.example.MyClass" -> "com.example"
+    /// # Arguments
+    /// * `type_name` - The type name to get the package for. e.g. "com.example.MyClass[]"
+    /// # Returns
+    /// * `String` - The package name, empty for the default package and primitive types
+    pub fn get_package_name<T>(type_name: T) -> String
+    where
+        T: AsRef<str>,
+    {
+        let name = type_name.as_ref().trim_end_matches("[]");
+        name.rsplit_once('.')
+            .map(|(package, _)| package.to_string())
+            .unwrap_or_default()
+    }
+
     /// Get the parameter type names from the method signature. e.g. "(ILjava/lang/String;)V" -> ["int", "java.lang.String"]
     /// # Arguments
     /// * `method_signature` - The method signature to get the parameter type names for. e.g. "(ILjava/lang/String;)V"
@@ -0,0 +1,457 @@
+use crate::errors::Error;
+use crate::uitls::DexSignature;
+use std::fmt::{Display, Formatter};
+
+/// A generic type of a `dalvik.annotation.Signature`, see JVMS 4.7.9.1.
+/// `Display` renders it like `java.lang.reflect.Type::getTypeName`,
+/// e.g. "java.util.List<? extends java.lang.Number>".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GenericType {
+    /// primitive descriptor, e.g. 'I', 'V' for void return types
+    Primitive(char),
+    Class(ClassType),
+    /// type variable name, e.g. "T"
+    TypeVariable(String),
+    Array(Box<GenericType>),
+}
+
+/// A class type with its type arguments, e.g. `java.util.Map<K, V>$Entry<K, V>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassType {
+    /// binary name, e.g. "java.util.Map$Entry"
+    pub name: String,
+    pub arguments: Vec<TypeArgument>,
+    /// enclosing class of an inner class type, when the signature spells it out
+    pub owner: Option<Box<ClassType>>,
+}
+
+/// A type argument of a parameterized type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeArgument {
+    /// `?`
+    Any,
+    Exact(GenericType),
+    /// `? extends T`
+    Extends(GenericType),
+    /// `? super T`
+    Super(GenericType),
+}
+
+/// A type parameter declaration, e.g. `T extends java.lang.Comparable<T>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeParameter {
+    pub name: String,
+    /// class bound first, then the interface bounds, empty for an implicit `Object` bound
+    pub bounds: Vec<GenericType>,
+}
+
+/// Generic signature of a class.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub super_class: GenericType,
+    pub interfaces: Vec<GenericType>,
+}
+
+/// Generic signature of a method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<GenericType>,
+    pub return_type: GenericType,
+    pub throws: Vec<GenericType>,
+}
+
+impl ClassType {
+    /// erased descriptor, e.g. "Ljava/util/Map$Entry;"
+    pub fn descriptor(&self) -> String {
+        format!("L{};", self.name.replace('.', "/"))
+    }
+}
+
+impl GenericType {
+    /// Parse a field signature, e.g. "Ljava/util/List<Ljava/lang/String;>;"
+    pub fn parse(signature: &str) -> Result<Self, Error> {
+        let mut parser = SignatureParser::new(signature);
+        let field_type = parser.java_type()?;
+        parser.finish()?;
+        Ok(field_type)
+    }
+
+    /// erased descriptor, None for type variables and arrays of type variables,
+    /// their erasure depends on the declared bounds
+    pub fn descriptor(&self) -> Option<String> {
+        match self {
+            GenericType::Primitive(c) => Some(c.to_string()),
+            GenericType::Class(class) => Some(class.descriptor()),
+            GenericType::TypeVariable(_) => None,
+            GenericType::Array(component) => component.descriptor().map(|d| format!("[{}", d)),
+        }
+    }
+}
+
+impl ClassSignature {
+    /// Parse a class signature, e.g. "<T:Ljava/lang/Object;>Ljava/lang/Object;"
+    pub fn parse(signature: &str) -> Result<Self, Error> {
+        let mut parser = SignatureParser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type()?;
+        let mut interfaces = Vec::new();
+        while !parser.at_end() {
+            interfaces.push(parser.class_type()?);
+        }
+        Ok(Self {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+}
+
+impl MethodSignature {
+    /// Parse a method signature, e.g. "<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;"
+    pub fn parse(signature: &str) -> Result<Self, Error> {
+        let mut parser = SignatureParser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect('(')?;
+        let mut parameters = Vec::new();
+        while parser.peek() != Some(')') {
+            parameters.push(parser.java_type()?);
+        }
+        parser.expect(')')?;
+        let return_type = parser.java_type()?;
+        let mut throws = Vec::new();
+        while parser.peek() == Some('^') {
+            parser.next()?;
+            throws.push(parser.reference_type()?);
+        }
+        parser.finish()?;
+        Ok(Self {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        })
+    }
+}
+
+/// internal use, recursive descent parser of the signature grammar
+struct SignatureParser<'s> {
+    signature: &'s str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'s> SignatureParser<'s> {
+    fn new(signature: &'s str) -> Self {
+        Self {
+            signature,
+            chars: signature.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::DexParseError(format!(
+            "invalid generic signature {:?} at {}: {}",
+            self.signature, self.pos, message
+        ))
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<char, Error> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.next()? {
+            c if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn finish(&self) -> Result<(), Error> {
+        match self.at_end() {
+            true => Ok(()),
+            false => Err(self.error("trailing characters")),
+        }
+    }
+
+    /// identifier up to one of the `terminators`
+    fn identifier(&mut self, terminators: &[char]) -> Result<String, Error> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !terminators.contains(&c)) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("empty identifier"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, Error> {
+        let mut type_parameters = Vec::new();
+        if self.peek() != Some('<') {
+            return Ok(type_parameters);
+        }
+        self.next()?;
+        while self.peek() != Some('>') {
+            let name = self.identifier(&[':', '>'])?;
+            let mut bounds = Vec::new();
+            // the class bound may be empty, e.g. "T::Ljava/lang/Comparable;"
+            self.expect(':')?;
+            if !matches!(self.peek(), Some(':') | Some('>')) {
+                bounds.push(self.reference_type()?);
+            }
+            while self.peek() == Some(':') {
+                self.next()?;
+                bounds.push(self.reference_type()?);
+            }
+            type_parameters.push(TypeParameter { name, bounds });
+        }
+        self.expect('>')?;
+        if type_parameters.is_empty() {
+            return Err(self.error("empty type parameters"));
+        }
+        Ok(type_parameters)
+    }
+
+    /// JavaTypeSignature, or 'V' for return types
+    fn java_type(&mut self) -> Result<GenericType, Error> {
+        match self.peek() {
+            Some(c @ ('V' | 'Z' | 'B' | 'S' | 'C' | 'I' | 'J' | 'F' | 'D')) => {
+                self.next()?;
+                Ok(GenericType::Primitive(c))
+            }
+            _ => self.reference_type(),
+        }
+    }
+
+    fn reference_type(&mut self) -> Result<GenericType, Error> {
+        match self.peek() {
+            Some('L') => self.class_type(),
+            Some('T') => {
+                self.next()?;
+                let name = self.identifier(&[';'])?;
+                self.expect(';')?;
+                Ok(GenericType::TypeVariable(name))
+            }
+            Some('[') => {
+                self.next()?;
+                let component = self.java_type()?;
+                if component == GenericType::Primitive('V') {
+                    return Err(self.error("array of void"));
+                }
+                Ok(GenericType::Array(Box::new(component)))
+            }
+            _ => Err(self.error("expected a reference type")),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<GenericType, Error> {
+        self.expect('L')?;
+        let name = self.identifier(&['<', '.', ';'])?.replace('/', ".");
+        let mut class = ClassType {
+            name,
+            arguments: self.type_arguments()?,
+            owner: None,
+        };
+        while self.peek() == Some('.') {
+            self.next()?;
+            let simple_name = self.identifier(&['<', '.', ';'])?;
+            let owner = class;
+            class = ClassType {
+                name: format!("{}${}", owner.name, simple_name),
+                arguments: self.type_arguments()?,
+                owner: Some(Box::new(owner)),
+            };
+        }
+        self.expect(';')?;
+        Ok(GenericType::Class(class))
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>, Error> {
+        let mut arguments = Vec::new();
+        if self.peek() != Some('<') {
+            return Ok(arguments);
+        }
+        self.next()?;
+        while self.peek() != Some('>') {
+            let argument = match self.peek() {
+                Some('*') => {
+                    self.next()?;
+                    TypeArgument::Any
+                }
+                Some('+') => {
+                    self.next()?;
+                    TypeArgument::Extends(self.reference_type()?)
+                }
+                Some('-') => {
+                    self.next()?;
+                    TypeArgument::Super(self.reference_type()?)
+                }
+                _ => TypeArgument::Exact(self.reference_type()?),
+            };
+            arguments.push(argument);
+        }
+        self.expect('>')?;
+        if arguments.is_empty() {
+            return Err(self.error("empty type arguments"));
+        }
+        Ok(arguments)
+    }
+}
+
+/// "A, B" list of displayable items
+fn join<T: Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for GenericType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenericType::Primitive(c) => {
+                let name = DexSignature::primitive_type_signature(c.to_string());
+                write!(f, "{}", name.unwrap_or_else(|| c.to_string()))
+            }
+            GenericType::Class(class) => write!(f, "{}", class),
+            GenericType::TypeVariable(name) => write!(f, "{}", name),
+            GenericType::Array(component) => write!(f, "{}[]", component),
+        }
+    }
+}
+
+impl Display for ClassType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.owner {
+            Some(owner) => {
+                let simple_name = &self.name[owner.name.len() + 1..];
+                write!(f, "{}${}", owner, simple_name)?;
+            }
+            None => write!(f, "{}", self.name)?,
+        }
+        if !self.arguments.is_empty() {
+            write!(f, "<{}>", join(&self.arguments))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TypeArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeArgument::Any => write!(f, "?"),
+            TypeArgument::Exact(bound) => write!(f, "{}", bound),
+            TypeArgument::Extends(bound) => write!(f, "? extends {}", bound),
+            TypeArgument::Super(bound) => write!(f, "? super {}", bound),
+        }
+    }
+}
+
+impl Display for TypeParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.bounds.is_empty() {
+            let bounds: Vec<String> = self.bounds.iter().map(|b| b.to_string()).collect();
+            write!(f, " extends {}", bounds.join(" & "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameterized_field() {
+        let list = GenericType::parse("Ljava/util/List<Ljava/lang/String;>;").unwrap();
+        assert_eq!(list.to_string(), "java.util.List<java.lang.String>");
+        assert_eq!(list.descriptor().unwrap(), "Ljava/util/List;");
+        let other = GenericType::parse("Ljava/util/List<Ljava/lang/Integer;>;").unwrap();
+        assert_ne!(list, other);
+    }
+
+    #[test]
+    fn test_wildcards_and_arrays() {
+        let map = GenericType::parse("Ljava/util/Map<*+Ljava/lang/Number;-[TT;>;").unwrap();
+        assert_eq!(
+            map.to_string(),
+            "java.util.Map<?, ? extends java.lang.Number, ? super T[]>"
+        );
+        let GenericType::Class(class) = &map else {
+            panic!("expected a class type");
+        };
+        assert_eq!(class.arguments[0], TypeArgument::Any);
+        assert_eq!(GenericType::parse("[[I").unwrap().to_string(), "int[][]");
+        assert_eq!(GenericType::parse("[TT;").unwrap().descriptor(), None);
+    }
+
+    #[test]
+    fn test_inner_class() {
+        let entry = GenericType::parse("Ljava/util/Map<TK;TV;>.Entry<TK;TV;>;").unwrap();
+        assert_eq!(entry.to_string(), "java.util.Map<K, V>$Entry<K, V>");
+        assert_eq!(entry.descriptor().unwrap(), "Ljava/util/Map$Entry;");
+    }
+
+    #[test]
+    fn test_class_signature() {
+        let signature = ClassSignature::parse(
+            "<K:Ljava/lang/Object;V::Ljava/lang/Comparable<TV;>;>Ljava/util/AbstractMap<TK;TV;>;Ljava/io/Serializable;",
+        )
+        .unwrap();
+        assert_eq!(signature.type_parameters.len(), 2);
+        assert_eq!(
+            signature.type_parameters[1].to_string(),
+            "V extends java.lang.Comparable<V>"
+        );
+        assert_eq!(
+            signature.super_class.to_string(),
+            "java.util.AbstractMap<K, V>"
+        );
+        assert_eq!(signature.interfaces[0].to_string(), "java.io.Serializable");
+    }
+
+    #[test]
+    fn test_method_signature() {
+        let signature = MethodSignature::parse(
+            "<T:Ljava/lang/Object;>(Ljava/util/List<TT;>;I)TT;^Ljava/io/IOException;^TE;",
+        )
+        .unwrap();
+        assert_eq!(signature.type_parameters[0].name, "T");
+        assert_eq!(
+            signature
+                .parameters
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>(),
+            ["java.util.List<T>", "int"]
+        );
+        assert_eq!(signature.return_type, GenericType::TypeVariable("T".into()));
+        assert_eq!(signature.throws.len(), 2);
+        let void = MethodSignature::parse("()V").unwrap();
+        assert_eq!(void.return_type.to_string(), "void");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(GenericType::parse("Ljava/util/List<>;").is_err());
+        assert!(GenericType::parse("Ljava/util/List;X").is_err());
+        assert!(GenericType::parse("Ljava/util/List").is_err());
+        assert!(MethodSignature::parse("(I").is_err());
+        assert!(GenericType::parse("[V").is_err());
+    }
+}
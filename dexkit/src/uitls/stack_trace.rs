@@ -0,0 +1,128 @@
+use std::fmt::{Display, Formatter};
+
+/// A frame of a Java / Android stack trace, e.g. "at a.b.c(SourceFile:123)".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// class name, e.g. "com.example.MainActivity$1"
+    pub class_name: String,
+    /// method name, e.g. "onClick" or "<init>"
+    pub method_name: String,
+    /// source file, None for "Unknown Source" and native methods
+    pub source_file: Option<String>,
+    pub line_number: Option<u32>,
+    pub is_native: bool,
+}
+
+impl StackFrame {
+    /// Parse a stack trace line, None if the line is not a frame.
+    /// Leading logcat columns and module prefixes such as "java.base/" are skipped.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let frame = match line.strip_prefix("at ") {
+            Some(frame) => frame,
+            None => {
+                let start = line.rfind("\tat ").or_else(|| line.rfind(" at "))?;
+                &line[start + 4..]
+            }
+        };
+        let open = frame.find('(')?;
+        let close = frame.rfind(')').filter(|&close| close > open)?;
+        let qualified = frame[..open].trim();
+        let qualified = qualified.rsplit('/').next().unwrap_or(qualified);
+        let (class_name, method_name) = qualified.rsplit_once('.')?;
+        if class_name.is_empty() || method_name.is_empty() || class_name.contains(' ') {
+            return None;
+        }
+
+        let location = &frame[open + 1..close];
+        let (file, line_number) = match location.rsplit_once(':') {
+            Some((file, line)) if line.parse::<u32>().is_ok() => (file, line.parse().ok()),
+            _ => (location, None),
+        };
+        let is_native = file == "Native Method";
+        let source_file = match file {
+            "" | "Unknown Source" | "Native Method" => None,
+            file => Some(file.to_string()),
+        };
+        Some(StackFrame {
+            class_name: class_name.to_string(),
+            method_name: method_name.to_string(),
+            source_file,
+            line_number,
+            is_native,
+        })
+    }
+
+    /// Parse every frame of a stack trace, the other lines are skipped,
+    /// e.g. the exception message and "Caused by:" lines.
+    pub fn parse_all(trace: &str) -> Vec<Self> {
+        trace.lines().filter_map(Self::parse).collect()
+    }
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at {}.{}(", self.class_name, self.method_name)?;
+        match (&self.source_file, self.line_number) {
+            _ if self.is_native => write!(f, "Native Method")?,
+            (Some(file), Some(line)) => write!(f, "{}:{}", file, line)?,
+            (Some(file), None) => write!(f, "{}", file)?,
+            (None, Some(line)) => write!(f, "Unknown Source:{}", line)?,
+            (None, None) => write!(f, "Unknown Source")?,
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame() {
+        let frame = StackFrame::parse("\tat a.b.c(SourceFile:123)").unwrap();
+        assert_eq!(frame.class_name, "a.b");
+        assert_eq!(frame.method_name, "c");
+        assert_eq!(frame.source_file.as_deref(), Some("SourceFile"));
+        assert_eq!(frame.line_number, Some(123));
+        assert!(!frame.is_native);
+        assert_eq!(frame.to_string(), "at a.b.c(SourceFile:123)");
+
+        let frame = StackFrame::parse("at com.example.Foo$1.<init>(Unknown Source:4)").unwrap();
+        assert_eq!(frame.class_name, "com.example.Foo$1");
+        assert_eq!(frame.method_name, "<init>");
+        assert_eq!(frame.source_file, None);
+        assert_eq!(frame.line_number, Some(4));
+
+        let frame =
+            StackFrame::parse("at java.base/java.lang.Thread.run(Thread.java:833)").unwrap();
+        assert_eq!(frame.class_name, "java.lang.Thread");
+
+        let frame =
+            StackFrame::parse("at android.os.MessageQueue.nativePollOnce(Native Method)").unwrap();
+        assert!(frame.is_native);
+        assert_eq!(frame.line_number, None);
+        assert_eq!(
+            frame.to_string(),
+            "at android.os.MessageQueue.nativePollOnce(Native Method)"
+        );
+    }
+
+    #[test]
+    fn test_parse_trace() {
+        let trace = "10-19 12:00:00.000  1234  1234 E AndroidRuntime: FATAL EXCEPTION: main\n\
+            10-19 12:00:00.000  1234  1234 E AndroidRuntime: java.lang.NullPointerException: at index 1\n\
+            10-19 12:00:00.000  1234  1234 E AndroidRuntime: \tat a.b.c(SourceFile:1)\n\
+            10-19 12:00:00.000  1234  1234 E AndroidRuntime: Caused by: java.lang.IllegalStateException\n\
+            10-19 12:00:00.000  1234  1234 E AndroidRuntime: \tat a.d.e(SourceFile)\n\
+            10-19 12:00:00.000  1234  1234 E AndroidRuntime: \t... 12 more";
+        let frames = StackFrame::parse_all(trace);
+        let names: Vec<String> = frames
+            .iter()
+            .map(|f| format!("{}.{}", f.class_name, f.method_name))
+            .collect();
+        assert_eq!(names, vec!["a.b.c", "a.d.e"]);
+        assert_eq!(frames[1].source_file.as_deref(), Some("SourceFile"));
+        assert_eq!(frames[1].line_number, None);
+    }
+}
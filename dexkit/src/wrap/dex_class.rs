@@ -55,6 +55,11 @@ impl DexClass {
             .unwrap_or_default()
     }
 
+    /// Get the package name. e.g. "com.example"
+    pub fn package_name(&self) -> String {
+        DexSignature::get_package_name(&self.type_name)
+    }
+
     /// Check if the class is an array type. e.g. "com.example.MyClass[]" -> true
     pub fn is_array(&self) -> bool {
         self.type_name.ends_with("[]")
@@ -0,0 +1,51 @@
+use dexkit::result::{ClassData, FromDexAnnotation};
+
+/// `@kotlin.Metadata`, put on every class by the Kotlin compiler
+#[derive(Debug, FromDexAnnotation)]
+#[dex(annotation = "kotlin.Metadata")]
+pub struct KotlinMetadata {
+    /// 1 class, 2 file facade, 3 synthetic class, 4 multi-file facade, 5 multi-file part
+    #[dex(name = "k", default)]
+    pub kind: i32,
+    #[dex(name = "mv", default)]
+    pub metadata_version: Vec<i32>,
+    #[dex(name = "pn")]
+    pub package_name: Option<String>,
+}
+
+/// `@java.lang.annotation.Retention` of an annotation class
+#[derive(Debug, FromDexAnnotation)]
+#[dex(annotation = "java.lang.annotation.Retention")]
+pub struct Retention {
+    pub value: RetentionPolicy,
+}
+
+/// read from the `java.lang.annotation.RetentionPolicy` constants, e.g. `RUNTIME`
+#[derive(Debug, FromDexAnnotation)]
+pub enum RetentionPolicy {
+    Source,
+    Class,
+    Runtime,
+}
+
+/// `@dalvik.annotation.EnclosingClass` of a member class
+#[derive(Debug, FromDexAnnotation)]
+#[dex(annotation = "dalvik.annotation.EnclosingClass")]
+pub struct EnclosingClass<'a> {
+    pub value: ClassData<'a>,
+}
+
+pub fn print_annotations(class: &ClassData) {
+    let annotations = class.annotations();
+    match KotlinMetadata::find(&annotations) {
+        Some(Ok(metadata)) => println!("[Rust] Kotlin metadata: {:?}", metadata),
+        Some(Err(e)) => println!("[Rust] Invalid Kotlin metadata: {}", e),
+        None => println!("[Rust] Not a Kotlin class"),
+    }
+    if let Some(Ok(retention)) = Retention::find(&annotations) {
+        println!("[Rust] Retention: {:?}", retention.value);
+    }
+    if let Some(Ok(enclosing)) = EnclosingClass::find(&annotations) {
+        println!("[Rust] Enclosing class: {}", enclosing.value.descriptor());
+    }
+}
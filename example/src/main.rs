@@ -12,6 +12,9 @@ use dexkit::{
 };
 use std::path::Path;
 
+mod annotations;
+mod targets;
+
 fn main() -> Result<(), Error> {
     let cargo_manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
     let cargo_manifest_dir = Path::new(&cargo_manifest_dir);
@@ -27,6 +30,7 @@ fn main() -> Result<(), Error> {
     let create_time = std::time::Instant::now();
     let bridge = DexkitBridge::create_apk_path(apk_path.to_str().unwrap())?;
     println!("[Rust] Create Bridge time: {:?}", create_time.elapsed());
+    targets::print_targets(&bridge);
     do_search(bridge);
     println!("[Rust] Find Use time: {:?}", create_time.elapsed());
     Ok(())
@@ -92,6 +96,7 @@ fn do_search(bridge: DexkitBridge) {
 
     let first = class_data_list.first().unwrap();
     // println!("[Rust] First class: {:#?}", first);
+    annotations::print_annotations(first);
 
     let supper_class = first.supper_class();
     // println!("[Rust] Found supper class: {:#?}", supper_class);
@@ -0,0 +1,36 @@
+use dexkit::DexkitBridge;
+use dexkit::query::DexTargets;
+use dexkit::result::{ClassData, FieldData, MethodData};
+
+/// Hook targets of the demo app, resolved with one query per field
+#[derive(DexTargets)]
+pub struct MainTargets<'a> {
+    #[class(name = "io.github.cargo.ndk.plugin.MainActivity")]
+    pub main_activity: ClassData<'a>,
+    #[method(
+        name = "onCreate",
+        params = "android.os.Bundle",
+        returns = "void",
+        in_class = "self.main_activity"
+    )]
+    pub on_create: MethodData<'a>,
+    #[method(name = "onDestroy", in_class = "self.main_activity")]
+    pub on_destroy: Option<MethodData<'a>>,
+    #[field(type = "self.main_activity")]
+    pub instances: Vec<FieldData<'a>>,
+}
+
+pub fn print_targets(bridge: &DexkitBridge) {
+    match MainTargets::resolve(bridge) {
+        Ok(targets) => {
+            println!(
+                "[Rust] Resolved {} -> {}, onDestroy: {}, {} instance fields",
+                targets.main_activity.descriptor(),
+                targets.on_create.descriptor(),
+                targets.on_destroy.is_some(),
+                targets.instances.len()
+            );
+        }
+        Err(e) => println!("[Rust] Unresolved targets: {}", e),
+    }
+}